@@ -5,13 +5,15 @@
 use magnus::{function, method, prelude::*, Error, Ruby};
 use methodray_core::{
     analyzer::AstInstaller,
+    config::Config,
     env::{GlobalEnv, LocalEnv},
     parser, rbs,
 };
+use std::path::Path;
 
 #[magnus::wrap(class = "MethodRay::Analyzer")]
 pub struct Analyzer {
-    #[allow(dead_code)]
+    /// Project directory, where the configuration is discovered
     path: String,
 }
 
@@ -36,9 +38,10 @@ impl Analyzer {
         // Build graph
         let mut genv = GlobalEnv::new();
 
-        // Register built-in methods from RBS
+        // Register built-in methods from RBS (and the configured signatures)
         let ruby = unsafe { Ruby::get_unchecked() };
-        rbs::register_rbs_methods(&mut genv, &ruby)?;
+        let config = load_config(&self.path)?;
+        rbs::register_rbs_methods(&mut genv, &ruby, &config.signature_paths)?;
 
         let mut lenv = LocalEnv::new();
         let mut installer = AstInstaller::new(&mut genv, &mut lenv, &source);
@@ -67,17 +70,41 @@ impl Analyzer {
 }
 
 /// Setup function that only generates RBS cache
-/// This is used during gem build to pre-generate the cache
+/// This is used during gem build to pre-generate the cache, and in a
+/// project to build the cache with its configured signature paths
 fn setup() -> Result<String, Error> {
     let ruby = unsafe { Ruby::get_unchecked() };
     let mut genv = GlobalEnv::new();
 
     // This will load RBS and save to cache if not already cached
-    let count = rbs::register_rbs_methods(&mut genv, &ruby)?;
+    let config = load_config(".")?;
+    let count = rbs::register_rbs_methods(&mut genv, &ruby, &config.signature_paths)?;
 
     Ok(format!("RBS cache generated with {} methods", count))
 }
 
+/// Discover the project configuration of a directory
+fn load_config(dir: &str) -> Result<Config, Error> {
+    Config::discover(Path::new(dir)).map_err(|e| {
+        let ruby = unsafe { Ruby::get_unchecked() };
+        Error::new(ruby.exception_runtime_error(), format!("{:#}", e))
+    })
+}
+
+/// Resolved project configuration of a directory, as JSON
+///
+/// Shares the loader of the CLI and the LSP server, so the gem sees the
+/// same settings.
+fn config_json(dir: String) -> Result<String, Error> {
+    let config = load_config(&dir)?;
+    let mut json = serde_json::to_value(&config).map_err(|e| {
+        let ruby = unsafe { Ruby::get_unchecked() };
+        Error::new(ruby.exception_runtime_error(), e.to_string())
+    })?;
+    json["root"] = serde_json::Value::String(config.root.display().to_string());
+    Ok(json.to_string())
+}
+
 #[magnus::init]
 fn init(ruby: &Ruby) -> Result<(), Error> {
     let module = ruby.define_module("MethodRay")?;
@@ -89,6 +116,7 @@ fn init(ruby: &Ruby) -> Result<(), Error> {
 
     // Module-level setup function for cache generation
     module.define_singleton_method("setup", function!(setup, 0))?;
+    module.define_singleton_method("config_json", function!(config_json, 1))?;
 
     Ok(())
 }
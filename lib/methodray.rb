@@ -1,9 +1,16 @@
 # frozen_string_literal: true
 
+require 'json'
 require 'rbs'
 require_relative 'methodray/version'
 require_relative 'methodray/methodray'
 
 module MethodRay
   class Error < StandardError; end
+
+  # Project configuration (.methodray.yml / methodray.toml) discovered
+  # from dir upward, resolved by the same loader as the CLI
+  def self.config(dir = Dir.pwd)
+    JSON.parse(config_json(dir))
+  end
 end
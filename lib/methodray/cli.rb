@@ -18,6 +18,10 @@ module MethodRay
         Commands.watch(args)
       when 'clear-cache'
         Commands.clear_cache(args)
+      when 'rules'
+        Commands.rules(args)
+      when 'explain'
+        Commands.explain(args)
       else
         puts "Unknown command: #{command}"
         Commands.help
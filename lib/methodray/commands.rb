@@ -15,9 +15,14 @@ module MethodRay
             methodray check [FILE] [OPTIONS]  # Type check a Ruby file
             methodray watch FILE              # Watch file for changes and auto-check
             methodray clear-cache             # Clear RBS method cache
+            methodray rules                   # List diagnostic rules and their codes
+            methodray explain CODE            # Explain a diagnostic rule (e.g., E001)
 
           Examples:
             methodray check app/models/user.rb
+            methodray check app/models/user.rb --format json
+            methodray check app/models/user.rb --context 2 --color never
+            methodray check --update-baseline
             methodray watch app/models/user.rb
         HELP
       end
@@ -38,6 +43,14 @@ module MethodRay
         exec_rust_cli('clear-cache', args)
       end
 
+      def rules(args)
+        exec_rust_cli('rules', args)
+      end
+
+      def explain(args)
+        exec_rust_cli('explain', args)
+      end
+
       private
 
       def exec_rust_cli(command, args)
@@ -3,10 +3,14 @@
 //! This module is responsible for:
 //! - Processing BlockNode (e.g., `{ |x| x.to_s }` or `do |x| x.to_s end`)
 //! - Registering block parameters as local variables
+//! - Numbered (`_1`, `_2`) and `it` parameters
 //! - Managing block scope
+//! - Creating Proc values for blocks, lambdas and procs
 
 use crate::env::{GlobalEnv, LocalEnv, ScopeKind};
-use crate::graph::VertexId;
+use crate::graph::{ChangeSet, VertexId};
+use crate::types::Type;
+use ruby_prism::Node;
 
 use super::parameters::install_required_parameter;
 
@@ -38,6 +42,60 @@ pub fn install_block_parameter(genv: &mut GlobalEnv, lenv: &mut LocalEnv, name:
     install_required_parameter(genv, lenv, name)
 }
 
+/// Install numbered block parameters `_1` through `_<maximum>`
+///
+/// # Example
+/// ```ruby
+/// pairs.each { puts _1 + _2 }  # maximum is 2
+/// ```
+pub fn install_numbered_parameters(
+    genv: &mut GlobalEnv,
+    lenv: &mut LocalEnv,
+    maximum: u8,
+) -> Vec<VertexId> {
+    (1..=maximum)
+        .map(|n| install_block_parameter(genv, lenv, format!("_{}", n)))
+        .collect()
+}
+
+/// Install a Proc value for a block, lambda or proc
+///
+/// The block body's last expression flows into the Proc's return vertex
+/// `ret_vtx` (which also receives `next` values), so `blk.call(x)` and
+/// `yield x` return the block's result type.
+pub fn install_proc(
+    genv: &mut GlobalEnv,
+    changes: &mut ChangeSet,
+    param_vtxs: Vec<VertexId>,
+    ret_vtx: VertexId,
+    body_vtx: Option<VertexId>,
+) -> VertexId {
+    if let Some(body_vtx) = body_vtx {
+        changes.add_edge(body_vtx, ret_vtx);
+    }
+    genv.new_source(Type::proc_of(param_vtxs, ret_vtx))
+}
+
+/// Check if a call creates a Proc from its block
+///
+/// # Example
+/// ```ruby
+/// lambda { |x| x }
+/// proc { |x| x }
+/// Proc.new { |x| x }
+/// ```
+pub fn is_proc_constructor(receiver: Option<&Node>, method_name: &str) -> bool {
+    match receiver {
+        None => matches!(method_name, "lambda" | "proc"),
+        Some(receiver) => {
+            method_name == "new"
+                && receiver
+                    .as_constant_read_node()
+                    .is_some_and(|constant| constant.name().as_slice() == b"Proc")
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -75,6 +133,18 @@ mod tests {
         exit_block_scope(&mut genv);
     }
 
+    #[test]
+    fn test_install_numbered_parameters() {
+        let mut genv = GlobalEnv::new();
+        let mut lenv = LocalEnv::new();
+
+        let vtxs = install_numbered_parameters(&mut genv, &mut lenv, 2);
+
+        assert_eq!(vtxs.len(), 2);
+        assert_eq!(lenv.get_var("_1"), Some(vtxs[0]));
+        assert_eq!(lenv.get_var("_2"), Some(vtxs[1]));
+    }
+
     #[test]
     fn test_block_inherits_parent_scope_vars() {
         let mut genv = GlobalEnv::new();
@@ -91,4 +161,29 @@ mod tests {
 
         exit_block_scope(&mut genv);
     }
+
+    #[test]
+    fn test_install_proc() {
+        let mut genv = GlobalEnv::new();
+        let mut changes = ChangeSet::new();
+
+        let param_vtx = genv.new_vertex();
+        let ret_vtx = genv.new_vertex();
+        let body_vtx = genv.new_source(Type::string());
+        let proc_vtx = install_proc(
+            &mut genv,
+            &mut changes,
+            vec![param_vtx],
+            ret_vtx,
+            Some(body_vtx),
+        );
+        genv.apply_changes(changes);
+
+        let Type::Proc { params, ret } = genv.get_source(proc_vtx).unwrap().ty.clone() else {
+            panic!("expected Proc type");
+        };
+        assert_eq!(params, vec![param_vtx]);
+        assert_eq!(ret, ret_vtx);
+        assert_eq!(genv.get_vertex(ret).unwrap().show(), "String");
+    }
 }
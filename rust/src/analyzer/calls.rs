@@ -2,31 +2,155 @@
 //!
 //! This module is responsible for:
 //! - Creating MethodCallBox for method invocations (x.upcase)
+//! - Operator and index calls (a + b, -x, arr[0], arr[0] = v), which are
+//!   ordinary method calls with arguments
+//! - Receiver-less calls on implicit self (each_item { ... })
+//! - `super` resolved against the ancestors of the enclosing class
+//! - Passing blocks to the called method
 //! - Managing return value vertices
 //! - Attaching source location for error reporting
 
+use crate::env::method_registry::SuperMethod;
 use crate::env::GlobalEnv;
-use crate::graph::{MethodCallBox, VertexId};
+use crate::graph::{substitute_type_variables, MethodCallBox, VertexId};
 use crate::source_map::SourceLocation;
+use crate::types::Type;
+
+use super::variables::install_self;
 
 /// Install method call and return the return value's VertexId
 pub fn install_method_call(
     genv: &mut GlobalEnv,
     recv_vtx: VertexId,
     method_name: String,
+    arg_vtxs: Vec<VertexId>,
+    block_vtx: Option<VertexId>,
     location: Option<SourceLocation>,
+) -> VertexId {
+    install_method_call_with_receiver_location(
+        genv,
+        recv_vtx,
+        method_name,
+        arg_vtxs,
+        block_vtx,
+        location,
+        None,
+    )
+}
+
+/// Install method call whose receiver expression is in the source
+///
+/// An undefined method is reported with a label on the receiver.
+pub fn install_method_call_with_receiver_location(
+    genv: &mut GlobalEnv,
+    recv_vtx: VertexId,
+    method_name: String,
+    arg_vtxs: Vec<VertexId>,
+    block_vtx: Option<VertexId>,
+    location: Option<SourceLocation>,
+    receiver_location: Option<SourceLocation>,
 ) -> VertexId {
     // Create Vertex for return value
     let ret_vtx = genv.new_vertex();
 
-    // Create MethodCallBox with location
+    // Create MethodCallBox with arguments, block and location
     let box_id = genv.alloc_box_id();
-    let call_box = MethodCallBox::new(box_id, recv_vtx, method_name, ret_vtx, location);
+    let call_box = MethodCallBox::new(
+        box_id,
+        recv_vtx,
+        method_name,
+        arg_vtxs,
+        block_vtx,
+        ret_vtx,
+        location,
+    )
+    .with_receiver_location(receiver_location);
     genv.register_box(box_id, Box::new(call_box));
 
     ret_vtx
 }
 
+/// Install receiver-less method call on implicit self and return the return value's VertexId
+///
+/// Only methods known to the registry are checked; unknown receiver-less
+/// calls (Kernel methods, DSLs, private helpers) are not reported.
+pub fn install_implicit_self_call(
+    genv: &mut GlobalEnv,
+    method_name: String,
+    arg_vtxs: Vec<VertexId>,
+    block_vtx: Option<VertexId>,
+    location: Option<SourceLocation>,
+) -> VertexId {
+    let recv_vtx = install_self(genv);
+    let ret_vtx = genv.new_vertex();
+
+    let box_id = genv.alloc_box_id();
+    let call_box = MethodCallBox::new(
+        box_id,
+        recv_vtx,
+        method_name,
+        arg_vtxs,
+        block_vtx,
+        ret_vtx,
+        location,
+    )
+    .with_implicit_self();
+    genv.register_box(box_id, Box::new(call_box));
+
+    ret_vtx
+}
+
+/// Install `super` and return the return value's VertexId
+///
+/// The overridden method is resolved when the call is installed. Arguments
+/// flow into its positional parameters, the block (the enclosing method's
+/// block when none is given) into its block vertex, and its return values
+/// into the result. A `super` no ancestor answers is recorded as an error.
+pub fn install_super_call(
+    genv: &mut GlobalEnv,
+    arg_vtxs: Vec<VertexId>,
+    block_vtx: Option<VertexId>,
+    location: Option<SourceLocation>,
+) -> VertexId {
+    let ret_vtx = genv.new_vertex();
+    let Some((method_name, super_method)) = genv.resolve_current_super_method() else {
+        return ret_vtx;
+    };
+
+    match super_method {
+        SuperMethod::Found(info) => {
+            for (arg_vtx, param_vtx) in arg_vtxs.iter().zip(&info.param_vertices) {
+                genv.add_edge(*arg_vtx, *param_vtx);
+            }
+
+            let block_vtx = block_vtx.or_else(|| genv.current_method_block_vertex());
+            if let Some(block_vtx) = block_vtx {
+                if let Some(method_block_vtx) = info.block_vertex {
+                    genv.add_edge(block_vtx, method_block_vtx);
+                }
+            }
+
+            if info.return_type == Type::Bot {
+                if let Some(method_return_vtx) = info.return_vertex {
+                    genv.add_edge(method_return_vtx, ret_vtx);
+                }
+            } else if let Some(return_type) =
+                substitute_type_variables(&info.return_type, &genv.current_self_type())
+            {
+                let ret_src = genv.new_source(return_type);
+                genv.add_edge(ret_src, ret_vtx);
+            }
+        }
+        SuperMethod::Missing => {
+            let self_type = genv.current_self_type();
+            genv.record_missing_super_method(self_type, method_name, location);
+        }
+        SuperMethod::Unknown => {}
+    }
+
+    ret_vtx
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -37,7 +161,14 @@ mod tests {
         let mut genv = GlobalEnv::new();
 
         let recv_vtx = genv.new_source(Type::string());
-        let ret_vtx = install_method_call(&mut genv, recv_vtx, "upcase".to_string(), None);
+        let ret_vtx = install_method_call(
+            &mut genv,
+            recv_vtx,
+            "upcase".to_string(),
+            vec![],
+            None,
+            None,
+        );
 
         // Return vertex should exist
         assert!(genv.get_vertex(ret_vtx).is_some());
@@ -48,9 +179,92 @@ mod tests {
         let mut genv = GlobalEnv::new();
 
         let recv_vtx = genv.new_source(Type::string());
-        let _ret_vtx = install_method_call(&mut genv, recv_vtx, "upcase".to_string(), None);
+        let _ret_vtx = install_method_call(
+            &mut genv,
+            recv_vtx,
+            "upcase".to_string(),
+            vec![],
+            None,
+            None,
+        );
 
         // Box should be added
         assert_eq!(genv.box_count(), 1);
     }
+
+    #[test]
+    fn test_install_operator_call_with_argument() {
+        let mut genv = GlobalEnv::new();
+        genv.register_builtin_method(Type::integer(), "+", Type::integer());
+
+        // 1 + 2
+        let recv_vtx = genv.new_source(Type::integer());
+        let arg_vtx = genv.new_source(Type::integer());
+        let ret_vtx = install_method_call(
+            &mut genv,
+            recv_vtx,
+            "+".to_string(),
+            vec![arg_vtx],
+            None,
+            None,
+        );
+
+        genv.run_all();
+
+        assert_eq!(genv.get_vertex(ret_vtx).unwrap().show(), "Integer");
+    }
+
+    #[test]
+    fn test_install_implicit_self_call_unknown_method() {
+        let mut genv = GlobalEnv::new();
+
+        let _ret_vtx =
+            install_implicit_self_call(&mut genv, "puts".to_string(), vec![], None, None);
+
+        genv.run_all();
+
+        // Unknown receiver-less calls are not reported
+        assert_eq!(genv.type_errors.len(), 0);
+    }
+
+    #[test]
+    fn test_install_super_call_returns_superclass_method_values() {
+        let mut genv = GlobalEnv::new();
+
+        genv.enter_class("Base".to_string(), None);
+        let block_vtx = genv.new_vertex();
+        let return_vtx = genv.new_source(Type::string());
+        genv.register_user_method(Type::instance("Base"), "name", block_vtx, return_vtx);
+        genv.exit_scope();
+
+        genv.enter_class("User".to_string(), Some("Base".to_string()));
+        genv.enter_method("name".to_string());
+        let ret_vtx = install_super_call(&mut genv, vec![], None, None);
+
+        assert_eq!(genv.get_vertex(ret_vtx).unwrap().show(), "String");
+        assert!(genv.missing_super_methods.is_empty());
+    }
+
+    #[test]
+    fn test_install_super_call_without_superclass_method() {
+        let mut genv = GlobalEnv::new();
+
+        genv.enter_class("User".to_string(), Some("Object".to_string()));
+        genv.enter_method("name".to_string());
+        let _ret_vtx = install_super_call(&mut genv, vec![], None, None);
+
+        assert_eq!(genv.missing_super_methods.len(), 1);
+        assert_eq!(genv.missing_super_methods[0].method_name, "name");
+    }
+
+    #[test]
+    fn test_install_super_call_to_object_method_is_not_reported() {
+        let mut genv = GlobalEnv::new();
+
+        genv.enter_class("User".to_string(), Some("Object".to_string()));
+        genv.enter_method("initialize".to_string());
+        let _ret_vtx = install_super_call(&mut genv, vec![], None, None);
+
+        assert!(genv.missing_super_methods.is_empty());
+    }
 }
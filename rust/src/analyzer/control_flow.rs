@@ -0,0 +1,232 @@
+//! Control Flow Handlers - Joining local variable bindings
+//!
+//! This module is responsible for:
+//! - Snapshotting and restoring local variable bindings around branches
+//! - Merging bindings at the join point of conditionals (if/unless/case, &&/||)
+//! - Loop headers joining loop entry and back edges (while/until/for, blocks)
+//! - Merging branch values into the value of the conditional
+//! - Targets of `next`, `break` and `return` values
+//!
+//! Each assignment creates a new vertex (SSA-like), so after a join a local
+//! is bound to a merge vertex fed by the binding at the end of each path.
+
+use std::collections::HashMap;
+
+use crate::env::{GlobalEnv, LocalEnv};
+use crate::graph::{ChangeSet, VertexId};
+
+/// Local variable bindings visible at a program point (name → vertex)
+pub type LocalBindings = HashMap<String, VertexId>;
+
+/// Take a snapshot of the visible local variable bindings
+pub fn snapshot_locals(genv: &GlobalEnv) -> LocalBindings {
+    genv.scope_manager.visible_vars()
+}
+
+/// Restore the bindings of a snapshot before installing the next branch
+///
+/// Locals first assigned in the previous branch are unbound again.
+pub fn restore_locals(genv: &mut GlobalEnv, snapshot: &LocalBindings) {
+    genv.scope_manager.restore_vars(snapshot);
+}
+
+/// Merge the bindings at the end of each branch into the join point
+///
+/// A local bound to different vertices gets a merge vertex fed by each
+/// branch. A path that never assigns a local contributes no type to it.
+///
+/// # Example
+/// ```ruby
+/// x = 1
+/// if cond
+///   x = "one"
+/// end
+/// x  # Integer | String
+/// ```
+pub fn merge_branch_locals(
+    genv: &mut GlobalEnv,
+    lenv: &mut LocalEnv,
+    changes: &mut ChangeSet,
+    branches: &[LocalBindings],
+) {
+    let mut names: Vec<&String> = branches.iter().flat_map(|b| b.keys()).collect();
+    names.sort();
+    names.dedup();
+
+    for name in names {
+        let mut vtxs: Vec<VertexId> = Vec::new();
+        for vtx in branches.iter().filter_map(|b| b.get(name)) {
+            if !vtxs.contains(vtx) {
+                vtxs.push(*vtx);
+            }
+        }
+
+        let bound_vtx = if let [vtx] = vtxs.as_slice() {
+            *vtx
+        } else {
+            let merge_vtx = genv.new_vertex();
+            for vtx in &vtxs {
+                changes.add_edge(*vtx, merge_vtx);
+            }
+            merge_vtx
+        };
+
+        // Record only bindings changed by the branches
+        if genv.scope_manager.lookup_var(name) != Some(bound_vtx) {
+            genv.scope_manager.assign_var(name.clone(), bound_vtx);
+            lenv.new_var(name.clone(), bound_vtx);
+        }
+    }
+}
+
+/// Enter a loop: bind each visible local to a loop header vertex
+///
+/// The header joins the binding before the loop and the bindings at the end
+/// of the body (connected by `exit_loop`), so reads in the body see the
+/// types assigned by later iterations.
+pub fn enter_loop(genv: &mut GlobalEnv, changes: &mut ChangeSet) -> LocalBindings {
+    let mut headers = LocalBindings::new();
+
+    let mut locals: Vec<(String, VertexId)> = snapshot_locals(genv).into_iter().collect();
+    locals.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    for (name, vtx) in locals {
+        let header_vtx = genv.new_vertex();
+        changes.add_edge(vtx, header_vtx);
+        genv.scope_manager.assign_var(name.clone(), header_vtx);
+        headers.insert(name, header_vtx);
+    }
+
+    headers
+}
+
+/// Exit a loop: connect the bindings at the end of the body back to the headers
+///
+/// After the loop, each local is bound to its header: the body may have run
+/// zero or more times.
+pub fn exit_loop(
+    genv: &mut GlobalEnv,
+    lenv: &mut LocalEnv,
+    changes: &mut ChangeSet,
+    headers: &LocalBindings,
+) {
+    let mut names: Vec<&String> = headers.keys().collect();
+    names.sort();
+
+    for name in names {
+        let header_vtx = headers[name];
+        let Some(end_vtx) = genv.scope_manager.lookup_var(name) else {
+            continue;
+        };
+
+        if end_vtx != header_vtx {
+            // Reassigned in the body
+            changes.add_edge(end_vtx, header_vtx);
+            genv.scope_manager.assign_var(name.clone(), header_vtx);
+            lenv.new_var(name.clone(), header_vtx);
+        }
+    }
+}
+
+/// Vertices receiving the values of `next`, `break` and `return`
+///
+/// - method body: `return` flows into the method's return vertex
+/// - block: `next` flows into the block result, `break` into the call result
+/// - lambda: all three flow into the lambda result
+/// - while/until/for: `break` flows into the loop value
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JumpTargets {
+    pub next_vtx: Option<VertexId>,
+    pub break_vtx: Option<VertexId>,
+    pub return_vtx: Option<VertexId>,
+}
+
+/// Install the value of a conditional as the union of its branch values
+pub fn install_branch_values(
+    genv: &mut GlobalEnv,
+    changes: &mut ChangeSet,
+    value_vtxs: Vec<VertexId>,
+) -> VertexId {
+    let merge_vtx = genv.new_vertex();
+    for vtx in value_vtxs {
+        changes.add_edge(vtx, merge_vtx);
+    }
+    merge_vtx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Type;
+
+    #[test]
+    fn test_merge_branch_locals() {
+        let mut genv = GlobalEnv::new();
+        let mut lenv = LocalEnv::new();
+        let mut changes = ChangeSet::new();
+
+        // x = 1
+        let int_vtx = genv.new_source(Type::integer());
+        genv.scope_manager.assign_var("x".to_string(), int_vtx);
+        let before = snapshot_locals(&genv);
+
+        // then: x = "one"
+        let str_vtx = genv.new_source(Type::string());
+        genv.scope_manager.assign_var("x".to_string(), str_vtx);
+        let then_locals = snapshot_locals(&genv);
+        restore_locals(&mut genv, &before);
+
+        // else: (no assignment)
+        let else_locals = snapshot_locals(&genv);
+
+        merge_branch_locals(
+            &mut genv,
+            &mut lenv,
+            &mut changes,
+            &[then_locals, else_locals],
+        );
+        genv.apply_changes(changes);
+
+        let x_vtx = genv.scope_manager.lookup_var("x").unwrap();
+        assert_eq!(genv.get_vertex(x_vtx).unwrap().show(), "(Integer | String)");
+        assert_eq!(lenv.get_var("x"), Some(x_vtx));
+    }
+
+    #[test]
+    fn test_restore_unbinds_branch_locals() {
+        let mut genv = GlobalEnv::new();
+
+        let before = snapshot_locals(&genv);
+        let vtx = genv.new_vertex();
+        genv.scope_manager.assign_var("y".to_string(), vtx);
+        restore_locals(&mut genv, &before);
+
+        assert_eq!(genv.scope_manager.lookup_var("y"), None);
+    }
+
+    #[test]
+    fn test_loop_back_edge() {
+        let mut genv = GlobalEnv::new();
+        let mut lenv = LocalEnv::new();
+        let mut changes = ChangeSet::new();
+
+        // x = 1; while cond; x = "one"; end
+        let int_vtx = genv.new_source(Type::integer());
+        genv.scope_manager.assign_var("x".to_string(), int_vtx);
+
+        let headers = enter_loop(&mut genv, &mut changes);
+        let header_vtx = genv.scope_manager.lookup_var("x").unwrap();
+
+        let str_vtx = genv.new_source(Type::string());
+        genv.scope_manager.assign_var("x".to_string(), str_vtx);
+        exit_loop(&mut genv, &mut lenv, &mut changes, &headers);
+        genv.apply_changes(changes);
+
+        // Reads in the body and after the loop see both types
+        assert_eq!(genv.scope_manager.lookup_var("x"), Some(header_vtx));
+        assert_eq!(
+            genv.get_vertex(header_vtx).unwrap().show(),
+            "(Integer | String)"
+        );
+    }
+}
@@ -7,11 +7,12 @@
 //! - Extracting class/module names from AST nodes (including qualified names like Api::User)
 
 use crate::env::GlobalEnv;
+use crate::graph::VertexId;
 use ruby_prism::Node;
 
 /// Install class definition
-pub fn install_class(genv: &mut GlobalEnv, class_name: String) {
-    genv.enter_class(class_name);
+pub fn install_class(genv: &mut GlobalEnv, class_name: String, superclass: Option<String>) {
+    genv.enter_class(class_name, superclass);
 }
 
 /// Install module definition
@@ -19,9 +20,36 @@ pub fn install_module(genv: &mut GlobalEnv, module_name: String) {
     genv.enter_module(module_name);
 }
 
+/// Vertices of a user-defined method, shared by its body and its call sites
+pub struct MethodVertices {
+    /// Receives the blocks passed at call sites (read by `yield` and `&block`)
+    pub block_vtx: VertexId,
+    /// Receives the values returned by the body and by `return`
+    pub return_vtx: VertexId,
+}
+
 /// Install method definition
-pub fn install_method(genv: &mut GlobalEnv, method_name: String) {
-    genv.enter_method(method_name);
+///
+/// Registers the method on the enclosing class (or Object at top level) and
+/// returns the vertices receiving blocks and return values.
+pub fn install_method(
+    genv: &mut GlobalEnv,
+    method_name: String,
+    singleton: bool,
+) -> MethodVertices {
+    let recv_ty = genv.current_self_type();
+    let block_vtx = genv.new_vertex();
+    let return_vtx = genv.new_vertex();
+    genv.register_user_method(recv_ty, &method_name, block_vtx, return_vtx);
+    if singleton {
+        genv.enter_singleton_method(method_name);
+    } else {
+        genv.enter_method(method_name);
+    }
+    MethodVertices {
+        block_vtx,
+        return_vtx,
+    }
 }
 
 /// Exit current scope (class, module, or method)
@@ -35,6 +63,15 @@ pub fn extract_class_name(class_node: &ruby_prism::ClassNode) -> String {
     extract_constant_path(&class_node.constant_path()).unwrap_or_else(|| "UnknownClass".to_string())
 }
 
+/// Extract superclass name from ClassNode (`class Admin < User`)
+///
+/// A superclass that is not a constant (e.g., `Struct.new(:name)`) is "UnknownClass".
+pub fn extract_superclass_name(class_node: &ruby_prism::ClassNode) -> Option<String> {
+    class_node.superclass().map(|superclass| {
+        extract_constant_path(&superclass).unwrap_or_else(|| "UnknownClass".to_string())
+    })
+}
+
 /// Extract module name from ModuleNode
 /// Supports both simple names (Utils) and qualified names (Api::V1::Utils)
 pub fn extract_module_name(module_node: &ruby_prism::ModuleNode) -> String {
@@ -49,7 +86,7 @@ pub fn extract_module_name(module_node: &ruby_prism::ModuleNode) -> String {
 /// - `Api::User` (ConstantPathNode) → "Api::User"
 /// - `Api::V1::User` (nested ConstantPathNode) → "Api::V1::User"
 /// - `::Api::User` (absolute path with COLON3) → "Api::User"
-fn extract_constant_path(node: &Node) -> Option<String> {
+pub fn extract_constant_path(node: &Node) -> Option<String> {
     // Simple constant read: `User`
     if let Some(constant_read) = node.as_constant_read_node() {
         return Some(String::from_utf8_lossy(constant_read.name().as_slice()).to_string());
@@ -85,7 +122,7 @@ mod tests {
     fn test_enter_exit_class_scope() {
         let mut genv = GlobalEnv::new();
 
-        install_class(&mut genv, "User".to_string());
+        install_class(&mut genv, "User".to_string(), None);
         assert_eq!(
             genv.scope_manager.current_class_name(),
             Some("User".to_string())
@@ -109,12 +146,31 @@ mod tests {
         assert_eq!(genv.scope_manager.current_module_name(), None);
     }
 
+    #[test]
+    fn test_install_method_registers_user_method() {
+        let mut genv = GlobalEnv::new();
+
+        install_class(&mut genv, "User".to_string(), None);
+        let vertices = install_method(&mut genv, "each_item".to_string(), false);
+
+        let info = genv
+            .resolve_method(&crate::types::Type::instance("User"), "each_item")
+            .unwrap();
+        assert_eq!(info.block_vertex, Some(vertices.block_vtx));
+        assert_eq!(info.return_vertex, Some(vertices.return_vtx));
+        assert_eq!(genv.current_method_block_vertex(), Some(vertices.block_vtx));
+        assert_eq!(
+            genv.current_method_return_vertex(),
+            Some(vertices.return_vtx)
+        );
+    }
+
     #[test]
     fn test_nested_method_scope() {
         let mut genv = GlobalEnv::new();
 
-        install_class(&mut genv, "User".to_string());
-        install_method(&mut genv, "greet".to_string());
+        install_class(&mut genv, "User".to_string(), None);
+        install_method(&mut genv, "greet".to_string(), false);
 
         // Still in User class context
         assert_eq!(
@@ -133,7 +189,7 @@ mod tests {
         let mut genv = GlobalEnv::new();
 
         install_module(&mut genv, "Helpers".to_string());
-        install_method(&mut genv, "format".to_string());
+        install_method(&mut genv, "format".to_string(), false);
 
         // Should find module context from within method
         assert_eq!(
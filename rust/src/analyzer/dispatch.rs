@@ -5,10 +5,12 @@
 
 use crate::env::{GlobalEnv, LocalEnv};
 use crate::graph::{ChangeSet, VertexId};
-use crate::source_map::SourceLocation;
+use crate::source_map::{LineIndex, SourceLocation};
 use ruby_prism::Node;
 
-use super::calls::install_method_call;
+use super::calls::{
+    install_implicit_self_call, install_method_call, install_method_call_with_receiver_location,
+};
 use super::variables::{
     install_ivar_read, install_ivar_write, install_local_var_read, install_local_var_write,
     install_self,
@@ -28,21 +30,42 @@ pub enum NeedsChildKind<'a> {
     IvarWrite { ivar_name: String, value: Node<'a> },
     /// Local variable write: need to process value, then call finish_local_var_write
     LocalVarWrite { var_name: String, value: Node<'a> },
-    /// Method call: need to process receiver, then call finish_method_call
+    /// Local variable operator write (x += 1): need to process value, then call
+    /// finish_local_var_operator_write
+    LocalVarOperatorWrite {
+        var_name: String,
+        operator: String,
+        value: Node<'a>,
+        location: SourceLocation,
+    },
+    /// Method call: need to process receiver and arguments, then call finish_method_call
+    ///
+    /// Operators (`a + b`, `-x`) and index access (`arr[0]`, `arr[0] = v`)
+    /// are also method calls in prism and go through this path.
+    /// Receiver-less calls (`each_item { |i| ... }`) have no receiver.
     MethodCall {
-        receiver: Node<'a>,
+        receiver: Option<Node<'a>>,
         method_name: String,
+        /// Positional arguments (e.g., `b` in `a + b`, `0` in `arr[0]`)
+        args: Vec<Node<'a>>,
         location: SourceLocation,
+        /// Location of the receiver expression (None for receiver-less calls)
+        receiver_location: Option<SourceLocation>,
         /// Optional block attached to the method call
         block: Option<Node<'a>>,
     },
+    /// Yield: need to process arguments, then call finish_yield
+    Yield {
+        args: Vec<Node<'a>>,
+        location: SourceLocation,
+    },
 }
 
 /// First pass: check if node can be handled immediately without child processing
 ///
 /// Note: Literals (including Array) are handled in install.rs via install_literal
 /// because Array literals need child processing for element type inference.
-pub fn dispatch_simple(genv: &mut GlobalEnv, lenv: &mut LocalEnv, node: &Node) -> DispatchResult {
+pub fn dispatch_simple(genv: &mut GlobalEnv, node: &Node) -> DispatchResult {
     // Instance variable read: @name
     if let Some(ivar_read) = node.as_instance_variable_read_node() {
         let ivar_name = String::from_utf8_lossy(ivar_read.name().as_slice()).to_string();
@@ -57,10 +80,18 @@ pub fn dispatch_simple(genv: &mut GlobalEnv, lenv: &mut LocalEnv, node: &Node) -
         return DispatchResult::Vertex(install_self(genv));
     }
 
-    // Local variable read: x
+    // Local variable read: x (also numbered block parameters like _1)
     if let Some(read_node) = node.as_local_variable_read_node() {
         let var_name = String::from_utf8_lossy(read_node.name().as_slice()).to_string();
-        return match install_local_var_read(lenv, &var_name) {
+        return match install_local_var_read(genv, &var_name) {
+            Some(vtx) => DispatchResult::Vertex(vtx),
+            None => DispatchResult::NotHandled,
+        };
+    }
+
+    // Implicit block parameter read: it (Ruby 3.4)
+    if node.as_it_local_variable_read_node().is_some() {
+        return match install_local_var_read(genv, "it") {
             Some(vtx) => DispatchResult::Vertex(vtx),
             None => DispatchResult::NotHandled,
         };
@@ -70,7 +101,10 @@ pub fn dispatch_simple(genv: &mut GlobalEnv, lenv: &mut LocalEnv, node: &Node) -
 }
 
 /// Check if node needs child processing
-pub fn dispatch_needs_child<'a>(node: &Node<'a>, source: &str) -> Option<NeedsChildKind<'a>> {
+pub fn dispatch_needs_child<'a>(
+    node: &Node<'a>,
+    line_index: &LineIndex,
+) -> Option<NeedsChildKind<'a>> {
     // Instance variable write: @name = value
     if let Some(ivar_write) = node.as_instance_variable_write_node() {
         let ivar_name = String::from_utf8_lossy(ivar_write.name().as_slice()).to_string();
@@ -89,23 +123,58 @@ pub fn dispatch_needs_child<'a>(node: &Node<'a>, source: &str) -> Option<NeedsCh
         });
     }
 
-    // Method call: x.upcase or x.each { |i| ... }
+    // Local variable operator write: x += value
+    if let Some(op_write) = node.as_local_variable_operator_write_node() {
+        let var_name = String::from_utf8_lossy(op_write.name().as_slice()).to_string();
+        let operator = String::from_utf8_lossy(op_write.binary_operator().as_slice()).to_string();
+        let location = SourceLocation::from_prism_location_with_index(&node.location(), line_index);
+        return Some(NeedsChildKind::LocalVarOperatorWrite {
+            var_name,
+            operator,
+            value: op_write.value(),
+            location,
+        });
+    }
+
+    // Method call: x.upcase, x.each { |i| ... }, a + b, arr[0], each_item { |i| ... }
     if let Some(call_node) = node.as_call_node() {
-        if let Some(receiver) = call_node.receiver() {
-            let method_name = String::from_utf8_lossy(call_node.name().as_slice()).to_string();
-            let location =
-                SourceLocation::from_prism_location_with_source(&node.location(), source);
-
-            // Get block if present (e.g., `x.each { |i| ... }`)
-            let block = call_node.block();
-
-            return Some(NeedsChildKind::MethodCall {
-                receiver,
-                method_name,
-                location,
-                block,
-            });
-        }
+        let method_name = String::from_utf8_lossy(call_node.name().as_slice()).to_string();
+        // Point at the method name (`upcase` in `x.upcase`), not the whole call
+        let location = SourceLocation::from_prism_location_with_index(
+            &call_node.message_loc().unwrap_or_else(|| node.location()),
+            line_index,
+        );
+        let receiver_location = call_node.receiver().map(|receiver| {
+            SourceLocation::from_prism_location_with_index(&receiver.location(), line_index)
+        });
+
+        // Get arguments (e.g., `1` in `x + 1`, `0` in `arr[0]`)
+        let args: Vec<Node<'a>> = call_node
+            .arguments()
+            .map(|args_node| args_node.arguments().iter().collect())
+            .unwrap_or_default();
+
+        // Get block if present (e.g., `x.each { |i| ... }` or `x.each(&blk)`)
+        let block = call_node.block();
+
+        return Some(NeedsChildKind::MethodCall {
+            receiver: call_node.receiver(),
+            method_name,
+            args,
+            location,
+            receiver_location,
+            block,
+        });
+    }
+
+    // Yield: yield x, y
+    if let Some(yield_node) = node.as_yield_node() {
+        let args: Vec<Node<'a>> = yield_node
+            .arguments()
+            .map(|args_node| args_node.arguments().iter().collect())
+            .unwrap_or_default();
+        let location = SourceLocation::from_prism_location_with_index(&node.location(), line_index);
+        return Some(NeedsChildKind::Yield { args, location });
     }
 
     None
@@ -127,12 +196,75 @@ pub fn finish_local_var_write(
     install_local_var_write(genv, lenv, changes, var_name, value_vtx)
 }
 
-/// Finish method call after receiver is processed
+/// Finish local variable operator write after value is processed
+///
+/// `x += 1` is checked as the method call `x.+(1)` and its result is
+/// written back to `x`.
+pub fn finish_local_var_operator_write(
+    genv: &mut GlobalEnv,
+    lenv: &mut LocalEnv,
+    changes: &mut ChangeSet,
+    var_name: String,
+    operator: String,
+    value_vtx: VertexId,
+    location: SourceLocation,
+) -> Option<VertexId> {
+    let recv_vtx = install_local_var_read(genv, &var_name)?;
+    let ret_vtx = install_method_call(
+        genv,
+        recv_vtx,
+        operator,
+        vec![value_vtx],
+        None,
+        Some(location),
+    );
+    Some(install_local_var_write(
+        genv, lenv, changes, var_name, ret_vtx,
+    ))
+}
+
+/// Finish method call after receiver, arguments and block are processed
+///
+/// A missing receiver means a call on implicit self.
 pub fn finish_method_call(
     genv: &mut GlobalEnv,
-    recv_vtx: VertexId,
+    recv_vtx: Option<VertexId>,
     method_name: String,
+    arg_vtxs: Vec<VertexId>,
+    block_vtx: Option<VertexId>,
     location: SourceLocation,
+    receiver_location: Option<SourceLocation>,
 ) -> VertexId {
-    install_method_call(genv, recv_vtx, method_name, Some(location))
+    match recv_vtx {
+        Some(recv_vtx) => install_method_call_with_receiver_location(
+            genv,
+            recv_vtx,
+            method_name,
+            arg_vtxs,
+            block_vtx,
+            Some(location),
+            receiver_location,
+        ),
+        None => install_implicit_self_call(genv, method_name, arg_vtxs, block_vtx, Some(location)),
+    }
+}
+
+/// Finish yield after arguments are processed
+///
+/// `yield x` calls the block passed to the enclosing method, so it is
+/// installed as `block.call(x)` on the method's block vertex.
+pub fn finish_yield(
+    genv: &mut GlobalEnv,
+    arg_vtxs: Vec<VertexId>,
+    location: SourceLocation,
+) -> Option<VertexId> {
+    let block_vtx = genv.current_method_block_vertex()?;
+    Some(install_method_call(
+        genv,
+        block_vtx,
+        "call".to_string(),
+        arg_vtxs,
+        None,
+        Some(location),
+    ))
 }
@@ -0,0 +1,327 @@
+//! Dynamic Method Handlers - Methods defined by calls in a class body
+//!
+//! This module is responsible for:
+//! - Recognizing `alias_method`, `define_method` and delegation calls with
+//!   literal symbol (or string) arguments
+//! - Registering aliases with the signature of the original method
+//! - Registering delegated methods and forwarding them to the target's method
+//! - Registering `define_method` with a Proc or Method body untyped
+//!
+//! The `define_method` block body is installed in install.rs, since it needs
+//! the full installer.
+
+use crate::env::GlobalEnv;
+use crate::graph::VertexId;
+use crate::source_map::SourceLocation;
+use ruby_prism::Node;
+
+use super::calls::{install_implicit_self_call, install_method_call};
+use super::variables::install_ivar_read;
+
+/// A method definition made by a call in a class body
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DynamicDefinition {
+    /// `alias_method :new_name, :old_name`
+    Alias { new_name: String, old_name: String },
+    /// `define_method(:name) { |arg| ... }`
+    DefineMethod { name: String },
+    /// Methods forwarded to a target (`@ivar` or a method name)
+    Delegation {
+        target: String,
+        methods: Vec<DelegatedMethod>,
+    },
+}
+
+/// A method defined by delegation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DelegatedMethod {
+    /// Method called on the target
+    pub target_method: String,
+    /// Name of the method defined on the class
+    pub name: String,
+}
+
+/// A delegated method whose target is resolved at the end of the class body
+///
+/// The target is usually an instance variable assigned in `initialize`,
+/// after the delegation call.
+#[derive(Debug, Clone)]
+pub struct PendingDelegation {
+    pub target: String,
+    pub target_method: String,
+    pub return_vtx: VertexId,
+    pub location: SourceLocation,
+}
+
+/// Recognize a receiver-less call that defines methods
+///
+/// # Example
+/// ```ruby
+/// alias_method :full_name, :name
+/// define_method(:label) { name.upcase }
+/// def_delegators :@items, :size, :first       # Forwardable
+/// def_delegator :@items, :first, :head        # Forwardable
+/// delegate [:size, :first] => :@items         # Forwardable
+/// delegate :size, :first, to: :items          # ActiveSupport
+/// ```
+pub fn dynamic_definition(method_name: &str, args: &[Node]) -> Option<DynamicDefinition> {
+    match method_name {
+        "alias_method" => {
+            let [new_name, old_name] = args else {
+                return None;
+            };
+            Some(DynamicDefinition::Alias {
+                new_name: literal_name(new_name)?,
+                old_name: literal_name(old_name)?,
+            })
+        }
+        "define_method" => Some(DynamicDefinition::DefineMethod {
+            name: literal_name(args.first()?)?,
+        }),
+        "def_delegators" | "def_instance_delegators" => {
+            let (target, names) = args.split_first()?;
+            let methods = names
+                .iter()
+                .map(|name| literal_name(name).map(DelegatedMethod::same_name))
+                .collect::<Option<Vec<_>>>()?;
+            Some(DynamicDefinition::Delegation {
+                target: literal_name(target)?,
+                methods,
+            })
+        }
+        "def_delegator" | "def_instance_delegator" => {
+            let target = literal_name(args.first()?)?;
+            let target_method = literal_name(args.get(1)?)?;
+            let name = match args.get(2) {
+                Some(name) => literal_name(name)?,
+                None => target_method.clone(),
+            };
+            Some(DynamicDefinition::Delegation {
+                target,
+                methods: vec![DelegatedMethod {
+                    target_method,
+                    name,
+                }],
+            })
+        }
+        "delegate" | "instance_delegate" => delegate_definition(args),
+        _ => None,
+    }
+}
+
+impl DelegatedMethod {
+    fn same_name(name: String) -> Self {
+        Self {
+            target_method: name.clone(),
+            name,
+        }
+    }
+}
+
+/// Recognize `delegate :a, :b, to: :target, prefix: true` or `delegate [:a, :b] => :target`
+fn delegate_definition(args: &[Node]) -> Option<DynamicDefinition> {
+    let (options, names) = args.split_last()?;
+    let options = options.as_keyword_hash_node()?;
+
+    let mut target = None;
+    let mut prefix = None;
+    for element in options.elements().iter() {
+        let assoc = element.as_assoc_node()?;
+        let key = assoc.key();
+
+        // Forwardable: delegate [:a, :b] => :target
+        if let Some(array) = key.as_array_node() {
+            let methods = array
+                .elements()
+                .iter()
+                .map(|name| literal_name(&name).map(DelegatedMethod::same_name))
+                .collect::<Option<Vec<_>>>()?;
+            return Some(DynamicDefinition::Delegation {
+                target: literal_name(&assoc.value())?,
+                methods,
+            });
+        }
+
+        // ActiveSupport: to: :target, prefix: true | :name
+        match literal_name(&key)?.as_str() {
+            "to" => target = literal_name(&assoc.value()),
+            "prefix" => {
+                let value = assoc.value();
+                if value.as_true_node().is_some() {
+                    prefix = Some(None);
+                } else if let Some(name) = literal_name(&value) {
+                    prefix = Some(Some(name));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let target = target?;
+    let methods = names
+        .iter()
+        .map(|name| {
+            let target_method = literal_name(name)?;
+            let name = match &prefix {
+                Some(Some(prefix)) => format!("{}_{}", prefix, target_method),
+                Some(None) => format!("{}_{}", target.trim_start_matches('@'), target_method),
+                None => target_method.clone(),
+            };
+            Some(DelegatedMethod {
+                target_method,
+                name,
+            })
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some(DynamicDefinition::Delegation { target, methods })
+}
+
+/// Get the name in a literal symbol or string argument (`:name`, `"name"`)
+pub fn literal_name(node: &Node) -> Option<String> {
+    if let Some(symbol) = node.as_symbol_node() {
+        return Some(String::from_utf8_lossy(symbol.unescaped()).to_string());
+    }
+    if let Some(string) = node.as_string_node() {
+        return Some(String::from_utf8_lossy(string.unescaped()).to_string());
+    }
+    None
+}
+
+/// Register a delegated method on the current class and return its return vertex
+pub fn install_delegated_method(genv: &mut GlobalEnv, name: &str) -> VertexId {
+    let recv_ty = genv.current_self_type();
+    let block_vtx = genv.new_vertex();
+    let return_vtx = genv.new_vertex();
+    genv.register_user_method(recv_ty, name, block_vtx, return_vtx);
+    return_vtx
+}
+
+/// Register a `define_method` whose body is not a literal block
+///
+/// The body is a Proc or Method value (`&blk`, `instance_method(:name)`),
+/// so the method is registered untyped like an alias of an unknown method.
+pub fn install_untyped_method(genv: &mut GlobalEnv, name: &str) {
+    let recv_ty = genv.current_self_type();
+    genv.register_untyped_method(recv_ty, name);
+}
+
+/// Forward a delegated method to its target's method
+///
+/// The target is an instance variable (`:@items`) or a method on self
+/// (`:items`); the target's method return values flow into the delegated
+/// method's return vertex.
+pub fn finish_delegation(genv: &mut GlobalEnv, delegation: PendingDelegation) {
+    let target_vtx = if delegation.target.starts_with('@') {
+        // An instance variable never assigned leaves the method untyped
+        let Some(ivar_vtx) = install_ivar_read(genv, &delegation.target) else {
+            return;
+        };
+        ivar_vtx
+    } else {
+        install_implicit_self_call(
+            genv,
+            delegation.target,
+            vec![],
+            None,
+            Some(delegation.location.clone()),
+        )
+    };
+    let ret_vtx = install_method_call(
+        genv,
+        target_vtx,
+        delegation.target_method,
+        vec![],
+        None,
+        Some(delegation.location),
+    );
+    genv.add_edge(ret_vtx, delegation.return_vtx);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_ruby_source;
+
+    /// Parse a single receiver-less call and recognize it
+    fn definition_of(source: &str) -> Option<DynamicDefinition> {
+        let parse_result = parse_ruby_source(source, "test.rb".to_string()).unwrap();
+        let root = parse_result.node();
+        let program = root.as_program_node().unwrap();
+        let statement = program.statements().body().iter().next().unwrap();
+        let call = statement.as_call_node().unwrap();
+        let method_name = String::from_utf8_lossy(call.name().as_slice()).to_string();
+        let args: Vec<Node> = call
+            .arguments()
+            .map(|args| args.arguments().iter().collect())
+            .unwrap_or_default();
+        dynamic_definition(&method_name, &args)
+    }
+
+    #[test]
+    fn test_alias_method() {
+        assert_eq!(
+            definition_of("alias_method :full_name, :name"),
+            Some(DynamicDefinition::Alias {
+                new_name: "full_name".to_string(),
+                old_name: "name".to_string(),
+            })
+        );
+        assert_eq!(definition_of("alias_method new_name, :name"), None);
+    }
+
+    #[test]
+    fn test_define_method() {
+        assert_eq!(
+            definition_of("define_method(\"label\") { 1 }"),
+            Some(DynamicDefinition::DefineMethod {
+                name: "label".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_def_delegators_and_def_delegator() {
+        assert_eq!(
+            definition_of("def_delegators :@items, :size, :first"),
+            Some(DynamicDefinition::Delegation {
+                target: "@items".to_string(),
+                methods: vec![
+                    DelegatedMethod::same_name("size".to_string()),
+                    DelegatedMethod::same_name("first".to_string()),
+                ],
+            })
+        );
+        assert_eq!(
+            definition_of("def_delegator :@items, :first, :head"),
+            Some(DynamicDefinition::Delegation {
+                target: "@items".to_string(),
+                methods: vec![DelegatedMethod {
+                    target_method: "first".to_string(),
+                    name: "head".to_string(),
+                }],
+            })
+        );
+    }
+
+    #[test]
+    fn test_delegate_forms() {
+        assert_eq!(
+            definition_of("delegate [:size] => :@items"),
+            Some(DynamicDefinition::Delegation {
+                target: "@items".to_string(),
+                methods: vec![DelegatedMethod::same_name("size".to_string())],
+            })
+        );
+        assert_eq!(
+            definition_of("delegate :name, to: :owner, prefix: true"),
+            Some(DynamicDefinition::Delegation {
+                target: "owner".to_string(),
+                methods: vec![DelegatedMethod {
+                    target_method: "name".to_string(),
+                    name: "owner_name".to_string(),
+                }],
+            })
+        );
+        assert_eq!(definition_of("delegate :name"), None);
+    }
+}
@@ -0,0 +1,92 @@
+//! Exception Handlers - Typing rescued exceptions
+//!
+//! This module is responsible for:
+//! - Collecting the exception classes listed in a `rescue` clause
+//! - Typing the `=> e` reference as the union of those classes
+//! - Defaulting a bare `rescue` to StandardError
+
+use crate::env::GlobalEnv;
+use crate::graph::{ChangeSet, VertexId};
+use crate::types::Type;
+use ruby_prism::Node;
+
+use super::definitions::extract_constant_path;
+
+/// Get the exception types rescued by a `rescue` clause
+///
+/// Returns None if a class is not a constant (e.g., `rescue *ERRORS`) or
+/// its methods are unknown (e.g., `rescue Errno::ENOENT`), since the
+/// rescued value then can't be checked.
+///
+/// # Example
+/// ```ruby
+/// rescue => e                          # StandardError
+/// rescue KeyError, IndexError => e     # KeyError | IndexError
+/// rescue MyError => e                  # MyError (class MyError < StandardError)
+/// ```
+pub fn rescued_exception_types(genv: &GlobalEnv, exceptions: &[Node]) -> Option<Vec<Type>> {
+    if exceptions.is_empty() {
+        return Some(vec![Type::instance("StandardError")]);
+    }
+
+    exceptions
+        .iter()
+        .map(|exception| genv.exception_type(&extract_constant_path(exception)?))
+        .collect()
+}
+
+/// Install the value bound by `rescue ... => e`
+///
+/// The vertex gets one source per rescued class, so it shows as their union.
+/// Unknown classes leave it untyped.
+pub fn install_exception_value(
+    genv: &mut GlobalEnv,
+    changes: &mut ChangeSet,
+    exception_types: Option<Vec<Type>>,
+) -> VertexId {
+    let exception_vtx = genv.new_vertex();
+    for ty in exception_types.unwrap_or_default() {
+        let src_vtx = genv.new_source(ty);
+        changes.add_edge(src_vtx, exception_vtx);
+    }
+    exception_vtx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bare_rescue_is_standard_error() {
+        assert_eq!(
+            rescued_exception_types(&GlobalEnv::new(), &[]),
+            Some(vec![Type::instance("StandardError")])
+        );
+    }
+
+    #[test]
+    fn test_install_exception_value_union() {
+        let mut genv = GlobalEnv::new();
+        let mut changes = ChangeSet::new();
+
+        let types = vec![Type::instance("KeyError"), Type::instance("IndexError")];
+        let vtx = install_exception_value(&mut genv, &mut changes, Some(types));
+        genv.apply_changes(changes);
+
+        assert_eq!(
+            genv.get_vertex(vtx).unwrap().show(),
+            "(IndexError | KeyError)"
+        );
+    }
+
+    #[test]
+    fn test_install_exception_value_unknown() {
+        let mut genv = GlobalEnv::new();
+        let mut changes = ChangeSet::new();
+
+        let vtx = install_exception_value(&mut genv, &mut changes, None);
+        genv.apply_changes(changes);
+
+        assert_eq!(genv.get_vertex(vtx).unwrap().show(), "untyped");
+    }
+}
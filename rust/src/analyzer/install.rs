@@ -3,32 +3,67 @@
 //! This module is responsible for:
 //! - Traversing the Ruby AST (Abstract Syntax Tree)
 //! - Coordinating the graph construction process
+//! - Installing conditionals and loops with joined local variable bindings
+//! - Routing `return`/`next`/`break` values and detecting unreachable code
+//! - Installing begin/rescue/else/ensure and `expr rescue fallback`
+//! - Installing case/in patterns and binding their captures
+//! - Installing `super` calls and recording `include` for the ancestor chain
+//! - Installing `alias`, `alias_method`, `define_method` and delegations
 
 use crate::env::{GlobalEnv, LocalEnv};
-use crate::graph::{BlockParameterTypeBox, ChangeSet, VertexId};
+use crate::graph::{
+    BlockParameterTypeBox, ChangeSet, DestructuredParameter, ElementAccess, TypeFilter, VertexId,
+};
+use crate::source_map::{LineIndex, SourceLocation};
+use crate::types::Type;
 use ruby_prism::Node;
 
-use super::blocks::{enter_block_scope, exit_block_scope, install_block_parameter};
+use super::blocks::{
+    enter_block_scope, exit_block_scope, install_block_parameter, install_numbered_parameters,
+    install_proc, is_proc_constructor,
+};
+use super::calls::install_super_call;
+use super::control_flow::{
+    enter_loop, exit_loop, install_branch_values, merge_branch_locals, restore_locals,
+    snapshot_locals, JumpTargets,
+};
 use super::definitions::{
-    exit_scope, extract_class_name, extract_module_name, install_class, install_method,
-    install_module,
+    exit_scope, extract_class_name, extract_constant_path, extract_module_name,
+    extract_superclass_name, install_class, install_method, install_module,
 };
 use super::dispatch::{
-    dispatch_needs_child, dispatch_simple, finish_ivar_write, finish_local_var_write,
-    finish_method_call, DispatchResult, NeedsChildKind,
+    dispatch_needs_child, dispatch_simple, finish_ivar_write, finish_local_var_operator_write,
+    finish_local_var_write, finish_method_call, finish_yield, DispatchResult, NeedsChildKind,
+};
+use super::dynamic_methods::{
+    dynamic_definition, finish_delegation, install_delegated_method, install_untyped_method,
+    literal_name, DynamicDefinition, PendingDelegation,
 };
+use super::exceptions::{install_exception_value, rescued_exception_types};
 use super::literals::install_literal;
+use super::narrowing::{
+    condition_narrowing, install_narrowed_value, keep_narrowed_locals, narrow_locals,
+    restore_narrowed_locals, when_narrowings, LocalFilters, Narrowing,
+};
 use super::parameters::{
-    install_keyword_rest_parameter, install_optional_parameter, install_required_parameter,
-    install_rest_parameter,
+    install_block_argument_parameter, install_keyword_rest_parameter, install_optional_parameter,
+    install_required_parameter, install_rest_parameter,
 };
+use super::patterns::{install_element_value, install_match_result, pattern_class_name};
 
 /// Build graph from AST
 pub struct AstInstaller<'a> {
     genv: &'a mut GlobalEnv,
     lenv: &'a mut LocalEnv,
     changes: ChangeSet,
-    source: &'a str,
+    /// Line index of the source, for node locations
+    line_index: LineIndex<'a>,
+    /// Where `next`, `break` and `return` values flow at the current point
+    jump_targets: JumpTargets,
+    /// Whether the current path ended with `return`, `raise`, `next` or `break`
+    terminated: bool,
+    /// Delegated methods of the current class body, forwarded at its end
+    pending_delegations: Vec<PendingDelegation>,
 }
 
 impl<'a> AstInstaller<'a> {
@@ -37,12 +72,27 @@ impl<'a> AstInstaller<'a> {
             genv,
             lenv,
             changes: ChangeSet::new(),
-            source,
+            line_index: LineIndex::new(source),
+            jump_targets: JumpTargets::default(),
+            terminated: false,
+            pending_delegations: Vec::new(),
         }
     }
 
     /// Install node (returns Vertex ID)
+    ///
+    /// The node's location is recorded for the vertex, so diagnostics can
+    /// point at where a type came from.
     pub fn install_node(&mut self, node: &Node) -> Option<VertexId> {
+        let vtx = self.install_node_kind(node)?;
+        let location =
+            SourceLocation::from_prism_location_with_index(&node.location(), &self.line_index);
+        self.genv.record_vertex_location(vtx, location);
+        Some(vtx)
+    }
+
+    /// Install node by its kind
+    fn install_node_kind(&mut self, node: &Node) -> Option<VertexId> {
         // Class definition
         if let Some(class_node) = node.as_class_node() {
             return self.install_class_node(&class_node);
@@ -63,8 +113,128 @@ impl<'a> AstInstaller<'a> {
             return self.install_block_node(&block_node);
         }
 
+        // Lambda literal: ->(x) { x }
+        if let Some(lambda_node) = node.as_lambda_node() {
+            return self.install_lambda_node(&lambda_node);
+        }
+
+        // Conditionals: if/elsif/else, modifier if, ternary
+        if let Some(if_node) = node.as_if_node() {
+            return self.install_if_node(&if_node);
+        }
+
+        // unless/else
+        if let Some(unless_node) = node.as_unless_node() {
+            return self.install_unless_node(&unless_node);
+        }
+
+        // case/when
+        if let Some(case_node) = node.as_case_node() {
+            return self.install_case_node(&case_node);
+        }
+
+        // case/in pattern matching
+        if let Some(case_match_node) = node.as_case_match_node() {
+            return self.install_case_match_node(&case_match_node);
+        }
+
+        // expr in pattern
+        if let Some(match_predicate) = node.as_match_predicate_node() {
+            let value_vtx = self.install_value(&match_predicate.value());
+            self.install_pattern(&match_predicate.pattern(), value_vtx);
+            return Some(install_match_result(self.genv, &mut self.changes));
+        }
+
+        // expr => pattern
+        if let Some(match_required) = node.as_match_required_node() {
+            let value_vtx = self.install_value(&match_required.value());
+            self.install_pattern(&match_required.pattern(), value_vtx);
+            return Some(self.genv.new_source(Type::Nil));
+        }
+
+        // alias new_name old_name
+        if let Some(alias_node) = node.as_alias_method_node() {
+            if let Some(new_name) = literal_name(&alias_node.new_name()) {
+                if let Some(old_name) = literal_name(&alias_node.old_name()) {
+                    self.genv.register_alias(&new_name, &old_name);
+                }
+            }
+            return Some(self.genv.new_source(Type::Nil));
+        }
+
+        // super(args), super()
+        if let Some(super_node) = node.as_super_node() {
+            return self.install_super_node(&super_node);
+        }
+
+        // super (forwards the method's arguments)
+        if let Some(forwarding_super) = node.as_forwarding_super_node() {
+            return self.install_forwarding_super_node(&forwarding_super);
+        }
+
+        // a && b, a and b
+        if let Some(and_node) = node.as_and_node() {
+            let narrowing = condition_narrowing(&and_node.left());
+            return self.install_short_circuit(&and_node.left(), &and_node.right(), narrowing);
+        }
+
+        // a || b, a or b
+        if let Some(or_node) = node.as_or_node() {
+            // The right side runs when the left side is falsy
+            let narrowing = condition_narrowing(&or_node.left()).negate();
+            return self.install_short_circuit(&or_node.left(), &or_node.right(), narrowing);
+        }
+
+        // Loops: while, until (also the `begin ... end while` modifier forms)
+        if let Some(while_node) = node.as_while_node() {
+            return self.install_loop(&while_node.predicate(), while_node.statements());
+        }
+
+        if let Some(until_node) = node.as_until_node() {
+            return self.install_loop(&until_node.predicate(), until_node.statements());
+        }
+
+        // for x in collection
+        if let Some(for_node) = node.as_for_node() {
+            return self.install_for_node(&for_node);
+        }
+
+        // begin/rescue/else/ensure (also method and block bodies with rescue)
+        if let Some(begin_node) = node.as_begin_node() {
+            return self.install_begin_node(&begin_node);
+        }
+
+        // expr rescue fallback
+        if let Some(rescue_modifier) = node.as_rescue_modifier_node() {
+            let branches = vec![
+                Some(rescue_modifier.expression()),
+                Some(rescue_modifier.rescue_expression()),
+            ];
+            return self.install_branches(branches);
+        }
+
+        // Jumps: return, next, break
+        if let Some(return_node) = node.as_return_node() {
+            return self.install_jump(return_node.arguments(), self.jump_targets.return_vtx);
+        }
+
+        if let Some(next_node) = node.as_next_node() {
+            return self.install_jump(next_node.arguments(), self.jump_targets.next_vtx);
+        }
+
+        if let Some(break_node) = node.as_break_node() {
+            return self.install_jump(break_node.arguments(), self.jump_targets.break_vtx);
+        }
+
+        // Parenthesized expression: (a; b)
+        if let Some(parentheses_node) = node.as_parentheses_node() {
+            return parentheses_node
+                .body()
+                .and_then(|body| self.install_branch_body(&body));
+        }
+
         // Try simple dispatch first (no child processing needed)
-        match dispatch_simple(self.genv, self.lenv, node) {
+        match dispatch_simple(self.genv, node) {
             DispatchResult::Vertex(vtx) => return Some(vtx),
             DispatchResult::NotHandled => {}
         }
@@ -75,7 +245,7 @@ impl<'a> AstInstaller<'a> {
         }
 
         // Check if node needs child processing
-        if let Some(kind) = dispatch_needs_child(node, self.source) {
+        if let Some(kind) = dispatch_needs_child(node, &self.line_index) {
             return self.process_needs_child(kind);
         }
 
@@ -92,10 +262,46 @@ impl<'a> AstInstaller<'a> {
             return self.install_array_literal_elements(elements);
         }
 
+        // Interpolated literals need their embedded expressions analyzed
+        self.install_interpolated_parts(node);
+
         // Other literals (String, Integer, Hash, nil, true, false, Symbol)
         install_literal(self.genv, node)
     }
 
+    /// Install embedded expressions of interpolated literals
+    ///
+    /// The literal itself has a fixed type, but calls inside `#{}` still need
+    /// to be checked (e.g., `"#{x.upcase}"`, `:"#{x}"`, `/#{x}/`, `` `#{x}` ``).
+    fn install_interpolated_parts(&mut self, node: &Node) {
+        let parts: Vec<Node> = if let Some(n) = node.as_interpolated_string_node() {
+            n.parts().iter().collect()
+        } else if let Some(n) = node.as_interpolated_symbol_node() {
+            n.parts().iter().collect()
+        } else if let Some(n) = node.as_interpolated_regular_expression_node() {
+            n.parts().iter().collect()
+        } else if let Some(n) = node.as_interpolated_x_string_node() {
+            n.parts().iter().collect()
+        } else {
+            return;
+        };
+
+        for part in &parts {
+            if let Some(embedded) = part.as_embedded_statements_node() {
+                // "#{expr}"
+                if let Some(statements) = embedded.statements() {
+                    self.install_statements(&statements);
+                }
+            } else if let Some(embedded) = part.as_embedded_variable_node() {
+                // "#@ivar"
+                self.install_node(&embedded.variable());
+            } else {
+                // String parts and nested interpolated strings ("a" "#{b}")
+                self.install_node(part);
+            }
+        }
+    }
+
     /// Install array literal with pre-collected elements
     fn install_array_literal_elements(&mut self, elements: Vec<Node>) -> Option<VertexId> {
         use crate::types::Type;
@@ -150,42 +356,147 @@ impl<'a> AstInstaller<'a> {
                     value_vtx,
                 ))
             }
+            NeedsChildKind::LocalVarOperatorWrite {
+                var_name,
+                operator,
+                value,
+                location,
+            } => {
+                let value_vtx = self.install_node(&value)?;
+                finish_local_var_operator_write(
+                    self.genv,
+                    self.lenv,
+                    &mut self.changes,
+                    var_name,
+                    operator,
+                    value_vtx,
+                    location,
+                )
+            }
             NeedsChildKind::MethodCall {
                 receiver,
                 method_name,
+                args,
                 location,
+                receiver_location,
                 block,
             } => {
-                let recv_vtx = self.install_node(&receiver)?;
+                // alias_method, define_method and delegations with literal names
+                if receiver.is_none() {
+                    if let Some(definition) = dynamic_definition(&method_name, &args) {
+                        if let Some(ret_vtx) =
+                            self.install_dynamic_definition(definition, block.as_ref(), &location)
+                        {
+                            return Some(ret_vtx);
+                        }
+                    }
+                }
+
+                // `include A, B` puts A nearest in the ancestors of the class
+                if receiver.is_none() && method_name == "include" {
+                    for arg in args.iter().rev() {
+                        if let Some(module_name) = extract_constant_path(arg) {
+                            self.genv.register_include(&module_name);
+                        }
+                    }
+                }
+
+                // lambda { }, proc { }, Proc.new { } evaluate to the block itself
+                if is_proc_constructor(receiver.as_ref(), &method_name) {
+                    if let Some(bn) = block.as_ref().and_then(|b| b.as_block_node()) {
+                        return self.install_block_node(&bn);
+                    }
+                }
 
-                // Process block if present (e.g., `x.each { |i| ... }`)
+                let recv_vtx = match &receiver {
+                    Some(receiver) => Some(self.install_node(receiver)?),
+                    None => None,
+                };
+
+                // Process arguments in order
+                // Arguments we can't type yet get an untyped vertex to keep positions aligned
+                let arg_vtxs: Vec<VertexId> = args
+                    .iter()
+                    .map(|arg| {
+                        self.install_node(arg)
+                            .unwrap_or_else(|| self.genv.new_vertex())
+                    })
+                    .collect();
+
+                // Process block if present (e.g., `x.each { |i| ... }` or `x.each(&blk)`)
                 // Collect block parameter vertex IDs for type inference
                 let mut block_param_vtxs: Vec<VertexId> = Vec::new();
+                let mut destructured_params: Vec<DestructuredParameter> = Vec::new();
+                let mut block_vtx: Option<VertexId> = None;
+                let mut break_vtx: Option<VertexId> = None;
                 if let Some(block_node) = block {
                     // Block may be a BlockNode or BlockArgumentNode
                     if let Some(bn) = block_node.as_block_node() {
-                        block_param_vtxs = self.install_block_node_with_params(&bn);
+                        // `break` in the block becomes the result of the call
+                        let block_break_vtx = self.genv.new_vertex();
+                        break_vtx = Some(block_break_vtx);
+                        let (param_vtxs, destructured, proc_vtx) =
+                            self.install_block_node_with_params(&bn, block_break_vtx);
+                        block_param_vtxs = param_vtxs;
+                        destructured_params = destructured;
+                        block_vtx = Some(proc_vtx);
+                    } else if let Some(block_arg) = block_node.as_block_argument_node() {
+                        // `&blk` passes a Proc; `&:sym` is a Symbol and not a Proc value
+                        if let Some(expression) = block_arg.expression() {
+                            if expression.as_symbol_node().is_none() {
+                                block_vtx = self.install_node(&expression);
+                            }
+                        }
                     }
                 }
 
                 // Create BlockParameterTypeBox if block has parameters
-                if !block_param_vtxs.is_empty() {
-                    let box_id = self.genv.alloc_box_id();
-                    let block_box = BlockParameterTypeBox::new(
-                        box_id,
-                        recv_vtx,
-                        method_name.clone(),
-                        block_param_vtxs,
-                    );
-                    self.genv.register_box(box_id, Box::new(block_box));
+                // Receiver-less calls only reach user-defined methods, typed through `yield`
+                if let Some(recv_vtx) = recv_vtx {
+                    if !block_param_vtxs.is_empty() {
+                        let box_id = self.genv.alloc_box_id();
+                        let block_box = BlockParameterTypeBox::new(
+                            box_id,
+                            recv_vtx,
+                            method_name.clone(),
+                            block_param_vtxs,
+                        )
+                        .with_destructured(destructured_params);
+                        self.genv.register_box(box_id, Box::new(block_box));
+                    }
                 }
 
-                Some(finish_method_call(
+                // raise/fail never return
+                let raises = recv_vtx.is_none() && matches!(method_name.as_str(), "raise" | "fail");
+
+                let ret_vtx = finish_method_call(
                     self.genv,
                     recv_vtx,
                     method_name,
+                    arg_vtxs,
+                    block_vtx,
                     location,
-                ))
+                    receiver_location,
+                );
+
+                if let Some(break_vtx) = break_vtx {
+                    self.changes.add_edge(break_vtx, ret_vtx);
+                }
+                if raises {
+                    self.terminated = true;
+                }
+
+                Some(ret_vtx)
+            }
+            NeedsChildKind::Yield { args, location } => {
+                let arg_vtxs: Vec<VertexId> = args
+                    .iter()
+                    .map(|arg| {
+                        self.install_node(arg)
+                            .unwrap_or_else(|| self.genv.new_vertex())
+                    })
+                    .collect();
+                finish_yield(self.genv, arg_vtxs, location)
             }
         }
     }
@@ -193,13 +504,19 @@ impl<'a> AstInstaller<'a> {
     /// Install class definition
     fn install_class_node(&mut self, class_node: &ruby_prism::ClassNode) -> Option<VertexId> {
         let class_name = extract_class_name(class_node);
-        install_class(self.genv, class_name);
+        let superclass = extract_superclass_name(class_node);
+        install_class(self.genv, class_name, superclass);
 
+        let outer_terminated = std::mem::replace(&mut self.terminated, false);
+        let outer_delegations = std::mem::take(&mut self.pending_delegations);
         if let Some(body) = class_node.body() {
             if let Some(statements) = body.as_statements_node() {
                 self.install_statements(&statements);
             }
         }
+        self.finish_delegations();
+        self.pending_delegations = outer_delegations;
+        self.terminated = outer_terminated;
 
         exit_scope(self.genv);
         None
@@ -210,81 +527,950 @@ impl<'a> AstInstaller<'a> {
         let module_name = extract_module_name(module_node);
         install_module(self.genv, module_name);
 
+        let outer_terminated = std::mem::replace(&mut self.terminated, false);
+        let outer_delegations = std::mem::take(&mut self.pending_delegations);
         if let Some(body) = module_node.body() {
             if let Some(statements) = body.as_statements_node() {
                 self.install_statements(&statements);
             }
         }
+        self.finish_delegations();
+        self.pending_delegations = outer_delegations;
+        self.terminated = outer_terminated;
 
         exit_scope(self.genv);
         None
     }
 
+    /// Install a method defined by `alias_method`, `define_method` or a delegation
+    ///
+    /// Returns the value of the call when it replaces the ordinary call
+    /// (`define_method` with a literal block returns the method name).
+    fn install_dynamic_definition(
+        &mut self,
+        definition: DynamicDefinition,
+        block: Option<&Node>,
+        location: &SourceLocation,
+    ) -> Option<VertexId> {
+        match definition {
+            DynamicDefinition::Alias { new_name, old_name } => {
+                self.genv.register_alias(&new_name, &old_name);
+                None
+            }
+            DynamicDefinition::DefineMethod { name } => {
+                let Some(block_node) = block.and_then(|block| block.as_block_node()) else {
+                    install_untyped_method(self.genv, &name);
+                    return None;
+                };
+                self.install_define_method(&name, &block_node);
+                Some(self.genv.new_source(Type::symbol()))
+            }
+            DynamicDefinition::Delegation { target, methods } => {
+                for method in methods {
+                    let return_vtx = install_delegated_method(self.genv, &method.name);
+                    self.pending_delegations.push(PendingDelegation {
+                        target: target.clone(),
+                        target_method: method.target_method,
+                        return_vtx,
+                        location: location.clone(),
+                    });
+                }
+                None
+            }
+        }
+    }
+
+    /// Install `define_method(:name) { |args| ... }`
+    ///
+    /// The block is the method body: its parameters are the method's
+    /// parameters and its value (or `return`/`next`) is the return value.
+    fn install_define_method(&mut self, name: &str, block_node: &ruby_prism::BlockNode) {
+        let recv_ty = self.genv.current_self_type();
+        let block_vtx = self.genv.new_vertex();
+        let return_vtx = self.genv.new_vertex();
+        self.genv
+            .register_user_method(recv_ty.clone(), name, block_vtx, return_vtx);
+
+        let targets = JumpTargets {
+            next_vtx: Some(return_vtx),
+            break_vtx: Some(return_vtx),
+            return_vtx: Some(return_vtx),
+        };
+        let (param_vtxs, _, _) = self.install_block_body(
+            block_node.parameters(),
+            block_node.body(),
+            return_vtx,
+            targets,
+        );
+        self.genv
+            .register_method_parameters(recv_ty, name, param_vtxs);
+    }
+
+    /// Forward the delegated methods of the current class body to their targets
+    fn finish_delegations(&mut self) {
+        for delegation in std::mem::take(&mut self.pending_delegations) {
+            finish_delegation(self.genv, delegation);
+        }
+    }
+
+    /// Install `super(args)`
+    ///
+    /// Without a block, the block passed to the enclosing method is forwarded.
+    fn install_super_node(&mut self, super_node: &ruby_prism::SuperNode) -> Option<VertexId> {
+        let arg_vtxs: Vec<VertexId> = super_node
+            .arguments()
+            .map(|args_node| {
+                args_node
+                    .arguments()
+                    .iter()
+                    .map(|arg| {
+                        self.install_node(&arg)
+                            .unwrap_or_else(|| self.genv.new_vertex())
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let (block_vtx, break_vtx) = match super_node.block() {
+            Some(block) => self.install_super_block(&block),
+            None => (None, None),
+        };
+
+        let location = SourceLocation::from_prism_location_with_index(
+            &super_node.location(),
+            &self.line_index,
+        );
+        let ret_vtx = install_super_call(self.genv, arg_vtxs, block_vtx, Some(location));
+        if let Some(break_vtx) = break_vtx {
+            self.changes.add_edge(break_vtx, ret_vtx);
+        }
+        Some(ret_vtx)
+    }
+
+    /// Install implicit `super`, forwarding the enclosing method's positional parameters
+    fn install_forwarding_super_node(
+        &mut self,
+        forwarding_super: &ruby_prism::ForwardingSuperNode,
+    ) -> Option<VertexId> {
+        let arg_vtxs = self.genv.current_method_param_vertices();
+        let (block_vtx, break_vtx) = match forwarding_super.block() {
+            Some(block) => self.install_super_block(&block.as_node()),
+            None => (None, None),
+        };
+
+        let location = SourceLocation::from_prism_location_with_index(
+            &forwarding_super.location(),
+            &self.line_index,
+        );
+        let ret_vtx = install_super_call(self.genv, arg_vtxs, block_vtx, Some(location));
+        if let Some(break_vtx) = break_vtx {
+            self.changes.add_edge(break_vtx, ret_vtx);
+        }
+        Some(ret_vtx)
+    }
+
+    /// Install the block given to `super`
+    ///
+    /// Returns the block's vertex and, for a literal block, the vertex of its `break` values.
+    fn install_super_block(&mut self, block: &Node) -> (Option<VertexId>, Option<VertexId>) {
+        if let Some(bn) = block.as_block_node() {
+            let break_vtx = self.genv.new_vertex();
+            let (_, _, proc_vtx) = self.install_block_node_with_params(&bn, break_vtx);
+            return (Some(proc_vtx), Some(break_vtx));
+        }
+
+        // `&blk` passes a Proc; `&:sym` is a Symbol and not a Proc value
+        let block_vtx = block
+            .as_block_argument_node()
+            .and_then(|block_arg| block_arg.expression())
+            .filter(|expression| expression.as_symbol_node().is_none())
+            .and_then(|expression| self.install_node(&expression));
+        (block_vtx, None)
+    }
+
     /// Install method definition
     fn install_def_node(&mut self, def_node: &ruby_prism::DefNode) -> Option<VertexId> {
         let method_name = String::from_utf8_lossy(def_node.name().as_slice()).to_string();
-        install_method(self.genv, method_name);
+        let singleton = def_node.receiver().is_some();
+        let vertices = install_method(self.genv, method_name.clone(), singleton);
+
+        // `return` inside the body (and its blocks) returns from this method
+        let outer_terminated = std::mem::replace(&mut self.terminated, false);
+        let outer_targets = std::mem::replace(
+            &mut self.jump_targets,
+            JumpTargets {
+                return_vtx: Some(vertices.return_vtx),
+                ..JumpTargets::default()
+            },
+        );
 
         // Process parameters BEFORE processing body
         // This ensures parameters are available as local variables in the method body
+        // Positional parameters are forwarded by implicit `super`
         if let Some(params_node) = def_node.parameters() {
-            self.install_parameters(&params_node);
+            let param_vtxs = self.install_parameters(&params_node, vertices.block_vtx);
+            let recv_ty = self.genv.current_self_type();
+            self.genv
+                .register_method_parameters(recv_ty, &method_name, param_vtxs);
         }
 
-        if let Some(body) = def_node.body() {
-            if let Some(statements) = body.as_statements_node() {
-                self.install_statements(&statements);
+        // The value of the last statement is returned, unless the body ended with a jump
+        // An endless method `def foo = expr` has a single expression body
+        let body_vtx = def_node
+            .body()
+            .and_then(|body| match body.as_statements_node() {
+                Some(statements) => self.install_statements(&statements),
+                None => self.install_node(&body),
+            });
+        if let Some(body_vtx) = body_vtx {
+            if !self.terminated {
+                self.changes.add_edge(body_vtx, vertices.return_vtx);
             }
         }
 
+        self.jump_targets = outer_targets;
+        self.terminated = outer_terminated;
+
         exit_scope(self.genv);
         None
     }
 
+    /// Install if/elsif/else, modifier `if` and ternary `cond ? a : b`
+    ///
+    /// The value is the union of the branch values (nil without else).
+    /// Locals tested by the condition are narrowed in each branch.
+    fn install_if_node(&mut self, if_node: &ruby_prism::IfNode) -> Option<VertexId> {
+        let predicate = if_node.predicate();
+        self.install_node(&predicate);
+        let narrowing = condition_narrowing(&predicate);
+
+        let then_branch = if_node.statements().map(|statements| statements.as_node());
+        self.install_narrowed_branches(vec![
+            (then_branch, narrowing.when_true),
+            (if_node.subsequent(), narrowing.when_false),
+        ])
+    }
+
+    /// Install unless/else and modifier `unless`
+    fn install_unless_node(&mut self, unless_node: &ruby_prism::UnlessNode) -> Option<VertexId> {
+        let predicate = unless_node.predicate();
+        self.install_node(&predicate);
+        let narrowing = condition_narrowing(&predicate);
+
+        let then_branch = unless_node
+            .statements()
+            .map(|statements| statements.as_node());
+        let else_branch = unless_node
+            .else_clause()
+            .map(|else_node| else_node.as_node());
+        self.install_narrowed_branches(vec![
+            (then_branch, narrowing.when_false),
+            (else_branch, narrowing.when_true),
+        ])
+    }
+
+    /// Install case/when: each `when` body and the else clause are branches
+    ///
+    /// `case x` with `when SomeClass` narrows `x` in the branches.
+    fn install_case_node(&mut self, case_node: &ruby_prism::CaseNode) -> Option<VertexId> {
+        let predicate = case_node.predicate();
+        if let Some(predicate) = &predicate {
+            self.install_node(predicate);
+        }
+
+        let mut bodies = Vec::new();
+        let mut when_conditions = Vec::new();
+        for condition in case_node.conditions().iter() {
+            if let Some(when_node) = condition.as_when_node() {
+                let conditions: Vec<Node> = when_node.conditions().iter().collect();
+                for when_condition in &conditions {
+                    self.install_node(when_condition);
+                }
+                when_conditions.push(conditions);
+                bodies.push(
+                    when_node
+                        .statements()
+                        .map(|statements| statements.as_node()),
+                );
+            }
+        }
+
+        let (when_filters, else_filters) = when_narrowings(predicate.as_ref(), &when_conditions);
+        let mut branches: Vec<_> = bodies.into_iter().zip(when_filters).collect();
+        branches.push((
+            case_node.else_clause().map(|else_node| else_node.as_node()),
+            else_filters,
+        ));
+
+        self.install_narrowed_branches(branches)
+    }
+
+    /// Install branches with locals narrowed by the tests guarding them
+    ///
+    /// A missing branch (`None`) is an empty path evaluating to nil. When a
+    /// single branch reaches the join point (a guard clause like
+    /// `return unless x.is_a?(String)`), its narrowing stays after the conditional.
+    fn install_narrowed_branches(
+        &mut self,
+        branches: Vec<(Option<Node>, LocalFilters)>,
+    ) -> Option<VertexId> {
+        let mut reaching = Vec::new();
+
+        let value_vtx = self.install_alternatives(branches, |installer, (body, filters)| {
+            let narrowed = narrow_locals(installer.genv, &filters);
+            let value_vtx = match body {
+                Some(body) => installer.install_branch_body(&body),
+                None => Some(installer.genv.new_source(Type::Nil)),
+            };
+            restore_narrowed_locals(installer.genv, &narrowed);
+
+            if !installer.terminated {
+                reaching.push(narrowed);
+            }
+            value_vtx
+        });
+
+        if let [narrowed] = reaching.as_slice() {
+            keep_narrowed_locals(self.genv, narrowed);
+        }
+        value_vtx
+    }
+
+    /// Install case/in: each `in` clause binds its pattern's captures, then runs its body
+    ///
+    /// Without an else clause, an unmatched value raises NoMatchingPatternError,
+    /// so there is no nil branch.
+    fn install_case_match_node(
+        &mut self,
+        case_match_node: &ruby_prism::CaseMatchNode,
+    ) -> Option<VertexId> {
+        let value_vtx = match case_match_node.predicate() {
+            Some(predicate) => self.install_value(&predicate),
+            None => self.genv.new_vertex(),
+        };
+
+        let mut branches = Vec::new();
+        for condition in case_match_node.conditions().iter() {
+            if let Some(in_node) = condition.as_in_node() {
+                let body = in_node.statements().map(|statements| statements.as_node());
+                branches.push((Some(in_node.pattern()), body));
+            }
+        }
+        if let Some(else_clause) = case_match_node.else_clause() {
+            branches.push((None, Some(else_clause.as_node())));
+        }
+
+        self.install_alternatives(branches, |installer, (pattern, body)| {
+            if let Some(pattern) = pattern {
+                installer.install_pattern(&pattern, value_vtx);
+            }
+            match body {
+                Some(body) => installer.install_branch_body(&body),
+                None => Some(installer.genv.new_source(Type::Nil)),
+            }
+        })
+    }
+
+    /// Install a pattern matched against `value_vtx` and bind its captures
+    ///
+    /// Captures are typed by the pattern's constant checks (`String => s`)
+    /// and by the element types of array and hash patterns.
+    fn install_pattern(&mut self, pattern: &Node, value_vtx: VertexId) {
+        // in x
+        if let Some(target) = pattern.as_local_variable_target_node() {
+            self.bind_pattern_target(&target, value_vtx);
+            return;
+        }
+
+        // in String => s
+        if let Some(capture) = pattern.as_capture_pattern_node() {
+            let captured_vtx = self.install_pattern_value(&capture.value(), value_vtx);
+            self.install_pattern(&capture.value(), value_vtx);
+            self.bind_pattern_target(&capture.target(), captured_vtx);
+            return;
+        }
+
+        // in [Integer => a, *rest], in Point[x, y]
+        if let Some(array_pattern) = pattern.as_array_pattern_node() {
+            let array_vtx = self.install_pattern_value(pattern, value_vtx);
+            for (i, required) in array_pattern.requireds().iter().enumerate() {
+                let element_vtx =
+                    install_element_value(self.genv, array_vtx, ElementAccess::Index(i));
+                self.install_pattern(&required, element_vtx);
+            }
+            if let Some(rest) = array_pattern.rest() {
+                self.install_rest_pattern(&rest, array_vtx, ElementAccess::Rest);
+            }
+            // Positions of post-rest elements depend on the array size
+            for post in array_pattern.posts().iter() {
+                let element_vtx = install_element_value(self.genv, array_vtx, ElementAccess::Any);
+                self.install_pattern(&post, element_vtx);
+            }
+            return;
+        }
+
+        // in [*, Integer => x, *]
+        if let Some(find_pattern) = pattern.as_find_pattern_node() {
+            let array_vtx = self.install_pattern_value(pattern, value_vtx);
+            self.install_rest_pattern(
+                &find_pattern.left().as_node(),
+                array_vtx,
+                ElementAccess::Rest,
+            );
+            for required in find_pattern.requireds().iter() {
+                let element_vtx = install_element_value(self.genv, array_vtx, ElementAccess::Any);
+                self.install_pattern(&required, element_vtx);
+            }
+            self.install_rest_pattern(&find_pattern.right(), array_vtx, ElementAccess::Rest);
+            return;
+        }
+
+        // in {name: String => n, age:}
+        if let Some(hash_pattern) = pattern.as_hash_pattern_node() {
+            let hash_vtx = self.install_pattern_value(pattern, value_vtx);
+            for element in hash_pattern.elements().iter() {
+                if let Some(assoc) = element.as_assoc_node() {
+                    let element_vtx =
+                        install_element_value(self.genv, hash_vtx, ElementAccess::HashValue);
+                    let value = assoc.value();
+                    match value.as_implicit_node() {
+                        // {age:} binds `age`
+                        Some(implicit) => self.install_pattern(&implicit.value(), element_vtx),
+                        None => self.install_pattern(&value, element_vtx),
+                    }
+                }
+            }
+            if let Some(rest) = hash_pattern.rest() {
+                self.install_rest_pattern(&rest, hash_vtx, ElementAccess::Rest);
+            }
+            return;
+        }
+
+        // in Integer | Float
+        if let Some(alternation) = pattern.as_alternation_pattern_node() {
+            self.install_pattern(&alternation.left(), value_vtx);
+            self.install_pattern(&alternation.right(), value_vtx);
+            return;
+        }
+
+        // in x if cond, in x unless cond
+        if let Some(if_node) = pattern.as_if_node() {
+            if let Some(statements) = if_node.statements() {
+                for inner in statements.body().iter() {
+                    self.install_pattern(&inner, value_vtx);
+                }
+            }
+            self.install_node(&if_node.predicate());
+            return;
+        }
+
+        if let Some(unless_node) = pattern.as_unless_node() {
+            if let Some(statements) = unless_node.statements() {
+                for inner in statements.body().iter() {
+                    self.install_pattern(&inner, value_vtx);
+                }
+            }
+            self.install_node(&unless_node.predicate());
+            return;
+        }
+
+        // in ^x
+        if let Some(pinned) = pattern.as_pinned_variable_node() {
+            self.install_node(&pinned.variable());
+            return;
+        }
+
+        // in ^(expr)
+        if let Some(pinned) = pattern.as_pinned_expression_node() {
+            self.install_node(&pinned.expression());
+            return;
+        }
+
+        // Value patterns: constants, literals, ranges
+        self.install_node(pattern);
+    }
+
+    /// Get the vertex of the value matched by a pattern
+    ///
+    /// - constant checks narrow the value (`String`, `Point[x, y]`, `[x, y]` is an Array)
+    /// - `in Integer | Float` joins the alternatives
+    /// - literal patterns have the literal's type (`in 1 => n`)
+    /// - other patterns keep the value as is
+    fn install_pattern_value(&mut self, pattern: &Node, value_vtx: VertexId) -> VertexId {
+        if let Some(class_name) = pattern_class_name(pattern) {
+            return install_narrowed_value(self.genv, value_vtx, TypeFilter::Class(class_name));
+        }
+
+        if let Some(alternation) = pattern.as_alternation_pattern_node() {
+            let left_vtx = self.install_pattern_value(&alternation.left(), value_vtx);
+            let right_vtx = self.install_pattern_value(&alternation.right(), value_vtx);
+            return install_branch_values(self.genv, &mut self.changes, vec![left_vtx, right_vtx]);
+        }
+
+        if pattern.as_range_node().is_none() {
+            if let Some(literal_vtx) = install_literal(self.genv, pattern) {
+                return literal_vtx;
+            }
+        }
+
+        value_vtx
+    }
+
+    /// Install a splat of an array, find or hash pattern (`*rest`, `**opts`)
+    fn install_rest_pattern(&mut self, rest: &Node, value_vtx: VertexId, access: ElementAccess) {
+        let target = if let Some(splat) = rest.as_splat_node() {
+            splat.expression()
+        } else if let Some(assoc_splat) = rest.as_assoc_splat_node() {
+            // `**opts` captures the remaining pairs as a Hash
+            if let Some(target) = assoc_splat
+                .value()
+                .and_then(|value| value.as_local_variable_target_node())
+            {
+                self.bind_pattern_target(&target, value_vtx);
+            }
+            return;
+        } else {
+            None
+        };
+
+        if let Some(target) = target.and_then(|target| target.as_local_variable_target_node()) {
+            let rest_vtx = install_element_value(self.genv, value_vtx, access);
+            self.bind_pattern_target(&target, rest_vtx);
+        }
+    }
+
+    /// Bind a local variable captured by a pattern
+    fn bind_pattern_target(
+        &mut self,
+        target: &ruby_prism::LocalVariableTargetNode,
+        value_vtx: VertexId,
+    ) {
+        let name = String::from_utf8_lossy(target.name().as_slice()).to_string();
+        finish_local_var_write(self.genv, self.lenv, &mut self.changes, name, value_vtx);
+    }
+
+    /// Install an expression whose value is needed, untyped if it can't be typed
+    fn install_value(&mut self, node: &Node) -> VertexId {
+        self.install_node(node)
+            .unwrap_or_else(|| self.genv.new_vertex())
+    }
+
+    /// Install alternative branches of a conditional and join them
+    ///
+    /// Each branch starts from the local variable bindings before the
+    /// conditional. A missing branch (`None`) is an empty path evaluating to nil.
+    fn install_branches(&mut self, branches: Vec<Option<Node>>) -> Option<VertexId> {
+        self.install_alternatives(branches, |installer, branch| match branch {
+            Some(body) => installer.install_branch_body(&body),
+            None => Some(installer.genv.new_source(Type::Nil)),
+        })
+    }
+
+    /// Install alternative paths with `install_branch` and join them
+    ///
+    /// Each path starts from the local variable bindings before the first one.
+    /// Paths ending with a jump never reach the join point; if all of them
+    /// do, the conditional itself terminates the current path.
+    fn install_alternatives<T>(
+        &mut self,
+        branches: Vec<T>,
+        mut install_branch: impl FnMut(&mut Self, T) -> Option<VertexId>,
+    ) -> Option<VertexId> {
+        let before = snapshot_locals(self.genv);
+        let mut branch_locals = Vec::new();
+        let mut value_vtxs = Vec::new();
+        let mut all_terminated = true;
+
+        for branch in branches {
+            self.terminated = false;
+            let value_vtx = install_branch(self, branch);
+
+            if !self.terminated {
+                all_terminated = false;
+                value_vtxs.extend(value_vtx);
+                branch_locals.push(snapshot_locals(self.genv));
+            }
+            restore_locals(self.genv, &before);
+        }
+
+        self.terminated = all_terminated;
+        merge_branch_locals(self.genv, self.lenv, &mut self.changes, &branch_locals);
+        Some(install_branch_values(
+            self.genv,
+            &mut self.changes,
+            value_vtxs,
+        ))
+    }
+
+    /// Install begin/rescue/else/ensure
+    ///
+    /// The value is the union of the main body (or the else clause, which runs
+    /// after it) and the rescue bodies. A rescue may start after any statement
+    /// of the body, so it sees the bindings from before and after the body.
+    /// The ensure clause runs on every path.
+    fn install_begin_node(&mut self, begin_node: &ruby_prism::BeginNode) -> Option<VertexId> {
+        let before = snapshot_locals(self.genv);
+        let mut branch_locals = Vec::new();
+        let mut value_vtxs = Vec::new();
+
+        let mut body_vtx = begin_node
+            .statements()
+            .and_then(|statements| self.install_statements(&statements));
+        let body_locals = snapshot_locals(self.genv);
+
+        // Exceptions raised in the else clause are not rescued
+        if let Some(else_clause) = begin_node.else_clause() {
+            if !self.terminated {
+                body_vtx = self.install_branch_body(&else_clause.as_node());
+            }
+        }
+
+        let mut all_terminated = self.terminated;
+        if !self.terminated {
+            value_vtxs.extend(body_vtx);
+            branch_locals.push(snapshot_locals(self.genv));
+        }
+
+        restore_locals(self.genv, &before);
+        if begin_node.rescue_clause().is_some() {
+            merge_branch_locals(
+                self.genv,
+                self.lenv,
+                &mut self.changes,
+                &[before, body_locals],
+            );
+        }
+        let rescue_entry = snapshot_locals(self.genv);
+
+        let mut rescue_clause = begin_node.rescue_clause();
+        while let Some(rescue_node) = rescue_clause {
+            self.terminated = false;
+            let value_vtx = self.install_rescue_clause(&rescue_node);
+
+            if !self.terminated {
+                all_terminated = false;
+                value_vtxs.extend(value_vtx);
+                branch_locals.push(snapshot_locals(self.genv));
+            }
+            restore_locals(self.genv, &rescue_entry);
+            rescue_clause = rescue_node.subsequent();
+        }
+
+        self.terminated = all_terminated;
+        merge_branch_locals(self.genv, self.lenv, &mut self.changes, &branch_locals);
+
+        if let Some(statements) = begin_node
+            .ensure_clause()
+            .and_then(|ensure| ensure.statements())
+        {
+            let terminated = std::mem::replace(&mut self.terminated, false);
+            self.install_statements(&statements);
+            self.terminated |= terminated;
+        }
+
+        Some(install_branch_values(
+            self.genv,
+            &mut self.changes,
+            value_vtxs,
+        ))
+    }
+
+    /// Install a `rescue` clause: bind the exception and install the body
+    fn install_rescue_clause(&mut self, rescue_node: &ruby_prism::RescueNode) -> Option<VertexId> {
+        let exceptions: Vec<Node> = rescue_node.exceptions().iter().collect();
+        for exception in &exceptions {
+            self.install_node(exception);
+        }
+
+        // rescue => e, rescue => @error
+        if let Some(reference) = rescue_node.reference() {
+            let exception_types = rescued_exception_types(self.genv, &exceptions);
+            let exception_vtx =
+                install_exception_value(self.genv, &mut self.changes, exception_types);
+
+            if let Some(target) = reference.as_local_variable_target_node() {
+                let name = String::from_utf8_lossy(target.name().as_slice()).to_string();
+                finish_local_var_write(
+                    self.genv,
+                    self.lenv,
+                    &mut self.changes,
+                    name,
+                    exception_vtx,
+                );
+            } else if let Some(target) = reference.as_instance_variable_target_node() {
+                let name = String::from_utf8_lossy(target.name().as_slice()).to_string();
+                finish_ivar_write(self.genv, name, exception_vtx);
+            }
+        }
+
+        match rescue_node.statements() {
+            Some(statements) => self.install_statements(&statements),
+            None => Some(self.genv.new_source(Type::Nil)),
+        }
+    }
+
+    /// Install the body of a branch: statements, an else clause or an elsif
+    fn install_branch_body(&mut self, body: &Node) -> Option<VertexId> {
+        if let Some(statements) = body.as_statements_node() {
+            self.install_statements(&statements)
+        } else if let Some(else_node) = body.as_else_node() {
+            match else_node.statements() {
+                Some(statements) => self.install_statements(&statements),
+                None => Some(self.genv.new_source(Type::Nil)),
+            }
+        } else {
+            self.install_node(body)
+        }
+    }
+
+    /// Install `a && b` / `a || b`: the right side is evaluated conditionally
+    ///
+    /// `narrowing.when_true` holds while the right side runs (`x && x.upcase`),
+    /// `narrowing.when_false` when the left side alone is the result.
+    fn install_short_circuit(
+        &mut self,
+        left: &Node,
+        right: &Node,
+        narrowing: Narrowing,
+    ) -> Option<VertexId> {
+        let left_vtx = self.install_node(left);
+
+        let before = snapshot_locals(self.genv);
+        let narrowed = narrow_locals(self.genv, &narrowing.when_true);
+        let right_vtx = self.install_node(right);
+        restore_narrowed_locals(self.genv, &narrowed);
+        let after = snapshot_locals(self.genv);
+        restore_locals(self.genv, &before);
+
+        // `x || return`: only the left side reaches the join point
+        if self.terminated {
+            self.terminated = false;
+            narrow_locals(self.genv, &narrowing.when_false);
+            return left_vtx;
+        }
+
+        merge_branch_locals(self.genv, self.lenv, &mut self.changes, &[before, after]);
+
+        let value_vtxs = left_vtx.into_iter().chain(right_vtx).collect();
+        Some(install_branch_values(
+            self.genv,
+            &mut self.changes,
+            value_vtxs,
+        ))
+    }
+
+    /// Install while/until: the body runs zero or more times
+    ///
+    /// The value is nil, or a value passed to `break`.
+    fn install_loop(
+        &mut self,
+        predicate: &Node,
+        statements: Option<ruby_prism::StatementsNode>,
+    ) -> Option<VertexId> {
+        let value_vtx = self.genv.new_vertex();
+        let nil_vtx = self.genv.new_source(Type::Nil);
+        self.changes.add_edge(nil_vtx, value_vtx);
+
+        let headers = enter_loop(self.genv, &mut self.changes);
+        let outer_targets = self.enter_loop_targets(value_vtx);
+
+        // The predicate is evaluated before each iteration
+        self.install_node(predicate);
+        if let Some(statements) = statements {
+            self.install_statements(&statements);
+        }
+
+        self.jump_targets = outer_targets;
+        self.terminated = false;
+        exit_loop(self.genv, self.lenv, &mut self.changes, &headers);
+
+        Some(value_vtx)
+    }
+
+    /// Set the jump targets for the body of while/until/for
+    ///
+    /// `break` flows into the loop value, `next` only skips to the next
+    /// iteration, and `return` still returns from the enclosing method.
+    /// Returns the previous targets.
+    fn enter_loop_targets(&mut self, value_vtx: VertexId) -> JumpTargets {
+        let return_vtx = self.jump_targets.return_vtx;
+        std::mem::replace(
+            &mut self.jump_targets,
+            JumpTargets {
+                next_vtx: None,
+                break_vtx: Some(value_vtx),
+                return_vtx,
+            },
+        )
+    }
+
+    /// Install `for x in collection`
+    ///
+    /// Like `collection.each { |x| ... }`, but without a new scope: the index
+    /// and body locals remain visible after the loop.
+    fn install_for_node(&mut self, for_node: &ruby_prism::ForNode) -> Option<VertexId> {
+        let collection_vtx = self.install_node(&for_node.collection());
+
+        // for evaluates to the collection, or a value passed to `break`
+        let value_vtx = self.genv.new_vertex();
+        if let Some(collection_vtx) = collection_vtx {
+            self.changes.add_edge(collection_vtx, value_vtx);
+        }
+
+        let headers = enter_loop(self.genv, &mut self.changes);
+        let outer_targets = self.enter_loop_targets(value_vtx);
+
+        // Index variable typed from the collection's `each` block parameter
+        if let Some(target) = for_node.index().as_local_variable_target_node() {
+            let name = String::from_utf8_lossy(target.name().as_slice()).to_string();
+            let element_vtx = self.genv.new_vertex();
+            finish_local_var_write(self.genv, self.lenv, &mut self.changes, name, element_vtx);
+
+            if let Some(collection_vtx) = collection_vtx {
+                let box_id = self.genv.alloc_box_id();
+                let block_box = BlockParameterTypeBox::new(
+                    box_id,
+                    collection_vtx,
+                    "each".to_string(),
+                    vec![element_vtx],
+                );
+                self.genv.register_box(box_id, Box::new(block_box));
+            }
+        }
+
+        if let Some(statements) = for_node.statements() {
+            self.install_statements(&statements);
+        }
+
+        self.jump_targets = outer_targets;
+        self.terminated = false;
+        exit_loop(self.genv, self.lenv, &mut self.changes, &headers);
+
+        Some(value_vtx)
+    }
+
     /// Install block node
     ///
     /// Processes blocks like `{ |x| x.to_s }` or `do |item| item.upcase end`
+    /// and returns the block as a Proc value
     fn install_block_node(&mut self, block_node: &ruby_prism::BlockNode) -> Option<VertexId> {
-        // Use the version that collects param vtxs, but discard them
-        self.install_block_node_with_params(block_node);
-        None
+        // `next` and `break` both end the proc with a value
+        let ret_vtx = self.genv.new_vertex();
+        let targets = JumpTargets {
+            next_vtx: Some(ret_vtx),
+            break_vtx: Some(ret_vtx),
+            return_vtx: self.jump_targets.return_vtx,
+        };
+        let (_, _, proc_vtx) =
+            self.install_block_body(block_node.parameters(), block_node.body(), ret_vtx, targets);
+        Some(proc_vtx)
+    }
+
+    /// Install lambda literal: `->(x) { x.to_s }`
+    ///
+    /// `return` inside a lambda returns from the lambda itself.
+    fn install_lambda_node(&mut self, lambda_node: &ruby_prism::LambdaNode) -> Option<VertexId> {
+        let ret_vtx = self.genv.new_vertex();
+        let targets = JumpTargets {
+            next_vtx: Some(ret_vtx),
+            break_vtx: Some(ret_vtx),
+            return_vtx: Some(ret_vtx),
+        };
+        let (_, _, proc_vtx) = self.install_block_body(
+            lambda_node.parameters(),
+            lambda_node.body(),
+            ret_vtx,
+            targets,
+        );
+        Some(proc_vtx)
     }
 
-    /// Install block node and return block parameter vertex IDs
+    /// Install block node and return block parameter vertex IDs, destructured
+    /// parameters and the Proc vertex
     ///
     /// This is used when processing method calls with blocks to collect
     /// the block parameter vertices for type inference via BlockParameterTypeBox.
+    /// `break_vtx` receives the values passed to `break` (the result of the call).
     fn install_block_node_with_params(
         &mut self,
         block_node: &ruby_prism::BlockNode,
-    ) -> Vec<VertexId> {
+        break_vtx: VertexId,
+    ) -> (Vec<VertexId>, Vec<DestructuredParameter>, VertexId) {
+        let ret_vtx = self.genv.new_vertex();
+        let targets = JumpTargets {
+            next_vtx: Some(ret_vtx),
+            break_vtx: Some(break_vtx),
+            return_vtx: self.jump_targets.return_vtx,
+        };
+        self.install_block_body(block_node.parameters(), block_node.body(), ret_vtx, targets)
+    }
+
+    /// Install parameters and body of a block or lambda in a new block scope
+    ///
+    /// The body's value flows into `ret_vtx`, the Proc's return vertex.
+    /// Returns the parameter vertex IDs, the destructured parameters and the
+    /// Proc vertex for the block.
+    fn install_block_body(
+        &mut self,
+        params: Option<Node>,
+        body: Option<Node>,
+        ret_vtx: VertexId,
+        targets: JumpTargets,
+    ) -> (Vec<VertexId>, Vec<DestructuredParameter>, VertexId) {
+        // A block may run zero or more times, so enclosing locals join like a loop
+        let headers = enter_loop(self.genv, &mut self.changes);
+        let outer_targets = std::mem::replace(&mut self.jump_targets, targets);
+        let outer_terminated = std::mem::replace(&mut self.terminated, false);
+
         // Enter block scope
         enter_block_scope(self.genv);
 
         let mut param_vtxs = Vec::new();
+        let mut destructured = Vec::new();
 
         // Process block parameters BEFORE processing body
-        // block_node.parameters() returns Option<Node>, need to convert to BlockParametersNode
-        if let Some(params_node) = block_node.parameters() {
+        // parameters() returns Option<Node>: explicit `|x|`, numbered `_1` or `it`
+        if let Some(params_node) = params {
             if let Some(block_params) = params_node.as_block_parameters_node() {
-                param_vtxs = self.install_block_parameters_with_vtxs(&block_params);
+                param_vtxs =
+                    self.install_block_parameters_with_vtxs(&block_params, &mut destructured);
+            } else if let Some(numbered_params) = params_node.as_numbered_parameters_node() {
+                // { _1 + _2 }
+                param_vtxs =
+                    install_numbered_parameters(self.genv, self.lenv, numbered_params.maximum());
+            } else if params_node.as_it_parameters_node().is_some() {
+                // { it.upcase } (Ruby 3.4)
+                let vtx = install_block_parameter(self.genv, self.lenv, "it".to_string());
+                param_vtxs = vec![vtx];
             }
         }
 
         // Process block body
-        if let Some(body) = block_node.body() {
+        let body_vtx = body.and_then(|body| {
             if let Some(statements) = body.as_statements_node() {
-                self.install_statements(&statements);
+                self.install_statements(&statements)
             } else {
                 // Single expression body
-                self.install_node(&body);
+                self.install_node(&body)
             }
-        }
+        });
+
+        // A body ending with a jump has already sent its value
+        let body_vtx = body_vtx.filter(|_| !self.terminated);
+        self.jump_targets = outer_targets;
+        self.terminated = outer_terminated;
 
         // Exit block scope
         exit_block_scope(self.genv);
-
-        param_vtxs
+        exit_loop(self.genv, self.lenv, &mut self.changes, &headers);
+
+        let proc_vtx = install_proc(
+            self.genv,
+            &mut self.changes,
+            param_vtxs.clone(),
+            ret_vtx,
+            body_vtx,
+        );
+        (param_vtxs, destructured, proc_vtx)
     }
 
     /// Install block parameters as local variables
@@ -294,28 +1480,27 @@ impl<'a> AstInstaller<'a> {
     #[allow(dead_code)]
     fn install_block_parameters(&mut self, block_params: &ruby_prism::BlockParametersNode) {
         // Just call the version that returns vtxs and discard the result
-        self.install_block_parameters_with_vtxs(block_params);
+        self.install_block_parameters_with_vtxs(block_params, &mut Vec::new());
     }
 
     /// Install block parameters and return their vertex IDs
     ///
     /// This version is used when we need to track the block parameter vertices
     /// for type inference from the method's RBS block signature.
+    /// Destructured parameters like `|(k, v), i|` are collected into `destructured`.
     fn install_block_parameters_with_vtxs(
         &mut self,
         block_params: &ruby_prism::BlockParametersNode,
+        destructured: &mut Vec<DestructuredParameter>,
     ) -> Vec<VertexId> {
         let mut vtxs = Vec::new();
 
         // BlockParametersNode contains a ParametersNode
         if let Some(params) = block_params.parameters() {
-            // Process required parameters (most common in blocks)
+            // Process required parameters (most common in blocks): |x| or |(k, v)|
             for node in params.requireds().iter() {
-                if let Some(req_param) = node.as_required_parameter_node() {
-                    let name = String::from_utf8_lossy(req_param.name().as_slice()).to_string();
-                    let vtx = install_block_parameter(self.genv, self.lenv, name);
-                    vtxs.push(vtx);
-                }
+                let vtx = self.install_block_parameter_target(&node, destructured);
+                vtxs.push(vtx);
             }
 
             // Optional parameters in blocks: { |x = 1| ... }
@@ -350,21 +1535,89 @@ impl<'a> AstInstaller<'a> {
                     }
                 }
             }
+
+            // Post-rest parameters: { |*init, last| ... }
+            // Their position depends on the number of yielded values, so they stay untyped
+            for node in params.posts().iter() {
+                self.install_block_parameter_target(&node, destructured);
+            }
         }
 
         vtxs
     }
 
-    /// Install method parameters as local variables
-    fn install_parameters(&mut self, params_node: &ruby_prism::ParametersNode) {
-        // Required parameters: def foo(a, b)
-        for node in params_node.requireds().iter() {
-            if let Some(req_param) = node.as_required_parameter_node() {
+    /// Install a block parameter (plain or destructured) and return its vertex
+    fn install_block_parameter_target(
+        &mut self,
+        node: &Node,
+        destructured: &mut Vec<DestructuredParameter>,
+    ) -> VertexId {
+        if let Some(req_param) = node.as_required_parameter_node() {
+            let name = String::from_utf8_lossy(req_param.name().as_slice()).to_string();
+            install_block_parameter(self.genv, self.lenv, name)
+        } else if let Some(multi_target) = node.as_multi_target_node() {
+            self.install_destructured_block_parameter(&multi_target, destructured)
+        } else {
+            // Unnamed target: keeps the positions of its siblings aligned
+            self.genv.new_vertex()
+        }
+    }
+
+    /// Install a destructured block parameter: { |(k, v), i| ... }
+    ///
+    /// Returns the vertex of the whole value, which BlockParameterTypeBox
+    /// splits into the targets. Targets after a splat stay untyped.
+    fn install_destructured_block_parameter(
+        &mut self,
+        multi_target: &ruby_prism::MultiTargetNode,
+        destructured: &mut Vec<DestructuredParameter>,
+    ) -> VertexId {
+        let vtx = self.genv.new_vertex();
+
+        let mut targets = Vec::new();
+        for node in multi_target.lefts().iter() {
+            targets.push(self.install_block_parameter_target(&node, destructured));
+        }
+
+        let rest = multi_target.rest();
+        if let Some(splat) = rest.as_ref().and_then(|rest| rest.as_splat_node()) {
+            if let Some(req_param) = splat
+                .expression()
+                .and_then(|expression| expression.as_required_parameter_node())
+            {
                 let name = String::from_utf8_lossy(req_param.name().as_slice()).to_string();
-                install_required_parameter(self.genv, self.lenv, name);
+                install_rest_parameter(self.genv, self.lenv, name);
+            }
+        }
+
+        for node in multi_target.rights().iter() {
+            let target = self.install_block_parameter_target(&node, destructured);
+            if rest.is_none() {
+                targets.push(target);
             }
         }
 
+        destructured.push(DestructuredParameter { vtx, targets });
+        vtx
+    }
+
+    /// Install method parameters as local variables
+    ///
+    /// `block_vtx` is the method's block vertex, bound to the `&block` parameter.
+    /// Returns the vertices of the positional parameters in order; parameters
+    /// after a rest parameter are left out since their positions vary.
+    fn install_parameters(
+        &mut self,
+        params_node: &ruby_prism::ParametersNode,
+        block_vtx: VertexId,
+    ) -> Vec<VertexId> {
+        let mut positional_vtxs = Vec::new();
+
+        // Required parameters: def foo(a, b) or destructured def foo((a, b))
+        for node in params_node.requireds().iter() {
+            positional_vtxs.push(self.install_positional_parameter(&node));
+        }
+
         // Optional parameters: def foo(a = 1, b = "hello")
         for node in params_node.optionals().iter() {
             if let Some(opt_param) = node.as_optional_parameter_node() {
@@ -372,22 +1625,24 @@ impl<'a> AstInstaller<'a> {
                 let default_value = opt_param.value();
 
                 // Process default value to get its type
-                if let Some(default_vtx) = self.install_node(&default_value) {
+                let param_vtx = if let Some(default_vtx) = self.install_node(&default_value) {
                     install_optional_parameter(
                         self.genv,
                         self.lenv,
                         &mut self.changes,
                         name,
                         default_vtx,
-                    );
+                    )
                 } else {
                     // Fallback to untyped if default can't be processed
-                    install_required_parameter(self.genv, self.lenv, name);
-                }
+                    install_required_parameter(self.genv, self.lenv, name)
+                };
+                positional_vtxs.push(param_vtx);
             }
         }
 
         // Rest parameter: def foo(*args)
+        let has_rest = params_node.rest().is_some();
         if let Some(rest_node) = params_node.rest() {
             if let Some(rest_param) = rest_node.as_rest_parameter_node() {
                 if let Some(name_id) = rest_param.name() {
@@ -397,6 +1652,39 @@ impl<'a> AstInstaller<'a> {
             }
         }
 
+        // Post-rest required parameters: def foo(*rest, last)
+        for node in params_node.posts().iter() {
+            let param_vtx = self.install_positional_parameter(&node);
+            if !has_rest {
+                positional_vtxs.push(param_vtx);
+            }
+        }
+
+        // Keyword parameters: def foo(name:, greeting: "Hello")
+        for node in params_node.keywords().iter() {
+            if let Some(req_kw) = node.as_required_keyword_parameter_node() {
+                let name = String::from_utf8_lossy(req_kw.name().as_slice()).to_string();
+                install_required_parameter(self.genv, self.lenv, name);
+            } else if let Some(opt_kw) = node.as_optional_keyword_parameter_node() {
+                let name = String::from_utf8_lossy(opt_kw.name().as_slice()).to_string();
+                let default_value = opt_kw.value();
+
+                // Process default value to get its type
+                if let Some(default_vtx) = self.install_node(&default_value) {
+                    install_optional_parameter(
+                        self.genv,
+                        self.lenv,
+                        &mut self.changes,
+                        name,
+                        default_vtx,
+                    );
+                } else {
+                    // Fallback to untyped if default can't be processed
+                    install_required_parameter(self.genv, self.lenv, name);
+                }
+            }
+        }
+
         // Keyword rest parameter: def foo(**kwargs)
         if let Some(kwrest_node) = params_node.keyword_rest() {
             if let Some(kwrest_param) = kwrest_node.as_keyword_rest_parameter_node() {
@@ -406,17 +1694,110 @@ impl<'a> AstInstaller<'a> {
                 }
             }
         }
+
+        // Block parameter: def foo(&block)
+        if let Some(block_param) = params_node.block() {
+            if let Some(name_id) = block_param.name() {
+                let name = String::from_utf8_lossy(name_id.as_slice()).to_string();
+                install_block_argument_parameter(self.genv, self.lenv, name, block_vtx);
+            }
+        }
+
+        positional_vtxs
+    }
+
+    /// Install a required positional parameter (plain or destructured)
+    ///
+    /// Returns the parameter's vertex; a destructured parameter gets an untyped one.
+    fn install_positional_parameter(&mut self, node: &Node) -> VertexId {
+        if let Some(req_param) = node.as_required_parameter_node() {
+            let name = String::from_utf8_lossy(req_param.name().as_slice()).to_string();
+            return install_required_parameter(self.genv, self.lenv, name);
+        }
+        if let Some(multi_target) = node.as_multi_target_node() {
+            self.install_destructured_parameter(&multi_target);
+        }
+        self.genv.new_vertex()
+    }
+
+    /// Install a destructured parameter: def foo((a, (b, *c), d))
+    ///
+    /// Each name is installed as an untyped parameter; a splat collects an Array.
+    fn install_destructured_parameter(&mut self, multi_target: &ruby_prism::MultiTargetNode) {
+        for node in multi_target.lefts().iter() {
+            self.install_positional_parameter(&node);
+        }
+
+        if let Some(rest) = multi_target.rest() {
+            if let Some(splat) = rest.as_splat_node() {
+                if let Some(req_param) = splat
+                    .expression()
+                    .and_then(|expression| expression.as_required_parameter_node())
+                {
+                    let name = String::from_utf8_lossy(req_param.name().as_slice()).to_string();
+                    install_rest_parameter(self.genv, self.lenv, name);
+                }
+            }
+        }
+
+        for node in multi_target.rights().iter() {
+            self.install_positional_parameter(&node);
+        }
     }
 
     /// Process multiple statements
-    fn install_statements(&mut self, statements: &ruby_prism::StatementsNode) {
+    ///
+    /// Returns the vertex of the last statement (the value of the sequence).
+    /// Statements after a `return`/`raise`/`next`/`break` are reported as
+    /// unreachable and not installed.
+    fn install_statements(&mut self, statements: &ruby_prism::StatementsNode) -> Option<VertexId> {
+        let mut last_vtx = None;
         for stmt in &statements.body() {
-            self.install_node(&stmt);
+            if self.terminated {
+                let location = SourceLocation::from_prism_location_with_index(
+                    &stmt.location(),
+                    &self.line_index,
+                );
+                self.genv.record_unreachable_code(location);
+                break;
+            }
+            last_vtx = self.install_node(&stmt);
+        }
+        last_vtx
+    }
+
+    /// Install `return`/`next`/`break` with its value
+    ///
+    /// The value (nil without arguments, an Array for several) flows into
+    /// `target`, and the current path terminates.
+    fn install_jump(
+        &mut self,
+        arguments: Option<ruby_prism::ArgumentsNode>,
+        target: Option<VertexId>,
+    ) -> Option<VertexId> {
+        let args: Vec<Node> = arguments
+            .map(|arguments| arguments.arguments().iter().collect())
+            .unwrap_or_default();
+
+        let value_vtx = match args.len() {
+            0 => Some(self.genv.new_source(Type::Nil)),
+            1 => self.install_node(&args[0]),
+            _ => self.install_array_literal_elements(args),
+        };
+
+        if let Some(target) = target {
+            if let Some(value_vtx) = value_vtx {
+                self.changes.add_edge(value_vtx, target);
+            }
         }
+        self.terminated = true;
+        None
     }
 
     /// Finish installation (apply changes and execute Boxes)
-    pub fn finish(self) {
+    pub fn finish(mut self) {
+        // Delegations at the top level
+        self.finish_delegations();
         self.genv.apply_changes(self.changes);
         self.genv.run_all();
     }
@@ -3,9 +3,11 @@
 //! This module is responsible for:
 //! - String, Integer, Float, Hash, Regexp, Range literals
 //! - nil, true, false, Symbol literals
+//! - Interpolated String, Symbol, Regexp literals and xstrings
 //! - Creating Source vertices with fixed types
 //!
-//! Note: Array literals are handled in install.rs for element type inference
+//! Note: Array literals are handled in install.rs for element type inference.
+//! Embedded expressions of interpolated literals are also installed there.
 
 use crate::env::GlobalEnv;
 use crate::graph::VertexId;
@@ -22,6 +24,16 @@ pub fn install_literal(genv: &mut GlobalEnv, node: &Node) -> Option<VertexId> {
         return Some(genv.new_source(Type::string()));
     }
 
+    // "hello #{name}", <<~EOS heredoc with interpolation
+    if node.as_interpolated_string_node().is_some() {
+        return Some(genv.new_source(Type::string()));
+    }
+
+    // `ls`, `ls #{dir}` (xstring returns the command output as String)
+    if node.as_x_string_node().is_some() || node.as_interpolated_x_string_node().is_some() {
+        return Some(genv.new_source(Type::string()));
+    }
+
     // 42
     if node.as_integer_node().is_some() {
         return Some(genv.new_source(Type::integer()));
@@ -57,11 +69,21 @@ pub fn install_literal(genv: &mut GlobalEnv, node: &Node) -> Option<VertexId> {
         return Some(genv.new_source(Type::symbol()));
     }
 
+    // :"sym_#{name}"
+    if node.as_interpolated_symbol_node().is_some() {
+        return Some(genv.new_source(Type::symbol()));
+    }
+
     // /pattern/
     if node.as_regular_expression_node().is_some() {
         return Some(genv.new_source(Type::regexp()));
     }
 
+    // /pattern_#{name}/
+    if node.as_interpolated_regular_expression_node().is_some() {
+        return Some(genv.new_source(Type::regexp()));
+    }
+
     // 1..5, "a".."z" (Range literal)
     if node.as_range_node().is_some() {
         return Some(genv.new_source(Type::range()));
@@ -1,10 +1,15 @@
 mod blocks;
 mod calls;
+mod control_flow;
 mod definitions;
 mod dispatch;
+mod dynamic_methods;
+mod exceptions;
 mod install;
 mod literals;
+mod narrowing;
 mod parameters;
+mod patterns;
 mod variables;
 
 #[cfg(test)]
@@ -0,0 +1,379 @@
+//! Narrowing Handlers - Filtering local variable types in guarded branches
+//!
+//! This module is responsible for:
+//! - Extracting the type tests of a condition (`is_a?`, `nil?`, `respond_to?`, ...)
+//! - Rebinding locals to narrowed vertices inside the guarded branches
+//! - Keeping the narrowing after a guard clause (`return unless x.is_a?(String)`)
+//!
+//! A narrowed local is bound to a vertex fed by a NarrowingBox, which keeps
+//! the union members passing the test (the complement in the else branch).
+
+use crate::env::GlobalEnv;
+use crate::graph::{NarrowingBox, TypeFilter, VertexId};
+use ruby_prism::Node;
+
+use super::definitions::extract_constant_path;
+
+/// Type tests on locals (name → test), applied in order
+pub type LocalFilters = Vec<(String, TypeFilter)>;
+
+/// Type tests on locals implied by a condition being truthy or falsy
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Narrowing {
+    pub when_true: LocalFilters,
+    pub when_false: LocalFilters,
+}
+
+impl Narrowing {
+    /// A test on a local: the else branch gets the complement
+    pub fn test(name: String, filter: TypeFilter) -> Self {
+        Self {
+            when_true: vec![(name.clone(), filter.clone())],
+            when_false: vec![(name, TypeFilter::Not(Box::new(filter)))],
+        }
+    }
+
+    /// Swap the branches (`!cond`, `unless cond`)
+    pub fn negate(self) -> Self {
+        Self {
+            when_true: self.when_false,
+            when_false: self.when_true,
+        }
+    }
+}
+
+/// A local rebound to a narrowed vertex
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NarrowedLocal {
+    pub name: String,
+    pub original_vtx: VertexId,
+    pub narrowed_vtx: VertexId,
+}
+
+/// Extract the type tests of a condition
+///
+/// # Example
+/// ```ruby
+/// if x                         # x: truthy / nil or false
+/// if x.is_a?(String)           # also kind_of?, String === x
+/// if x.instance_of?(String)
+/// if x.nil?
+/// if x.respond_to?(:upcase)
+/// if !cond, if a && b, if a || b
+/// ```
+pub fn condition_narrowing(condition: &Node) -> Narrowing {
+    // if x
+    if let Some(local_read) = condition.as_local_variable_read_node() {
+        let name = String::from_utf8_lossy(local_read.name().as_slice()).to_string();
+        return Narrowing::test(name, TypeFilter::Truthy);
+    }
+
+    // if (cond)
+    if let Some(parentheses) = condition.as_parentheses_node() {
+        if let Some(statements) = parentheses
+            .body()
+            .and_then(|body| body.as_statements_node())
+        {
+            if let [inner] = statements.body().iter().collect::<Vec<_>>().as_slice() {
+                return condition_narrowing(inner);
+            }
+        }
+        return Narrowing::default();
+    }
+
+    // if a && b: both hold in the then branch
+    if let Some(and_node) = condition.as_and_node() {
+        let mut narrowing = condition_narrowing(&and_node.left());
+        narrowing
+            .when_true
+            .extend(condition_narrowing(&and_node.right()).when_true);
+        narrowing.when_false.clear();
+        return narrowing;
+    }
+
+    // if a || b: both fail in the else branch
+    if let Some(or_node) = condition.as_or_node() {
+        let mut narrowing = condition_narrowing(&or_node.left());
+        narrowing
+            .when_false
+            .extend(condition_narrowing(&or_node.right()).when_false);
+        narrowing.when_true.clear();
+        return narrowing;
+    }
+
+    let Some(call) = condition.as_call_node() else {
+        return Narrowing::default();
+    };
+    let method_name = String::from_utf8_lossy(call.name().as_slice()).to_string();
+    let args: Vec<Node> = call
+        .arguments()
+        .map(|arguments| arguments.arguments().iter().collect())
+        .unwrap_or_default();
+    let Some(receiver) = call.receiver() else {
+        return Narrowing::default();
+    };
+
+    // !cond, not cond
+    if method_name == "!" {
+        return condition_narrowing(&receiver).negate();
+    }
+
+    // String === x
+    if method_name == "===" {
+        if let [arg] = args.as_slice() {
+            if let Some(class_name) = extract_constant_path(&receiver) {
+                if let Some(name) = local_variable_name(arg) {
+                    return Narrowing::test(name, TypeFilter::Class(class_name));
+                }
+            }
+        }
+        return Narrowing::default();
+    }
+
+    let Some(name) = local_variable_name(&receiver) else {
+        return Narrowing::default();
+    };
+
+    let filter = match (method_name.as_str(), args.as_slice()) {
+        ("is_a?" | "kind_of?", [arg]) => extract_constant_path(arg).map(TypeFilter::Class),
+        ("instance_of?", [arg]) => extract_constant_path(arg).map(TypeFilter::InstanceOf),
+        ("nil?", []) => Some(TypeFilter::Nil),
+        ("respond_to?", [arg, ..]) => arg.as_symbol_node().map(|symbol| {
+            TypeFilter::RespondTo(String::from_utf8_lossy(symbol.unescaped()).to_string())
+        }),
+        _ => None,
+    };
+
+    match filter {
+        Some(filter) => Narrowing::test(name, filter),
+        None => Narrowing::default(),
+    }
+}
+
+/// Extract the type tests of `case x` / `when A, B` clauses
+///
+/// A `when` whose conditions are all classes narrows `x` to them. The else
+/// branch gets the complement if every `when` only lists classes.
+/// Returns the tests of each `when` clause and of the else branch.
+pub fn when_narrowings(
+    predicate: Option<&Node>,
+    when_conditions: &[Vec<Node>],
+) -> (Vec<LocalFilters>, LocalFilters) {
+    let none = (vec![Vec::new(); when_conditions.len()], Vec::new());
+    let Some(predicate) = predicate else {
+        // case; when x.nil? ... (conditions are plain conditions)
+        let when_true = when_conditions
+            .iter()
+            .map(|conditions| match conditions.as_slice() {
+                [condition] => condition_narrowing(condition).when_true,
+                _ => Vec::new(),
+            })
+            .collect();
+        return (when_true, Vec::new());
+    };
+    let Some(name) = local_variable_name(predicate) else {
+        return none;
+    };
+
+    let mut all_classes = Vec::new();
+    let mut when_filters = Vec::new();
+    let mut exhaustive = true;
+
+    for conditions in when_conditions {
+        let classes: Option<Vec<TypeFilter>> = conditions
+            .iter()
+            .map(|condition| extract_constant_path(condition).map(TypeFilter::Class))
+            .collect();
+
+        match classes {
+            Some(classes) => {
+                all_classes.extend(classes.iter().cloned());
+                when_filters.push(vec![(name.clone(), any_of(classes))]);
+            }
+            None => {
+                // `when 1` doesn't exclude any class from the else branch
+                exhaustive = false;
+                when_filters.push(Vec::new());
+            }
+        }
+    }
+
+    let else_filters = if exhaustive && !all_classes.is_empty() {
+        vec![(name, TypeFilter::Not(Box::new(any_of(all_classes))))]
+    } else {
+        Vec::new()
+    };
+
+    (when_filters, else_filters)
+}
+
+/// Combine class tests into one
+fn any_of(mut filters: Vec<TypeFilter>) -> TypeFilter {
+    if filters.len() == 1 {
+        filters.remove(0)
+    } else {
+        TypeFilter::AnyOf(filters)
+    }
+}
+
+/// Get the name of a local variable read
+fn local_variable_name(node: &Node) -> Option<String> {
+    node.as_local_variable_read_node()
+        .map(|local_read| String::from_utf8_lossy(local_read.name().as_slice()).to_string())
+}
+
+/// Install a vertex holding the members of `value_vtx` passing `filter`
+pub fn install_narrowed_value(
+    genv: &mut GlobalEnv,
+    value_vtx: VertexId,
+    filter: TypeFilter,
+) -> VertexId {
+    let narrowed_vtx = genv.new_vertex();
+    let box_id = genv.alloc_box_id();
+    let narrowing_box = NarrowingBox::new(box_id, value_vtx, narrowed_vtx, filter);
+    genv.register_box(box_id, Box::new(narrowing_box));
+    narrowed_vtx
+}
+
+/// Rebind locals to their narrowed values for a guarded branch
+///
+/// Unbound locals are skipped.
+pub fn narrow_locals(genv: &mut GlobalEnv, filters: &[(String, TypeFilter)]) -> Vec<NarrowedLocal> {
+    let mut narrowed = Vec::new();
+    for (name, filter) in filters {
+        let Some(original_vtx) = genv.scope_manager.lookup_var(name) else {
+            continue;
+        };
+        let narrowed_vtx = install_narrowed_value(genv, original_vtx, filter.clone());
+        genv.scope_manager.assign_var(name.clone(), narrowed_vtx);
+        narrowed.push(NarrowedLocal {
+            name: name.clone(),
+            original_vtx,
+            narrowed_vtx,
+        });
+    }
+    narrowed
+}
+
+/// Restore the original bindings of locals not reassigned in the branch
+///
+/// Applied in reverse, so a local narrowed twice (`x && x.is_a?(String)`)
+/// gets its original binding back.
+pub fn restore_narrowed_locals(genv: &mut GlobalEnv, narrowed: &[NarrowedLocal]) {
+    for local in narrowed.iter().rev() {
+        if genv.scope_manager.lookup_var(&local.name) == Some(local.narrowed_vtx) {
+            genv.scope_manager
+                .assign_var(local.name.clone(), local.original_vtx);
+        }
+    }
+}
+
+/// Keep the narrowed bindings after a conditional (guard clause)
+///
+/// e.g., after `return unless x.is_a?(String)`, `x` is a String.
+pub fn keep_narrowed_locals(genv: &mut GlobalEnv, narrowed: &[NarrowedLocal]) {
+    for local in narrowed {
+        if genv.scope_manager.lookup_var(&local.name) == Some(local.original_vtx) {
+            genv.scope_manager
+                .assign_var(local.name.clone(), local.narrowed_vtx);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::ChangeSet;
+    use crate::parser::parse_ruby_source;
+    use crate::types::Type;
+
+    /// Get the narrowing of `if <condition>; end` where `x` is a local
+    fn narrowing_of(condition_source: &str) -> Narrowing {
+        let source = format!("x = nil\nif {}; end", condition_source);
+        let parse_result = parse_ruby_source(&source, "test.rb".to_string()).unwrap();
+        let program = parse_result.node().as_program_node().unwrap();
+        let statement = program.statements().body().iter().nth(1).unwrap();
+        condition_narrowing(&statement.as_if_node().unwrap().predicate())
+    }
+
+    fn x_test(filter: TypeFilter) -> Narrowing {
+        Narrowing::test("x".to_string(), filter)
+    }
+
+    #[test]
+    fn test_condition_narrowing() {
+        let string = TypeFilter::Class("String".to_string());
+
+        assert_eq!(narrowing_of("x.is_a?(String)"), x_test(string.clone()));
+        assert_eq!(narrowing_of("String === x"), x_test(string.clone()));
+        assert_eq!(narrowing_of("!x.nil?"), x_test(TypeFilter::Nil).negate());
+        assert_eq!(
+            narrowing_of("x.respond_to?(:upcase)"),
+            x_test(TypeFilter::RespondTo("upcase".to_string()))
+        );
+        assert_eq!(narrowing_of("x"), x_test(TypeFilter::Truthy));
+
+        // Only the then branch of `a && b` is narrowed
+        let narrowing = narrowing_of("x && x.is_a?(String)");
+        assert_eq!(
+            narrowing.when_true,
+            vec![
+                ("x".to_string(), TypeFilter::Truthy),
+                ("x".to_string(), string)
+            ]
+        );
+        assert!(narrowing.when_false.is_empty());
+
+        // Method calls and non-constant classes are not narrowed
+        assert_eq!(narrowing_of("y.is_a?(String)"), Narrowing::default());
+        assert_eq!(narrowing_of("x.is_a?(klass)"), Narrowing::default());
+    }
+
+    #[test]
+    fn test_install_narrowed_value() {
+        let mut genv = GlobalEnv::new();
+        let mut changes = ChangeSet::new();
+
+        // x: Integer | nil
+        let value_vtx = genv.new_vertex();
+        for ty in [Type::integer(), Type::Nil] {
+            let src_vtx = genv.new_source(ty);
+            changes.add_edge(src_vtx, value_vtx);
+        }
+        genv.apply_changes(changes);
+
+        let non_nil_vtx = install_narrowed_value(
+            &mut genv,
+            value_vtx,
+            TypeFilter::Not(Box::new(TypeFilter::Nil)),
+        );
+        genv.run_all();
+
+        assert_eq!(genv.get_vertex(non_nil_vtx).unwrap().show(), "Integer");
+    }
+
+    #[test]
+    fn test_narrow_and_restore_locals() {
+        let mut genv = GlobalEnv::new();
+
+        let x_vtx = genv.new_vertex();
+        genv.scope_manager.assign_var("x".to_string(), x_vtx);
+
+        let filters = vec![("x".to_string(), TypeFilter::Nil)];
+        let narrowed = narrow_locals(&mut genv, &filters);
+        assert_eq!(narrowed.len(), 1);
+        assert_eq!(
+            genv.scope_manager.lookup_var("x"),
+            Some(narrowed[0].narrowed_vtx)
+        );
+
+        restore_narrowed_locals(&mut genv, &narrowed);
+        assert_eq!(genv.scope_manager.lookup_var("x"), Some(x_vtx));
+
+        keep_narrowed_locals(&mut genv, &narrowed);
+        assert_eq!(
+            genv.scope_manager.lookup_var("x"),
+            Some(narrowed[0].narrowed_vtx)
+        );
+    }
+}
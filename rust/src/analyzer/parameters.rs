@@ -9,10 +9,13 @@ use crate::env::{GlobalEnv, LocalEnv};
 use crate::graph::{ChangeSet, VertexId};
 use crate::types::Type;
 
+use super::variables::bind_local_var;
+
 /// Install a required parameter as a local variable
 ///
 /// Required parameters start with Bot (untyped) type since we don't know
-/// what type will be passed at call sites.
+/// what type will be passed at call sites. Also used for required keyword
+/// parameters (`name:`).
 ///
 /// # Example
 /// ```ruby
@@ -24,8 +27,8 @@ pub fn install_required_parameter(genv: &mut GlobalEnv, lenv: &mut LocalEnv, nam
     // Create a vertex for the parameter (starts as Bot/untyped)
     let param_vtx = genv.new_vertex();
 
-    // Register in LocalEnv for variable lookup
-    lenv.new_var(name, param_vtx);
+    // Register in the current scope for variable lookup
+    bind_local_var(genv, lenv, name, param_vtx);
 
     param_vtx
 }
@@ -33,6 +36,7 @@ pub fn install_required_parameter(genv: &mut GlobalEnv, lenv: &mut LocalEnv, nam
 /// Install an optional parameter with a default value
 ///
 /// The parameter's type is inferred from the default value expression.
+/// Also used for optional keyword parameters (`greeting: "Hello"`).
 ///
 /// # Example
 /// ```ruby
@@ -55,8 +59,8 @@ pub fn install_optional_parameter(
     // before the method body is processed
     genv.add_edge(default_value_vtx, param_vtx);
 
-    // Register in LocalEnv for variable lookup
-    lenv.new_var(name, param_vtx);
+    // Register in the current scope for variable lookup
+    bind_local_var(genv, lenv, name, param_vtx);
 
     param_vtx
 }
@@ -79,8 +83,8 @@ pub fn install_rest_parameter(genv: &mut GlobalEnv, lenv: &mut LocalEnv, name: S
     let array_src = genv.new_source(Type::array());
     genv.add_edge(array_src, param_vtx);
 
-    // Register in LocalEnv for variable lookup
-    lenv.new_var(name, param_vtx);
+    // Register in the current scope for variable lookup
+    bind_local_var(genv, lenv, name, param_vtx);
 
     param_vtx
 }
@@ -107,12 +111,32 @@ pub fn install_keyword_rest_parameter(
     let hash_src = genv.new_source(Type::hash());
     genv.add_edge(hash_src, param_vtx);
 
-    // Register in LocalEnv for variable lookup
-    lenv.new_var(name, param_vtx);
+    // Register in the current scope for variable lookup
+    bind_local_var(genv, lenv, name, param_vtx);
 
     param_vtx
 }
 
+/// Install a block parameter (&block) as a local variable bound to the method's block vertex
+///
+/// Blocks passed at call sites flow into the block vertex as Proc values.
+///
+/// # Example
+/// ```ruby
+/// def run(&block)  # 'block' is the Proc passed to `run { |x| ... }`
+///   block.call(1)
+/// end
+/// ```
+pub fn install_block_argument_parameter(
+    genv: &mut GlobalEnv,
+    lenv: &mut LocalEnv,
+    name: String,
+    block_vtx: VertexId,
+) -> VertexId {
+    bind_local_var(genv, lenv, name, block_vtx);
+    block_vtx
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -124,8 +148,9 @@ mod tests {
 
         let vtx = install_required_parameter(&mut genv, &mut lenv, "name".to_string());
 
-        // Parameter should be registered in LocalEnv
+        // Parameter should be registered in LocalEnv and the current scope
         assert_eq!(lenv.get_var("name"), Some(vtx));
+        assert_eq!(genv.scope_manager.lookup_var("name"), Some(vtx));
 
         // Vertex should exist in GlobalEnv (as untyped)
         let vertex = genv.get_vertex(vtx);
@@ -151,4 +176,23 @@ mod tests {
         assert_ne!(vtx_b, vtx_c);
         assert_ne!(vtx_a, vtx_c);
     }
+
+    #[test]
+    fn test_install_optional_parameter() {
+        let mut genv = GlobalEnv::new();
+        let mut lenv = LocalEnv::new();
+        let mut changes = ChangeSet::new();
+
+        let default_vtx = genv.new_source(Type::string());
+        let vtx = install_optional_parameter(
+            &mut genv,
+            &mut lenv,
+            &mut changes,
+            "greeting".to_string(),
+            default_vtx,
+        );
+
+        assert_eq!(lenv.get_var("greeting"), Some(vtx));
+        assert_eq!(genv.get_vertex(vtx).unwrap().show(), "String");
+    }
 }
@@ -0,0 +1,122 @@
+//! Pattern Handlers - Narrowing and deconstructing matched values
+//!
+//! This module is responsible for:
+//! - Getting the class a pattern checks (`in String => s`), narrowed in narrowing.rs
+//! - Extracting the elements matched by array, find and hash patterns
+//! - Typing the result of `expr in pattern`
+//!
+//! The pattern traversal itself is in install.rs, since sub-patterns may
+//! contain expressions (`^(expr)`) that need the full installer.
+
+use crate::env::GlobalEnv;
+use crate::graph::{ChangeSet, ElementAccess, ElementTypeBox, VertexId};
+use crate::types::Type;
+use ruby_prism::Node;
+
+use super::definitions::extract_constant_path;
+
+/// Install a vertex holding an element of an Array or Hash value
+pub fn install_element_value(
+    genv: &mut GlobalEnv,
+    value_vtx: VertexId,
+    access: ElementAccess,
+) -> VertexId {
+    let element_vtx = genv.new_vertex();
+    let box_id = genv.alloc_box_id();
+    let element_box = ElementTypeBox::new(box_id, value_vtx, element_vtx, access);
+    genv.register_box(box_id, Box::new(element_box));
+    element_vtx
+}
+
+/// Get the class checked by a pattern, if it checks one
+///
+/// # Example
+/// ```ruby
+/// in String            # String
+/// in Point[x, y]       # Point
+/// in [x, y]            # Array
+/// in {name:}           # Hash
+/// ```
+pub fn pattern_class_name(pattern: &Node) -> Option<String> {
+    if let Some(array_pattern) = pattern.as_array_pattern_node() {
+        return Some(
+            array_pattern
+                .constant()
+                .and_then(|constant| extract_constant_path(&constant))
+                .unwrap_or_else(|| "Array".to_string()),
+        );
+    }
+
+    if let Some(find_pattern) = pattern.as_find_pattern_node() {
+        return Some(
+            find_pattern
+                .constant()
+                .and_then(|constant| extract_constant_path(&constant))
+                .unwrap_or_else(|| "Array".to_string()),
+        );
+    }
+
+    if let Some(hash_pattern) = pattern.as_hash_pattern_node() {
+        return Some(
+            hash_pattern
+                .constant()
+                .and_then(|constant| extract_constant_path(&constant))
+                .unwrap_or_else(|| "Hash".to_string()),
+        );
+    }
+
+    extract_constant_path(pattern)
+}
+
+/// Install the boolean result of `expr in pattern`
+pub fn install_match_result(genv: &mut GlobalEnv, changes: &mut ChangeSet) -> VertexId {
+    let result_vtx = genv.new_vertex();
+    for class_name in ["TrueClass", "FalseClass"] {
+        let src_vtx = genv.new_source(Type::instance(class_name));
+        changes.add_edge(src_vtx, result_vtx);
+    }
+    result_vtx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_ruby_source;
+
+    /// Get the class checked by the pattern of `x in <pattern>`
+    fn class_name_of(pattern_source: &str) -> Option<String> {
+        let source = format!("x in {}", pattern_source);
+        let parse_result = parse_ruby_source(&source, "test.rb".to_string()).unwrap();
+        let program = parse_result.node().as_program_node().unwrap();
+        let statement = program.statements().body().iter().next().unwrap();
+        let match_predicate = statement.as_match_predicate_node().unwrap();
+        pattern_class_name(&match_predicate.pattern())
+    }
+
+    #[test]
+    fn test_pattern_class_name() {
+        assert_eq!(class_name_of("String"), Some("String".to_string()));
+        assert_eq!(class_name_of("Api::User"), Some("Api::User".to_string()));
+        assert_eq!(class_name_of("[a, b]"), Some("Array".to_string()));
+        assert_eq!(class_name_of("Point[a, b]"), Some("Point".to_string()));
+        assert_eq!(class_name_of("[*, 1, *]"), Some("Array".to_string()));
+        assert_eq!(class_name_of("{name:}"), Some("Hash".to_string()));
+        assert_eq!(class_name_of("1"), None);
+    }
+
+    #[test]
+    fn test_install_element_value() {
+        let mut genv = GlobalEnv::new();
+
+        let value_vtx = genv.new_source(Type::tuple(vec![Type::string(), Type::integer()]));
+        let first_vtx = install_element_value(&mut genv, value_vtx, ElementAccess::Index(0));
+        let rest_vtx = install_element_value(&mut genv, value_vtx, ElementAccess::Rest);
+        genv.run_all();
+
+        assert_eq!(genv.get_vertex(first_vtx).unwrap().show(), "String");
+        assert_eq!(
+            genv.get_vertex(rest_vtx).unwrap().show(),
+            "Array[String | Integer]"
+        );
+    }
+}
@@ -4,18 +4,41 @@
 //! - Class/method definition handling
 //! - Instance variable type tracking across methods
 //! - Type error detection for undefined methods
+//! - Method name and receiver locations of type errors
+//! - Where the receiver type of a type error came from
+//! - "Did you mean?" suggestions for undefined methods
 //! - Method chain type inference
+//! - Operator and index calls
+//! - Interpolated literals
+//! - Lambdas, procs and yield
+//! - Destructured, numbered and `it` block parameters
+//! - Method-, class- and module-local variable scoping
+//! - Conditionals, loops and joined local variable bindings
+//! - Return, next and break values and unreachable code
+//! - begin/rescue/else/ensure and rescued exception types
+//! - case/in pattern matching captures
+//! - Type narrowing in guarded branches
+//! - `super` and inherited methods resolved through superclasses and included modules
+//! - alias, alias_method, define_method and delegated methods
+//! - Open classes answering undefined methods with method_missing
+//! - Analysis of the AST recovered from syntax errors
 
 use crate::analyzer::AstInstaller;
+use crate::env::method_registry::OpenClassPolicy;
 use crate::env::{GlobalEnv, LocalEnv};
-use crate::parser::parse_ruby_source;
+use crate::parser::parse_ruby_source_tolerant;
 use crate::types::Type;
 
 /// Helper to run analysis on Ruby source code
 fn analyze(source: &str) -> (GlobalEnv, LocalEnv) {
-    let parse_result = parse_ruby_source(source, "test.rb".to_string()).unwrap();
+    analyze_with(GlobalEnv::new(), source)
+}
 
-    let mut genv = GlobalEnv::new();
+/// Helper to run analysis with a preconfigured GlobalEnv
+///
+/// Like the checker, syntax errors do not stop the analysis.
+fn analyze_with(mut genv: GlobalEnv, source: &str) -> (GlobalEnv, LocalEnv) {
+    let parse_result = parse_ruby_source_tolerant(source);
 
     // Register common methods
     genv.register_builtin_method(Type::string(), "upcase", Type::string());
@@ -30,10 +53,45 @@ fn analyze(source: &str) -> (GlobalEnv, LocalEnv) {
     genv.register_builtin_method(Type::float(), "abs", Type::float());
 
     // Register iterator methods for block tests
-    genv.register_builtin_method(Type::array(), "each", Type::array());
+    genv.register_builtin_method_with_block(
+        Type::array(),
+        "each",
+        Type::array(),
+        Some(vec![Type::instance("Elem")]),
+    );
     genv.register_builtin_method(Type::array(), "map", Type::array());
     genv.register_builtin_method(Type::hash(), "each", Type::hash());
 
+    // Register iterators with block parameter types
+    genv.register_builtin_method_with_block(
+        Type::array(),
+        "each_with_index",
+        Type::array(),
+        Some(vec![Type::instance("Elem"), Type::integer()]),
+    );
+    genv.register_builtin_method_with_block(
+        Type::array(),
+        "select",
+        Type::array(),
+        Some(vec![Type::instance("Elem")]),
+    );
+
+    // Register Kernel type tests (inherited by every class in RBS)
+    for class_name in ["String", "Integer", "Float", "NilClass"] {
+        for method_name in ["is_a?", "kind_of?", "instance_of?", "nil?", "respond_to?"] {
+            genv.register_builtin_method(
+                Type::instance(class_name),
+                method_name,
+                Type::instance("TrueClass"),
+            );
+        }
+    }
+
+    // Register exception methods
+    genv.register_builtin_method(Type::instance("StandardError"), "message", Type::string());
+    genv.register_builtin_method(Type::instance("KeyError"), "message", Type::string());
+    genv.register_builtin_method(Type::instance("KeyError"), "key", Type::Bot);
+
     // Register Regexp methods
     genv.register_builtin_method(Type::regexp(), "match", Type::instance("MatchData"));
     genv.register_builtin_method(Type::regexp(), "match?", Type::instance("TrueClass"));
@@ -48,6 +106,13 @@ fn analyze(source: &str) -> (GlobalEnv, LocalEnv) {
     genv.register_builtin_method(Type::range(), "include?", Type::instance("TrueClass"));
     genv.register_builtin_method(Type::range(), "cover?", Type::instance("TrueClass"));
 
+    // Register operator and index methods
+    genv.register_builtin_method(Type::integer(), "+", Type::integer());
+    genv.register_builtin_method(Type::integer(), "-@", Type::integer());
+    genv.register_builtin_method(Type::string(), "+", Type::string());
+    genv.register_builtin_method(Type::array(), "[]", Type::instance("Elem"));
+    genv.register_builtin_method(Type::array(), "[]=", Type::instance("Elem"));
+
     let mut lenv = LocalEnv::new();
     let mut installer = AstInstaller::new(&mut genv, &mut lenv, source);
 
@@ -83,6 +148,86 @@ end
     assert_eq!(genv.type_errors[0].method_name, "upcase");
 }
 
+#[test]
+fn test_type_error_points_at_method_name_and_receiver() {
+    let source = "x = 123\ny = x.upcase\n";
+
+    let (genv, _lenv) = analyze(source);
+
+    assert_eq!(genv.type_errors.len(), 1);
+    let error = &genv.type_errors[0];
+    let location = error.location.as_ref().unwrap();
+    assert_eq!(
+        (location.line, location.column, location.end_column),
+        (2, 7, 13)
+    );
+    let receiver = error.receiver_location.as_ref().unwrap();
+    assert_eq!(
+        (receiver.line, receiver.column, receiver.end_column),
+        (2, 5, 6)
+    );
+}
+
+#[test]
+fn test_type_error_suggestions() {
+    let source = r#"
+class Base
+  def save
+  end
+end
+
+class User < Base
+  def update
+    self.sav
+  end
+end
+
+name = "Alice".upcse
+"#;
+
+    let (genv, _lenv) = analyze(source);
+
+    assert_eq!(genv.type_errors.len(), 2);
+    let suggestions = |method_name: &str| {
+        genv.type_errors
+            .iter()
+            .find(|error| error.method_name == method_name)
+            .map(|error| error.suggestions.clone())
+            .unwrap()
+    };
+    assert_eq!(suggestions("upcse"), vec!["upcase"]);
+    // Methods of the superclass are suggested too
+    assert_eq!(suggestions("sav"), vec!["save"]);
+}
+
+#[test]
+fn test_type_error_origins() {
+    let source = r#"
+class Counter
+  def count
+    42
+  end
+
+  def label
+    n = count
+    n.upcase
+  end
+end
+"#;
+
+    let (genv, _lenv) = analyze(source);
+
+    assert_eq!(genv.type_errors.len(), 1);
+    let origins = &genv.type_errors[0].origins;
+    // The literal in `count` created the Integer
+    let literal = origins.iter().find(|origin| origin.is_source).unwrap();
+    assert_eq!((literal.location.line, literal.location.column), (4, 5));
+    // ... which flowed through the call in `label`
+    assert!(origins
+        .iter()
+        .any(|origin| !origin.is_source && origin.location.line == 8));
+}
+
 #[test]
 fn test_class_with_instance_variable() {
     let source = r#"
@@ -604,3 +749,1574 @@ b = x.size
     let b_vtx = lenv.get_var("b").unwrap();
     assert_eq!(genv.get_vertex(b_vtx).unwrap().show(), "Integer");
 }
+
+// ============================================
+// Operator and Index Call Tests
+// ============================================
+
+#[test]
+fn test_binary_operator_return_type() {
+    let source = r#"
+x = 1
+y = x + 2
+"#;
+
+    let (genv, lenv) = analyze(source);
+
+    assert_eq!(genv.type_errors.len(), 0);
+
+    let y_vtx = lenv.get_var("y").unwrap();
+    assert_eq!(genv.get_vertex(y_vtx).unwrap().show(), "Integer");
+}
+
+#[test]
+fn test_operator_on_nil_reported() {
+    let source = r#"
+x = nil + 1
+"#;
+
+    let (genv, _lenv) = analyze(source);
+
+    assert_eq!(genv.type_errors.len(), 1);
+    assert_eq!(genv.type_errors[0].method_name, "+");
+    assert_eq!(genv.type_errors[0].receiver_type.show(), "nil");
+}
+
+#[test]
+fn test_undefined_operator_reported() {
+    let source = r#"
+x = "a" - 1
+"#;
+
+    let (genv, _lenv) = analyze(source);
+
+    assert_eq!(genv.type_errors.len(), 1);
+    assert_eq!(genv.type_errors[0].method_name, "-");
+    assert_eq!(genv.type_errors[0].receiver_type.show(), "String");
+}
+
+#[test]
+fn test_unary_operator() {
+    let source = r#"
+x = 1
+y = -x
+"#;
+
+    let (genv, lenv) = analyze(source);
+
+    assert_eq!(genv.type_errors.len(), 0);
+
+    let y_vtx = lenv.get_var("y").unwrap();
+    assert_eq!(genv.get_vertex(y_vtx).unwrap().show(), "Integer");
+}
+
+#[test]
+fn test_index_returns_element_type() {
+    let source = r#"
+arr = [1, 2, 3]
+x = arr[0]
+"#;
+
+    let (genv, lenv) = analyze(source);
+
+    assert_eq!(genv.type_errors.len(), 0);
+
+    let x_vtx = lenv.get_var("x").unwrap();
+    assert_eq!(genv.get_vertex(x_vtx).unwrap().show(), "Integer");
+}
+
+#[test]
+fn test_index_write_is_checked() {
+    let source = r#"
+x = 1
+x[0] = 2
+"#;
+
+    let (genv, _lenv) = analyze(source);
+
+    assert_eq!(genv.type_errors.len(), 1);
+    assert_eq!(genv.type_errors[0].method_name, "[]=");
+}
+
+#[test]
+fn test_operator_write_is_checked() {
+    let source = r#"
+x = "a"
+x += "b"
+y = nil
+y += 1
+"#;
+
+    let (genv, lenv) = analyze(source);
+
+    assert_eq!(genv.type_errors.len(), 1);
+    assert_eq!(genv.type_errors[0].method_name, "+");
+
+    let x_vtx = lenv.get_var("x").unwrap();
+    assert_eq!(genv.get_vertex(x_vtx).unwrap().show(), "String");
+}
+
+// ============================================
+// Interpolated Literal Tests
+// ============================================
+
+#[test]
+fn test_interpolated_string_is_string() {
+    let source = r##"
+name = "world"
+x = "hello #{name}"
+y = "#{name}".upcase
+"##;
+
+    let (genv, lenv) = analyze(source);
+
+    assert_eq!(genv.type_errors.len(), 0);
+
+    let x_vtx = lenv.get_var("x").unwrap();
+    assert_eq!(genv.get_vertex(x_vtx).unwrap().show(), "String");
+
+    let y_vtx = lenv.get_var("y").unwrap();
+    assert_eq!(genv.get_vertex(y_vtx).unwrap().show(), "String");
+}
+
+#[test]
+fn test_interpolated_string_type_error() {
+    let source = r##"
+x = "hello #{1}".round
+"##;
+
+    let (genv, _lenv) = analyze(source);
+
+    assert_eq!(genv.type_errors.len(), 1);
+    assert_eq!(genv.type_errors[0].method_name, "round");
+    assert_eq!(genv.type_errors[0].receiver_type.show(), "String");
+}
+
+#[test]
+fn test_calls_inside_interpolation_are_checked() {
+    let source = r##"
+x = 1
+y = "value: #{x.upcase}"
+z = <<~EOS
+  #{x.downcase}
+EOS
+"##;
+
+    let (genv, _lenv) = analyze(source);
+
+    assert_eq!(genv.type_errors.len(), 2);
+    assert_eq!(genv.type_errors[0].method_name, "upcase");
+    assert_eq!(genv.type_errors[1].method_name, "downcase");
+}
+
+#[test]
+fn test_interpolated_symbol_regexp_and_xstring() {
+    let source = r##"
+name = "a"
+s = :"sym_#{name}"
+r = /#{name}/
+x = `echo #{name}`
+y = `ls`
+"##;
+
+    let (genv, lenv) = analyze(source);
+
+    assert_eq!(genv.type_errors.len(), 0);
+
+    let s_vtx = lenv.get_var("s").unwrap();
+    assert_eq!(genv.get_vertex(s_vtx).unwrap().show(), "Symbol");
+
+    let r_vtx = lenv.get_var("r").unwrap();
+    assert_eq!(genv.get_vertex(r_vtx).unwrap().show(), "Regexp");
+
+    let x_vtx = lenv.get_var("x").unwrap();
+    assert_eq!(genv.get_vertex(x_vtx).unwrap().show(), "String");
+
+    let y_vtx = lenv.get_var("y").unwrap();
+    assert_eq!(genv.get_vertex(y_vtx).unwrap().show(), "String");
+}
+
+// ============================================
+// Lambda, Proc and Yield Tests
+// ============================================
+
+#[test]
+fn test_lambda_call_flows_arguments() {
+    let source = r#"
+f = ->(x) { x.upcase }
+f.call(1)
+g = lambda { |y| y.downcase }
+g.(2)
+h = proc { |z| z.upcase }
+h["ok"]
+"#;
+
+    let (genv, _lenv) = analyze(source);
+
+    assert_eq!(genv.type_errors.len(), 2);
+    assert_eq!(genv.type_errors[0].method_name, "upcase");
+    assert_eq!(genv.type_errors[0].receiver_type.show(), "Integer");
+    assert_eq!(genv.type_errors[1].method_name, "downcase");
+    assert_eq!(genv.type_errors[1].receiver_type.show(), "Integer");
+}
+
+#[test]
+fn test_lambda_return_type() {
+    let source = r#"
+f = ->(x) { "hello" }
+g = Proc.new { |x| 1 }
+a = f.call(1)
+b = g.call(1)
+"#;
+
+    let (genv, lenv) = analyze(source);
+
+    assert_eq!(genv.type_errors.len(), 0);
+
+    let f_vtx = lenv.get_var("f").unwrap();
+    assert_eq!(genv.get_vertex(f_vtx).unwrap().show(), "Proc");
+
+    let a_vtx = lenv.get_var("a").unwrap();
+    assert_eq!(genv.get_vertex(a_vtx).unwrap().show(), "String");
+
+    let b_vtx = lenv.get_var("b").unwrap();
+    assert_eq!(genv.get_vertex(b_vtx).unwrap().show(), "Integer");
+}
+
+#[test]
+fn test_yield_flows_into_block_at_call_site() {
+    let source = r#"
+class Container
+  def each_item
+    yield 1
+  end
+
+  def run
+    each_item { |i| i.upcase }
+  end
+end
+"#;
+
+    let (genv, _lenv) = analyze(source);
+
+    assert_eq!(genv.type_errors.len(), 1);
+    assert_eq!(genv.type_errors[0].method_name, "upcase");
+    assert_eq!(genv.type_errors[0].receiver_type.show(), "Integer");
+}
+
+#[test]
+fn test_yield_result_is_block_result() {
+    let source = r#"
+def transform
+  y = yield "a"
+  y.upcase
+end
+
+transform { |s| 1 }
+"#;
+
+    let (genv, _lenv) = analyze(source);
+
+    assert_eq!(genv.type_errors.len(), 1);
+    assert_eq!(genv.type_errors[0].method_name, "upcase");
+    assert_eq!(genv.type_errors[0].receiver_type.show(), "Integer");
+}
+
+#[test]
+fn test_block_parameter_call() {
+    let source = r#"
+def run(&block)
+  block.call("a")
+end
+
+run { |x| x.upcase }
+run { |x| x.round }
+"#;
+
+    let (genv, _lenv) = analyze(source);
+
+    assert_eq!(genv.type_errors.len(), 1);
+    assert_eq!(genv.type_errors[0].method_name, "round");
+    assert_eq!(genv.type_errors[0].receiver_type.show(), "String");
+}
+
+#[test]
+fn test_block_argument_forwarding() {
+    let source = r#"
+def each_item
+  yield 1
+end
+
+f = ->(x) { x.upcase }
+each_item(&f)
+"#;
+
+    let (genv, _lenv) = analyze(source);
+
+    assert_eq!(genv.type_errors.len(), 1);
+    assert_eq!(genv.type_errors[0].method_name, "upcase");
+}
+
+#[test]
+fn test_receiver_less_calls() {
+    let source = r#"
+x = 1
+puts "hello"
+puts x.upcase
+"#;
+
+    let (genv, _lenv) = analyze(source);
+
+    // Unknown receiver-less calls are not reported, but their arguments are checked
+    assert_eq!(genv.type_errors.len(), 1);
+    assert_eq!(genv.type_errors[0].method_name, "upcase");
+}
+
+#[test]
+fn test_keyword_parameters() {
+    let source = r#"
+def greet(name:, greeting: "Hello", count: 1)
+  a = name
+  b = greeting.upcase
+  c = count.upcase
+end
+"#;
+
+    let (genv, lenv) = analyze(source);
+
+    // count is Integer from its default value
+    assert_eq!(genv.type_errors.len(), 1);
+    assert_eq!(genv.type_errors[0].method_name, "upcase");
+    assert_eq!(genv.type_errors[0].receiver_type.show(), "Integer");
+
+    // Required keyword parameter is available as a local variable
+    assert!(lenv.get_var("a").is_some());
+    let b_vtx = lenv.get_var("b").unwrap();
+    assert_eq!(genv.get_vertex(b_vtx).unwrap().show(), "String");
+}
+
+#[test]
+fn test_post_and_destructured_parameters() {
+    let source = r#"
+def pairs((key, (value, *others)), *rest, last)
+  a = key
+  b = value
+  c = others
+  d = last
+end
+"#;
+
+    let (genv, lenv) = analyze(source);
+
+    assert_eq!(genv.type_errors.len(), 0);
+
+    for name in ["key", "value", "others", "last"] {
+        assert!(lenv.get_var(name).is_some(), "{} should be installed", name);
+    }
+
+    let c_vtx = lenv.get_var("c").unwrap();
+    assert_eq!(genv.get_vertex(c_vtx).unwrap().show(), "Array");
+}
+
+#[test]
+fn test_block_parameter_is_bound() {
+    let source = r#"
+def run(&blk)
+  x = blk
+end
+
+run { |i| i }
+"#;
+
+    let (genv, lenv) = analyze(source);
+
+    assert_eq!(genv.type_errors.len(), 0);
+
+    let x_vtx = lenv.get_var("x").unwrap();
+    assert_eq!(genv.get_vertex(x_vtx).unwrap().show(), "Proc");
+}
+
+#[test]
+fn test_destructured_block_parameters() {
+    let source = r#"
+pairs = [["a", "b"]]
+pairs.each_with_index { |(first, second), i| x = first.upcase; y = second; z = i }
+"#;
+
+    let (genv, lenv) = analyze(source);
+
+    assert_eq!(genv.type_errors.len(), 0);
+
+    let y_vtx = lenv.get_var("y").unwrap();
+    assert_eq!(genv.get_vertex(y_vtx).unwrap().show(), "String");
+    let z_vtx = lenv.get_var("z").unwrap();
+    assert_eq!(genv.get_vertex(z_vtx).unwrap().show(), "Integer");
+}
+
+#[test]
+fn test_destructured_block_parameter_type_error() {
+    let source = r#"
+[[1, 2]].each_with_index { |(a, b), i| a.upcase }
+"#;
+
+    let (genv, _lenv) = analyze(source);
+
+    assert_eq!(genv.type_errors.len(), 1);
+    assert_eq!(genv.type_errors[0].method_name, "upcase");
+    assert_eq!(genv.type_errors[0].receiver_type.show(), "Integer");
+}
+
+#[test]
+fn test_block_parameters_auto_splat_array_element() {
+    let source = r#"
+[["a", "b"]].select { |a, b| y = b.upcase }
+"#;
+
+    let (genv, lenv) = analyze(source);
+
+    assert_eq!(genv.type_errors.len(), 0);
+
+    let y_vtx = lenv.get_var("y").unwrap();
+    assert_eq!(genv.get_vertex(y_vtx).unwrap().show(), "String");
+}
+
+#[test]
+fn test_numbered_block_parameters() {
+    let source = r#"
+[1, 2].select { _1.upcase }
+[1, 2].each_with_index { x = _2 }
+"#;
+
+    let (genv, lenv) = analyze(source);
+
+    assert_eq!(genv.type_errors.len(), 1);
+    assert_eq!(genv.type_errors[0].receiver_type.show(), "Integer");
+
+    let x_vtx = lenv.get_var("x").unwrap();
+    assert_eq!(genv.get_vertex(x_vtx).unwrap().show(), "Integer");
+}
+
+#[test]
+fn test_it_block_parameter() {
+    let source = r#"
+["a"].select { it.upcase }
+[1].select { it.upcase }
+"#;
+
+    let (genv, _lenv) = analyze(source);
+
+    assert_eq!(genv.type_errors.len(), 1);
+    assert_eq!(genv.type_errors[0].method_name, "upcase");
+    assert_eq!(genv.type_errors[0].receiver_type.show(), "Integer");
+}
+
+#[test]
+fn test_locals_do_not_leak_across_methods() {
+    let source = r#"
+def first
+  value = 1
+end
+
+def second
+  value = value.upcase
+end
+"#;
+
+    let (genv, _lenv) = analyze(source);
+
+    // `value` in second is its own (nil) local, not first's Integer
+    assert_eq!(genv.type_errors.len(), 0);
+}
+
+#[test]
+fn test_locals_do_not_leak_into_class_and_module_bodies() {
+    let source = r#"
+value = 1
+
+module Admin
+  value = value.upcase
+end
+
+class User
+  value = value.upcase
+end
+"#;
+
+    let (genv, _lenv) = analyze(source);
+
+    // Class and module bodies do not see the top level's Integer `value`
+    assert_eq!(genv.type_errors.len(), 0);
+}
+
+#[test]
+fn test_parameters_do_not_leak_across_methods() {
+    let source = r#"
+def first(name = 1)
+  name
+end
+
+def second
+  name = name.upcase if false
+end
+"#;
+
+    let (genv, _lenv) = analyze(source);
+
+    assert_eq!(genv.type_errors.len(), 0);
+}
+
+#[test]
+fn test_block_assigns_enclosing_local() {
+    let source = r#"
+def run
+  total = "start"
+  [1].select { |i| total = i }
+  total.upcase
+end
+"#;
+
+    let (genv, _lenv) = analyze(source);
+
+    // The block rebinds the method's local, so `total` may be Integer afterwards
+    assert_eq!(genv.type_errors.len(), 1);
+    assert_eq!(genv.type_errors[0].receiver_type.show(), "Integer");
+}
+
+#[test]
+fn test_calls_in_conditionals_are_checked() {
+    let source = r#"
+if ready?
+  1.upcase
+elsif done?
+  2.upcase
+else
+  "ok".upcase
+end
+3.upcase unless ready?
+"#;
+
+    let (genv, _lenv) = analyze(source);
+
+    assert_eq!(genv.type_errors.len(), 3);
+}
+
+#[test]
+fn test_branch_assignment_joins_after_if() {
+    let source = r#"
+x = 1
+if ready?
+  x = "one"
+end
+y = x
+"#;
+
+    let (genv, lenv) = analyze(source);
+
+    let y_vtx = lenv.get_var("y").unwrap();
+    assert_eq!(genv.get_vertex(y_vtx).unwrap().show(), "(Integer | String)");
+}
+
+#[test]
+fn test_branch_assignment_does_not_leak_into_else() {
+    let source = r#"
+x = "name"
+if ready?
+  x = 1
+else
+  x.upcase
+end
+"#;
+
+    let (genv, _lenv) = analyze(source);
+
+    // The else branch still sees the String binding
+    assert_eq!(genv.type_errors.len(), 0);
+}
+
+#[test]
+fn test_conditional_values() {
+    let source = r#"
+a = ready? ? 1 : "one"
+b = if ready? then 1 end
+c = case a
+    when 1 then :one
+    else 1.0
+    end
+"#;
+
+    let (genv, lenv) = analyze(source);
+
+    let a_vtx = lenv.get_var("a").unwrap();
+    assert_eq!(genv.get_vertex(a_vtx).unwrap().show(), "(Integer | String)");
+    let b_vtx = lenv.get_var("b").unwrap();
+    assert_eq!(genv.get_vertex(b_vtx).unwrap().show(), "(Integer | nil)");
+    let c_vtx = lenv.get_var("c").unwrap();
+    assert_eq!(genv.get_vertex(c_vtx).unwrap().show(), "(Float | Symbol)");
+}
+
+#[test]
+fn test_loop_reassignment_reaches_loop_body() {
+    let source = r#"
+x = "name"
+while ready?
+  x.upcase
+  x = 1
+end
+"#;
+
+    let (genv, _lenv) = analyze(source);
+
+    // The second iteration calls upcase on the Integer assigned by the first
+    assert_eq!(genv.type_errors.len(), 1);
+    assert_eq!(genv.type_errors[0].receiver_type.show(), "Integer");
+}
+
+#[test]
+fn test_for_loop_index_and_join() {
+    let source = r#"
+total = nil
+for item in ["a", "b"]
+  total = item.upcase
+end
+result = total
+"#;
+
+    let (genv, lenv) = analyze(source);
+
+    assert_eq!(genv.type_errors.len(), 0);
+
+    let result_vtx = lenv.get_var("result").unwrap();
+    assert_eq!(
+        genv.get_vertex(result_vtx).unwrap().show(),
+        "(String | nil)"
+    );
+}
+
+#[test]
+fn test_method_return_type_inferred_from_body_and_return() {
+    let source = r#"
+def label(n)
+  return "none" if n.zero?
+  :some
+end
+
+def size = 1
+
+x = label(1)
+y = size
+"#;
+
+    let (genv, lenv) = analyze(source);
+
+    let x_vtx = lenv.get_var("x").unwrap();
+    assert_eq!(genv.get_vertex(x_vtx).unwrap().show(), "(String | Symbol)");
+    let y_vtx = lenv.get_var("y").unwrap();
+    assert_eq!(genv.get_vertex(y_vtx).unwrap().show(), "Integer");
+}
+
+#[test]
+fn test_inferred_return_type_is_checked() {
+    let source = r#"
+def count
+  return 0 if ready?
+  1
+end
+
+count.upcase
+"#;
+
+    let (genv, _lenv) = analyze(source);
+
+    assert_eq!(genv.type_errors.len(), 1);
+    assert_eq!(genv.type_errors[0].receiver_type.show(), "Integer");
+    assert_eq!(genv.type_errors[0].method_name, "upcase");
+}
+
+#[test]
+fn test_next_value_flows_into_block_result() {
+    let source = r#"
+def run
+  yield 1
+end
+
+x = run { |i| next "skip" if ready?; :done }
+"#;
+
+    let (genv, lenv) = analyze(source);
+
+    let x_vtx = lenv.get_var("x").unwrap();
+    assert_eq!(genv.get_vertex(x_vtx).unwrap().show(), "(String | Symbol)");
+}
+
+#[test]
+fn test_break_value_flows_into_call_result() {
+    let source = r#"
+def run
+  yield 1
+  nil
+end
+
+x = run { |i| break "stop" }
+y = while ready?
+  break 1
+end
+"#;
+
+    let (genv, lenv) = analyze(source);
+
+    let x_vtx = lenv.get_var("x").unwrap();
+    assert_eq!(genv.get_vertex(x_vtx).unwrap().show(), "(String | nil)");
+    let y_vtx = lenv.get_var("y").unwrap();
+    assert_eq!(genv.get_vertex(y_vtx).unwrap().show(), "(Integer | nil)");
+}
+
+#[test]
+fn test_code_after_raise_and_return_is_unreachable() {
+    let source = r#"
+def fail_fast
+  raise "boom"
+  "never".upcase
+end
+
+def early(x)
+  if x
+    return 1
+  else
+    raise ArgumentError
+  end
+  puts "done"
+end
+"#;
+
+    let (genv, _lenv) = analyze(source);
+
+    let lines: Vec<usize> = genv.unreachable_code.iter().map(|loc| loc.line).collect();
+    assert_eq!(lines, vec![4, 13]);
+}
+
+#[test]
+fn test_branch_ending_with_return_does_not_join() {
+    let source = r#"
+def name(x)
+  value = "name"
+  if x
+    value = 1
+    return value
+  end
+  value.upcase
+end
+"#;
+
+    let (genv, _lenv) = analyze(source);
+
+    // Only the String binding reaches the call
+    assert_eq!(genv.type_errors.len(), 0);
+    assert!(genv.unreachable_code.is_empty());
+}
+
+#[test]
+fn test_rescue_binds_standard_error_by_default() {
+    let source = r#"
+begin
+  risky
+rescue => e
+  e.mesage
+end
+"#;
+
+    let (genv, lenv) = analyze(source);
+
+    assert_eq!(genv.type_errors.len(), 1);
+    assert_eq!(genv.type_errors[0].receiver_type.show(), "StandardError");
+    assert_eq!(genv.type_errors[0].method_name, "mesage");
+
+    let e_vtx = lenv.get_var("e").unwrap();
+    assert_eq!(genv.get_vertex(e_vtx).unwrap().show(), "StandardError");
+}
+
+#[test]
+fn test_rescue_binds_union_of_rescued_classes() {
+    let source = r#"
+begin
+  risky
+rescue KeyError, ArgumentError => e
+  error = e
+end
+"#;
+
+    let (genv, lenv) = analyze(source);
+
+    let error_vtx = lenv.get_var("error").unwrap();
+    assert_eq!(
+        genv.get_vertex(error_vtx).unwrap().show(),
+        "(ArgumentError | KeyError)"
+    );
+}
+
+#[test]
+fn test_rescue_binds_custom_exception_class() {
+    let source = r#"
+class MyError < StandardError
+end
+
+begin
+  risky
+rescue MyError => e
+  error = e
+  e.message.upcase
+end
+"#;
+
+    let (genv, lenv) = analyze(source);
+
+    // MyError inherits message from StandardError
+    assert_eq!(genv.type_errors.len(), 0);
+    let error_vtx = lenv.get_var("error").unwrap();
+    assert_eq!(genv.get_vertex(error_vtx).unwrap().show(), "MyError");
+}
+
+#[test]
+fn test_rescue_of_unknown_class_is_untyped() {
+    let source = r#"
+class LibError < Lib::Error
+end
+
+begin
+  risky
+rescue Errno::ENOENT => e
+  first = e
+  e.message
+rescue LibError, KeyError => e
+  second = e
+  e.message
+end
+"#;
+
+    let (genv, lenv) = analyze(source);
+
+    // Classes whose methods are unknown are not checked
+    assert_eq!(genv.type_errors.len(), 0);
+    for name in ["first", "second"] {
+        let vtx = lenv.get_var(name).unwrap();
+        assert_eq!(genv.get_vertex(vtx).unwrap().show(), "untyped");
+    }
+}
+
+#[test]
+fn test_begin_clauses_are_checked() {
+    let source = r#"
+begin
+  1.upcase
+rescue
+  2.upcase
+else
+  3.upcase
+ensure
+  4.upcase
+end
+"#;
+
+    let (genv, _lenv) = analyze(source);
+
+    assert_eq!(genv.type_errors.len(), 4);
+}
+
+#[test]
+fn test_begin_value_is_union_of_body_and_rescues() {
+    let source = r#"
+x = begin
+  1
+rescue KeyError
+  "fallback"
+end
+
+y = begin
+  1
+rescue KeyError
+  "fallback"
+else
+  :ok
+end
+
+z = (1 rescue "none")
+"#;
+
+    let (genv, lenv) = analyze(source);
+
+    let x_vtx = lenv.get_var("x").unwrap();
+    assert_eq!(genv.get_vertex(x_vtx).unwrap().show(), "(Integer | String)");
+    let y_vtx = lenv.get_var("y").unwrap();
+    assert_eq!(genv.get_vertex(y_vtx).unwrap().show(), "(String | Symbol)");
+    let z_vtx = lenv.get_var("z").unwrap();
+    assert_eq!(genv.get_vertex(z_vtx).unwrap().show(), "(Integer | String)");
+}
+
+#[test]
+fn test_rescue_sees_bindings_from_body() {
+    let source = r#"
+x = 1
+begin
+  x = "one"
+  risky
+rescue
+  y = x
+end
+"#;
+
+    let (genv, lenv) = analyze(source);
+
+    // The exception may be raised before or after the assignment
+    let y_vtx = lenv.get_var("y").unwrap();
+    assert_eq!(genv.get_vertex(y_vtx).unwrap().show(), "(Integer | String)");
+}
+
+#[test]
+fn test_method_body_with_rescue() {
+    let source = r#"
+def fetch
+  :ok
+rescue KeyError => e
+  e.message
+end
+
+result = fetch
+"#;
+
+    let (genv, lenv) = analyze(source);
+
+    assert_eq!(genv.type_errors.len(), 0);
+
+    let result_vtx = lenv.get_var("result").unwrap();
+    assert_eq!(
+        genv.get_vertex(result_vtx).unwrap().show(),
+        "(String | Symbol)"
+    );
+}
+
+#[test]
+fn test_case_in_capture_narrowed_by_constant() {
+    let source = r#"
+value = ready? ? "name" : 1
+case value
+in String => s
+  s.upcase
+in Integer => n
+  n.upcase
+end
+"#;
+
+    let (genv, lenv) = analyze(source);
+
+    assert_eq!(genv.type_errors.len(), 1);
+    assert_eq!(genv.type_errors[0].receiver_type.show(), "Integer");
+
+    let s_vtx = lenv.get_var("s").unwrap();
+    assert_eq!(genv.get_vertex(s_vtx).unwrap().show(), "String");
+}
+
+#[test]
+fn test_case_in_untyped_value_typed_by_pattern() {
+    let source = r#"
+case input
+in {name: String => name, age: Integer => age}
+  name.upcase
+  age.upcase
+end
+"#;
+
+    let (genv, _lenv) = analyze(source);
+
+    assert_eq!(genv.type_errors.len(), 1);
+    assert_eq!(genv.type_errors[0].receiver_type.show(), "Integer");
+}
+
+#[test]
+fn test_case_in_array_pattern_deconstructs_elements() {
+    let source = r#"
+case ["a", "b"]
+in [first, *rest]
+  x = first
+  y = rest
+end
+
+case [1, 2]
+in [*, Integer => found, *]
+  z = found
+end
+"#;
+
+    let (genv, lenv) = analyze(source);
+
+    let x_vtx = lenv.get_var("x").unwrap();
+    assert_eq!(genv.get_vertex(x_vtx).unwrap().show(), "String");
+    let y_vtx = lenv.get_var("y").unwrap();
+    assert_eq!(genv.get_vertex(y_vtx).unwrap().show(), "Array[String]");
+    let z_vtx = lenv.get_var("z").unwrap();
+    assert_eq!(genv.get_vertex(z_vtx).unwrap().show(), "Integer");
+}
+
+#[test]
+fn test_case_in_branches_and_value() {
+    let source = r#"
+result = case 1
+         in 0 then :zero
+         in Integer | Float => number then number
+         else "other"
+         end
+"#;
+
+    let (genv, lenv) = analyze(source);
+
+    let number_vtx = lenv.get_var("number").unwrap();
+    assert_eq!(genv.get_vertex(number_vtx).unwrap().show(), "Integer");
+    let result_vtx = lenv.get_var("result").unwrap();
+    assert_eq!(
+        genv.get_vertex(result_vtx).unwrap().show(),
+        "(Integer | String | Symbol)"
+    );
+}
+
+#[test]
+fn test_pin_and_guard_expressions_are_checked() {
+    let source = r#"
+limit = 1
+case 2
+in ^limit
+  :same
+in Integer => n if n.upcase
+  :big
+in ^(limit.upcase)
+  :other
+end
+
+1 => Integer => one
+matched = (1 in String)
+"#;
+
+    let (genv, lenv) = analyze(source);
+
+    assert_eq!(genv.type_errors.len(), 2);
+
+    let one_vtx = lenv.get_var("one").unwrap();
+    assert_eq!(genv.get_vertex(one_vtx).unwrap().show(), "Integer");
+    let matched_vtx = lenv.get_var("matched").unwrap();
+    assert_eq!(
+        genv.get_vertex(matched_vtx).unwrap().show(),
+        "(FalseClass | TrueClass)"
+    );
+}
+
+#[test]
+fn test_is_a_narrows_then_and_else_branches() {
+    let source = r#"
+x = ready? ? "name" : 1
+if x.is_a?(String)
+  x.upcase
+else
+  x.upcase
+end
+"#;
+
+    let (genv, _lenv) = analyze(source);
+
+    // Only the else branch (Integer) is reported
+    assert_eq!(genv.type_errors.len(), 1);
+    assert_eq!(genv.type_errors[0].receiver_type.show(), "Integer");
+}
+
+#[test]
+fn test_is_a_superclass_narrows_subclass() {
+    let source = r#"
+class Base
+end
+
+class User < Base
+  def check
+    x = self
+    if x.is_a?(Base)
+      x.abs
+    else
+      x.upcase
+    end
+
+    case x
+    when Base
+      x.round
+    end
+  end
+end
+"#;
+
+    // `is_a?` is inherited from Object
+    let mut genv = GlobalEnv::new();
+    genv.register_builtin_method(
+        Type::instance("Object"),
+        "is_a?",
+        Type::instance("TrueClass"),
+    );
+    let (genv, _lenv) = analyze_with(genv, source);
+
+    // A User is a Base: the guarded branches keep it, the else branch drops it
+    let mut method_names: Vec<&str> = genv
+        .type_errors
+        .iter()
+        .map(|error| error.method_name.as_str())
+        .collect();
+    method_names.sort_unstable();
+    assert_eq!(method_names, vec!["abs", "round"]);
+    assert!(genv
+        .type_errors
+        .iter()
+        .all(|error| error.receiver_type.show() == "User"));
+}
+
+#[test]
+fn test_case_when_class_narrows_branches() {
+    let source = r#"
+x = ready? ? "name" : 1.5
+case x
+when String
+  a = x
+when Float, Integer
+  b = x
+else
+  c = x
+end
+"#;
+
+    let (genv, lenv) = analyze(source);
+
+    let a_vtx = lenv.get_var("a").unwrap();
+    assert_eq!(genv.get_vertex(a_vtx).unwrap().show(), "String");
+    let b_vtx = lenv.get_var("b").unwrap();
+    assert_eq!(genv.get_vertex(b_vtx).unwrap().show(), "Float");
+    let c_vtx = lenv.get_var("c").unwrap();
+    assert_eq!(genv.get_vertex(c_vtx).unwrap().show(), "untyped");
+}
+
+#[test]
+fn test_nil_narrowing() {
+    let source = r#"
+x = ready? ? "name" : nil
+if x.nil?
+  a = x
+else
+  b = x
+end
+c = x && x.upcase
+if x
+  d = x
+end
+"#;
+
+    let (genv, lenv) = analyze(source);
+
+    assert_eq!(genv.type_errors.len(), 0);
+
+    let a_vtx = lenv.get_var("a").unwrap();
+    assert_eq!(genv.get_vertex(a_vtx).unwrap().show(), "nil");
+    let b_vtx = lenv.get_var("b").unwrap();
+    assert_eq!(genv.get_vertex(b_vtx).unwrap().show(), "String");
+    let d_vtx = lenv.get_var("d").unwrap();
+    assert_eq!(genv.get_vertex(d_vtx).unwrap().show(), "String");
+
+    // The join after the conditional sees the original union
+    let x_vtx = lenv.get_var("x").unwrap();
+    assert_eq!(genv.get_vertex(x_vtx).unwrap().show(), "(String | nil)");
+}
+
+#[test]
+fn test_respond_to_and_case_equality_narrowing() {
+    let source = r#"
+x = ready? ? "name" : 1
+if x.respond_to?(:upcase)
+  x.upcase
+end
+if Integer === x
+  x.upcase
+end
+"#;
+
+    let (genv, _lenv) = analyze(source);
+
+    assert_eq!(genv.type_errors.len(), 1);
+    assert_eq!(genv.type_errors[0].receiver_type.show(), "Integer");
+}
+
+#[test]
+fn test_guard_clause_narrowing() {
+    let source = r#"
+def shout(value)
+  return unless value.is_a?(String)
+  value.upcase
+end
+
+def label(value)
+  value = ready? ? "name" : nil
+  raise ArgumentError if value.nil?
+  value.upcase
+end
+"#;
+
+    let (genv, _lenv) = analyze(source);
+
+    assert_eq!(genv.type_errors.len(), 0);
+}
+
+#[test]
+fn test_super_returns_superclass_method_value() {
+    let source = r#"
+class Base
+  def label
+    1
+  end
+end
+
+class User < Base
+  def label
+    super.upcase
+  end
+end
+"#;
+
+    let (genv, _lenv) = analyze(source);
+
+    assert_eq!(genv.type_errors.len(), 1);
+    assert_eq!(genv.type_errors[0].receiver_type.show(), "Integer");
+    assert!(genv.missing_super_methods.is_empty());
+}
+
+#[test]
+fn test_implicit_super_forwards_parameters() {
+    let source = r#"
+class Base
+  def greet(name, greeting)
+    greeting.upcase
+  end
+end
+
+class User < Base
+  def greet(name = "Alice", greeting = 1)
+    super
+  end
+end
+
+class Admin < Base
+  def greet(name)
+    super(name, "hello")
+  end
+end
+"#;
+
+    let (genv, _lenv) = analyze(source);
+
+    assert_eq!(genv.type_errors.len(), 1);
+    assert_eq!(genv.type_errors[0].receiver_type.show(), "Integer");
+}
+
+#[test]
+fn test_super_through_included_module_and_namespace() {
+    let source = r#"
+module Greeting
+  def hello
+    "hi"
+  end
+end
+
+module Api
+  class Base
+    def id
+      1
+    end
+  end
+
+  class User < Base
+    include Greeting
+
+    def hello
+      super.upcase
+    end
+
+    def id
+      super.upcase
+    end
+  end
+end
+"#;
+
+    let (genv, _lenv) = analyze(source);
+
+    assert_eq!(genv.type_errors.len(), 1);
+    assert_eq!(genv.type_errors[0].receiver_type.show(), "Integer");
+    assert!(genv.missing_super_methods.is_empty());
+}
+
+#[test]
+fn test_inherited_method_call() {
+    let source = r#"
+module Greeting
+  def greeting
+    "Hello"
+  end
+end
+
+class Base
+  include Greeting
+
+  def name
+    "Alice"
+  end
+end
+
+class User < Base
+  def label
+    self.name.upcase + self.greeting.downcase
+  end
+
+  def id
+    self.name.abs
+  end
+end
+"#;
+
+    let (genv, _lenv) = analyze(source);
+
+    // Methods of the superclass and its included modules resolve on the
+    // subclass, with their return types
+    assert_eq!(genv.type_errors.len(), 1);
+    assert_eq!(genv.type_errors[0].method_name, "abs");
+    assert_eq!(genv.type_errors[0].receiver_type.show(), "String");
+}
+
+#[test]
+fn test_super_without_superclass_method() {
+    let source = r#"
+class User < Object
+  def initialize(name)
+    super()
+  end
+
+  def greet
+    super
+  end
+
+  def self.build
+    super
+  end
+end
+
+class Admin
+  def greet
+    super
+  end
+end
+
+class Record < ActiveRecord::Base
+  def save
+    super
+  end
+end
+
+module Auditable
+  def save
+    super
+  end
+end
+"#;
+
+    let (genv, _lenv) = analyze(source);
+
+    assert_eq!(genv.missing_super_methods.len(), 1);
+    assert_eq!(genv.missing_super_methods[0].method_name, "greet");
+    assert_eq!(genv.missing_super_methods[0].receiver_type.show(), "User");
+}
+
+#[test]
+fn test_alias_copies_signature() {
+    let source = r#"
+class User
+  def name
+    1
+  end
+
+  alias_method :full_name, :name
+  alias nickname name
+
+  def label
+    full_name.upcase
+  end
+
+  def short
+    nickname.upcase
+  end
+end
+"#;
+
+    let (genv, _lenv) = analyze(source);
+
+    assert_eq!(genv.type_errors.len(), 2);
+    assert!(genv
+        .type_errors
+        .iter()
+        .all(|error| error.receiver_type.show() == "Integer"));
+}
+
+#[test]
+fn test_define_method_registers_method() {
+    let source = r#"
+class User
+  define_method(:age) { 1 }
+
+  define_method("greet") do |name|
+    name
+  end
+
+  def label
+    age.upcase
+  end
+end
+"#;
+
+    let (genv, _lenv) = analyze(source);
+
+    assert_eq!(genv.type_errors.len(), 1);
+    assert_eq!(genv.type_errors[0].receiver_type.show(), "Integer");
+    assert!(genv
+        .resolve_method(&Type::instance("User"), "greet")
+        .is_some());
+}
+
+#[test]
+fn test_define_method_with_proc_body_is_untyped() {
+    let source = r#"
+class User
+  formatter = proc { |value| value.to_s }
+  define_method(:label, &formatter)
+  define_method(:title, instance_method(:to_s))
+end
+
+User.new.label.upcase
+User.new.title
+"#;
+
+    let (genv, _lenv) = analyze(source);
+
+    // The methods exist, but their return types are unknown
+    assert_eq!(genv.type_errors.len(), 0);
+    assert!(genv
+        .resolve_method(&Type::instance("User"), "label")
+        .is_some());
+}
+
+#[test]
+fn test_delegated_methods_forward_target_types() {
+    let source = r#"
+class Playlist
+  extend Forwardable
+
+  def_delegator :@length, :abs, :magnitude
+  delegate :upcase, to: :title, prefix: :display
+
+  def initialize
+    @length = 1.5
+  end
+
+  def title
+    "mix"
+  end
+
+  def label
+    display_upcase.downcase
+  end
+
+  def size
+    magnitude.upcase
+  end
+end
+"#;
+
+    let (genv, _lenv) = analyze(source);
+
+    assert_eq!(genv.type_errors.len(), 1);
+    assert_eq!(genv.type_errors[0].receiver_type.show(), "Float");
+    assert!(genv
+        .resolve_method(&Type::instance("Playlist"), "display_upcase")
+        .is_some());
+}
+
+#[test]
+fn test_method_missing_classes_are_open() {
+    let source = r#"
+class Proxy
+  def method_missing(name, *args)
+    name
+  end
+
+  def run
+    self.anything
+  end
+end
+
+class LoggingProxy < Proxy
+  def log
+    self.whatever
+  end
+end
+
+class User
+  def run
+    self.anything
+  end
+end
+"#;
+
+    let (genv, _lenv) = analyze(source);
+
+    assert_eq!(genv.type_errors.len(), 1);
+    assert_eq!(genv.type_errors[0].receiver_type.show(), "User");
+    assert!(genv.dynamic_calls.is_empty());
+}
+
+#[test]
+fn test_open_class_info_policy() {
+    let source = r#"
+class Settings
+  def respond_to_missing?(name, include_private = false)
+    true
+  end
+
+  def load
+    self.timeout
+  end
+end
+"#;
+
+    let mut genv = GlobalEnv::new();
+    genv.set_open_class_policy("Settings", OpenClassPolicy::Info);
+    let (genv, _lenv) = analyze_with(genv, source);
+
+    assert!(genv.type_errors.is_empty());
+    assert_eq!(genv.dynamic_calls.len(), 1);
+    assert_eq!(genv.dynamic_calls[0].method_name, "timeout");
+}
+
+#[test]
+fn test_syntax_error_keeps_rest_of_file_analyzed() {
+    let source = r#"
+class User
+  def broken
+    puts(1,
+  end
+
+  def name
+    x = 123
+    x.upcase
+  end
+end
+"#;
+
+    let (genv, _lenv) = analyze(source);
+
+    assert_eq!(genv.type_errors.len(), 1);
+    assert_eq!(genv.type_errors[0].method_name, "upcase");
+}
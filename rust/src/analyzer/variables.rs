@@ -7,9 +7,11 @@
 
 use crate::env::{GlobalEnv, LocalEnv};
 use crate::graph::{ChangeSet, VertexId};
-use crate::types::Type;
 
 /// Install local variable write: x = value
+///
+/// A local of an enclosing scope is rebound there (blocks assigning outer
+/// locals); otherwise the local is bound in the current scope.
 pub fn install_local_var_write(
     genv: &mut GlobalEnv,
     lenv: &mut LocalEnv,
@@ -18,14 +20,27 @@ pub fn install_local_var_write(
     value_vtx: VertexId,
 ) -> VertexId {
     let var_vtx = genv.new_vertex();
+    genv.scope_manager.assign_var(var_name.clone(), var_vtx);
     lenv.new_var(var_name, var_vtx);
     changes.add_edge(value_vtx, var_vtx);
     var_vtx
 }
 
 /// Install local variable read: x
-pub fn install_local_var_read(lenv: &LocalEnv, var_name: &str) -> Option<VertexId> {
-    lenv.get_var(var_name)
+///
+/// Locals are looked up through the enclosing scopes up to the nearest `def`.
+pub fn install_local_var_read(genv: &GlobalEnv, var_name: &str) -> Option<VertexId> {
+    genv.scope_manager.lookup_var(var_name)
+}
+
+/// Bind a local variable (e.g., a parameter) in the current scope
+///
+/// The binding is also recorded in LocalEnv, which keeps every local for reporting.
+pub fn bind_local_var(genv: &mut GlobalEnv, lenv: &mut LocalEnv, name: String, vtx: VertexId) {
+    genv.scope_manager
+        .current_scope_mut()
+        .set_local_var(name.clone(), vtx);
+    lenv.new_var(name, vtx);
 }
 
 /// Install instance variable write: @name = value
@@ -47,16 +62,14 @@ pub fn install_ivar_read(genv: &GlobalEnv, ivar_name: &str) -> Option<VertexId>
 /// Install self node
 /// Uses the fully qualified name if available (e.g., Api::V1::User instead of just User)
 pub fn install_self(genv: &mut GlobalEnv) -> VertexId {
-    if let Some(qualified_name) = genv.scope_manager.current_qualified_name() {
-        genv.new_source(Type::instance(&qualified_name))
-    } else {
-        genv.new_source(Type::instance("Object"))
-    }
+    let self_type = genv.current_self_type();
+    genv.new_source(self_type)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::Type;
 
     #[test]
     fn test_install_self_at_top_level() {
@@ -68,8 +81,33 @@ mod tests {
 
     #[test]
     fn test_local_var_read_not_found() {
-        let lenv = LocalEnv::new();
+        let genv = GlobalEnv::new();
+
+        assert_eq!(install_local_var_read(&genv, "unknown"), None);
+    }
+
+    #[test]
+    fn test_local_var_not_visible_across_methods() {
+        let mut genv = GlobalEnv::new();
+        let mut lenv = LocalEnv::new();
+        let mut changes = ChangeSet::new();
+
+        // def first; x = "hello"; end
+        genv.enter_method("first".to_string());
+        let value_vtx = genv.new_source(Type::string());
+        let x_vtx = install_local_var_write(
+            &mut genv,
+            &mut lenv,
+            &mut changes,
+            "x".to_string(),
+            value_vtx,
+        );
+        assert_eq!(install_local_var_read(&genv, "x"), Some(x_vtx));
+        genv.exit_scope();
 
-        assert_eq!(install_local_var_read(&lenv, "unknown"), None);
+        // def second; x; end
+        genv.enter_method("second".to_string());
+        assert_eq!(install_local_var_read(&genv, "x"), None);
+        genv.exit_scope();
     }
 }
@@ -1,15 +1,23 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
 #[cfg(feature = "ruby-ffi")]
 use crate::rbs::loader::RbsMethodInfo;
 
+/// Version of the cached data
+///
+/// Bump it whenever the cache layout or the set of loaded methods changes,
+/// so caches written by an older build are rebuilt.
+pub const CACHE_FORMAT_VERSION: u32 = 1;
+
 /// Binary cache for RBS method definitions
 #[derive(Serialize, Deserialize, Debug)]
 pub struct RbsCache {
+    /// Cache format version (see `CACHE_FORMAT_VERSION`)
+    pub format_version: u32,
     /// MethodRay version
     pub version: String,
     /// RBS gem version
@@ -18,6 +26,8 @@ pub struct RbsCache {
     pub methods: Vec<SerializableMethodInfo>,
     /// Cache creation timestamp
     pub timestamp: SystemTime,
+    /// Extra RBS signature directories loaded into the cache
+    pub signature_paths: Vec<PathBuf>,
 }
 
 /// Serializable version of RbsMethodInfo
@@ -39,15 +49,34 @@ impl SerializableMethodInfo {
 
 #[allow(dead_code)]
 impl RbsCache {
-    /// Get user cache file path (in ~/.cache/methodray/)
-    pub fn cache_path() -> Result<PathBuf> {
+    /// Get the user cache directory (~/.cache/methodray/)
+    pub fn cache_dir() -> Result<PathBuf> {
         let cache_dir = dirs::cache_dir()
             .context("Failed to get cache directory")?
             .join("methodray");
 
         fs::create_dir_all(&cache_dir).context("Failed to create cache directory")?;
 
-        Ok(cache_dir.join("rbs_cache.bin"))
+        Ok(cache_dir)
+    }
+
+    /// Get the user cache file path for a set of signature paths
+    pub fn cache_path(signature_paths: &[PathBuf]) -> Result<PathBuf> {
+        Ok(Self::cache_file(&Self::cache_dir()?, signature_paths))
+    }
+
+    /// Cache file in `cache_dir` for a set of signature paths
+    ///
+    /// Projects with their own signatures get their own cache file, keyed by
+    /// the sorted paths, so they don't overwrite each other's cache.
+    pub fn cache_file(cache_dir: &Path, signature_paths: &[PathBuf]) -> PathBuf {
+        if signature_paths.is_empty() {
+            return cache_dir.join("rbs_cache.bin");
+        }
+        cache_dir.join(format!(
+            "rbs_cache-{}.bin",
+            signature_paths_key(signature_paths)
+        ))
     }
 
     /// Get bundled cache path (shipped with gem)
@@ -72,32 +101,47 @@ impl RbsCache {
         None
     }
 
-    /// Load cache from disk
-    /// Tries bundled cache first, then user cache
-    pub fn load() -> Result<Self> {
+    /// Load the cache for a set of signature paths
+    ///
+    /// Without signature paths, tries the bundled cache first, then the user
+    /// cache. The bundled cache has no project signatures, so it is skipped
+    /// when signature paths are configured.
+    pub fn load(signature_paths: &[PathBuf]) -> Result<Self> {
         // Try bundled cache first (shipped with gem)
-        if let Some(bundled_path) = Self::bundled_cache_path() {
-            if let Ok(bytes) = fs::read(&bundled_path) {
-                if let Ok(cache) = bincode::deserialize::<Self>(&bytes) {
+        if signature_paths.is_empty() {
+            if let Some(bundled_path) = Self::bundled_cache_path() {
+                if let Ok(cache) = Self::read(&bundled_path) {
                     return Ok(cache);
                 }
             }
         }
 
         // Fall back to user cache
-        let path = Self::cache_path()?;
-        let bytes = fs::read(&path)
+        Self::read(&Self::cache_path(signature_paths)?)
+    }
+
+    /// Read a cache file
+    pub fn read(path: &Path) -> Result<Self> {
+        let bytes = fs::read(path)
             .with_context(|| format!("Failed to read cache from {}", path.display()))?;
 
-        bincode::deserialize(&bytes).context("Failed to deserialize cache")
+        let cache: Self = bincode::deserialize(&bytes).context("Failed to deserialize cache")?;
+        if cache.format_version != CACHE_FORMAT_VERSION {
+            anyhow::bail!("Cache at {} has an outdated format", path.display());
+        }
+        Ok(cache)
     }
 
-    /// Save cache to disk
+    /// Save cache to disk, in the user cache file for its signature paths
     pub fn save(&self) -> Result<()> {
-        let path = Self::cache_path()?;
+        self.save_to(&Self::cache_path(&self.signature_paths)?)
+    }
+
+    /// Save cache to a file
+    pub fn save_to(&self, path: &Path) -> Result<()> {
         let bytes = bincode::serialize(self).context("Failed to serialize cache")?;
 
-        fs::write(&path, bytes)
+        fs::write(path, bytes)
             .with_context(|| format!("Failed to write cache to {}", path.display()))?;
 
         Ok(())
@@ -105,7 +149,14 @@ impl RbsCache {
 
     /// Check if cache is valid
     pub fn is_valid(&self, current_version: &str, current_rbs_version: &str) -> bool {
-        self.version == current_version && self.rbs_version == current_rbs_version
+        self.format_version == CACHE_FORMAT_VERSION
+            && self.version == current_version
+            && self.rbs_version == current_rbs_version
+    }
+
+    /// Check if the cache was built with exactly these signature paths (in any order)
+    pub fn has_signature_paths(&self, signature_paths: &[PathBuf]) -> bool {
+        sorted_paths(&self.signature_paths) == sorted_paths(signature_paths)
     }
 
     /// Get methods for registration (works without ruby-ffi feature)
@@ -133,6 +184,7 @@ impl RbsCache {
         methods: Vec<RbsMethodInfo>,
         version: String,
         rbs_version: String,
+        signature_paths: Vec<PathBuf>,
     ) -> Self {
         let serializable_methods = methods
             .into_iter()
@@ -145,12 +197,35 @@ impl RbsCache {
             .collect();
 
         Self {
+            format_version: CACHE_FORMAT_VERSION,
             version,
             rbs_version,
             methods: serializable_methods,
             timestamp: SystemTime::now(),
+            signature_paths,
+        }
+    }
+}
+
+fn sorted_paths(paths: &[PathBuf]) -> Vec<&PathBuf> {
+    let mut sorted: Vec<&PathBuf> = paths.iter().collect();
+    sorted.sort();
+    sorted
+}
+
+/// FNV-1a hash of the sorted signature paths
+///
+/// A stable hash is used so the CLI and the Ruby extension, built
+/// separately, agree on the cache file.
+fn signature_paths_key(signature_paths: &[PathBuf]) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for path in sorted_paths(signature_paths) {
+        for byte in path.to_string_lossy().bytes().chain([0]) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x100000001b3);
         }
     }
+    format!("{:016x}", hash)
 }
 
 #[cfg(test)]
@@ -161,6 +236,7 @@ mod tests {
     #[test]
     fn test_cache_serialization() {
         let cache = RbsCache {
+            format_version: CACHE_FORMAT_VERSION,
             version: "0.1.0".to_string(),
             rbs_version: "3.7.0".to_string(),
             methods: vec![SerializableMethodInfo {
@@ -170,6 +246,7 @@ mod tests {
                 block_param_types: None,
             }],
             timestamp: SystemTime::now(),
+            signature_paths: vec![],
         };
 
         let bytes = bincode::serialize(&cache).unwrap();
@@ -182,15 +259,44 @@ mod tests {
     #[test]
     fn test_cache_validation() {
         let cache = RbsCache {
+            format_version: CACHE_FORMAT_VERSION,
             version: "0.1.0".to_string(),
             rbs_version: "3.7.0".to_string(),
             methods: vec![],
             timestamp: SystemTime::now(),
+            signature_paths: vec![],
         };
 
         assert!(cache.is_valid("0.1.0", "3.7.0"));
         assert!(!cache.is_valid("0.2.0", "3.7.0"));
         assert!(!cache.is_valid("0.1.0", "3.8.0"));
+        assert!(cache.has_signature_paths(&[]));
+        assert!(!cache.has_signature_paths(&[PathBuf::from("/app/sig")]));
+    }
+
+    #[test]
+    fn test_cache_file_is_keyed_by_signature_paths() {
+        let dir = Path::new("/cache");
+        let app = PathBuf::from("/app/sig");
+        let vendor = PathBuf::from("/app/vendor/sig");
+
+        assert_eq!(
+            RbsCache::cache_file(dir, &[]),
+            PathBuf::from("/cache/rbs_cache.bin")
+        );
+        // Order does not matter, the paths do
+        assert_eq!(
+            RbsCache::cache_file(dir, &[app.clone(), vendor.clone()]),
+            RbsCache::cache_file(dir, &[vendor.clone(), app.clone()])
+        );
+        assert_ne!(
+            RbsCache::cache_file(dir, std::slice::from_ref(&app)),
+            RbsCache::cache_file(dir, &[vendor])
+        );
+        assert_ne!(
+            RbsCache::cache_file(dir, &[app]),
+            RbsCache::cache_file(dir, &[])
+        );
     }
 
     #[test]
@@ -209,6 +315,7 @@ mod tests {
     #[test]
     fn test_cache_methods_accessor() {
         let cache = RbsCache {
+            format_version: CACHE_FORMAT_VERSION,
             version: "0.1.0".to_string(),
             rbs_version: "3.7.0".to_string(),
             methods: vec![
@@ -226,6 +333,7 @@ mod tests {
                 },
             ],
             timestamp: SystemTime::now(),
+            signature_paths: vec![],
         };
 
         let methods = cache.methods();
@@ -242,6 +350,7 @@ mod tests {
         let cache_path = temp_dir.path().join("test_cache.bin");
 
         let original_cache = RbsCache {
+            format_version: CACHE_FORMAT_VERSION,
             version: "0.1.0".to_string(),
             rbs_version: "3.7.0".to_string(),
             methods: vec![
@@ -259,6 +368,7 @@ mod tests {
                 },
             ],
             timestamp: SystemTime::now(),
+            signature_paths: vec![],
         };
 
         // Save to temp file
@@ -279,10 +389,12 @@ mod tests {
     #[test]
     fn test_cache_with_empty_methods() {
         let cache = RbsCache {
+            format_version: CACHE_FORMAT_VERSION,
             version: "0.1.0".to_string(),
             rbs_version: "3.7.0".to_string(),
             methods: vec![],
             timestamp: SystemTime::now(),
+            signature_paths: vec![],
         };
 
         let bytes = bincode::serialize(&cache).unwrap();
@@ -295,10 +407,12 @@ mod tests {
     #[test]
     fn test_cache_validation_version_mismatch() {
         let cache = RbsCache {
+            format_version: CACHE_FORMAT_VERSION,
             version: "0.1.0".to_string(),
             rbs_version: "3.7.0".to_string(),
             methods: vec![],
             timestamp: SystemTime::now(),
+            signature_paths: vec![],
         };
 
         // Both versions must match
@@ -306,4 +420,19 @@ mod tests {
         assert!(!cache.is_valid("0.1.0", "3.7.1"));
         assert!(!cache.is_valid("0.2.0", "4.0.0"));
     }
+
+    #[test]
+    fn test_cache_validation_format_version_mismatch() {
+        let cache = RbsCache {
+            format_version: CACHE_FORMAT_VERSION - 1,
+            version: "0.1.0".to_string(),
+            rbs_version: "3.7.0".to_string(),
+            methods: vec![],
+            timestamp: SystemTime::now(),
+            signature_paths: vec![],
+        };
+
+        // A cache written by a build with another layout is rebuilt
+        assert!(!cache.is_valid("0.1.0", "3.7.0"));
+    }
 }
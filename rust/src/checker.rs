@@ -1,27 +1,54 @@
 use crate::analyzer::AstInstaller;
-use crate::diagnostics::Diagnostic;
+use crate::cache::RbsCache;
+use crate::config::Config;
+use crate::diagnostics::{Diagnostic, Suppressions};
+use crate::env::method_registry::OpenClassPolicy;
 use crate::env::{GlobalEnv, LocalEnv};
 use crate::parser;
+use crate::source_map::{LineIndex, SourceLocation};
 use anyhow::{Context, Result};
 use std::path::Path;
 
 /// File type checker
+///
+/// Creates a fresh GlobalEnv for each check.
 pub struct FileChecker {
-    // No state - creates fresh GlobalEnv for each check
+    /// Project configuration (rule severities, open class policies)
+    config: Config,
+    /// Reporting policies of open classes (`method_missing`), by class name
+    open_class_policies: Vec<(String, OpenClassPolicy)>,
+    /// RBS methods, loaded once and registered in each check
+    rbs_cache: RbsCache,
 }
 
 impl FileChecker {
-    /// Create new FileChecker
+    /// Create new FileChecker for a project configuration
+    ///
+    /// Loads the RBS cache built with the configured signature paths.
     /// Note: This is for standalone CLI usage (no Ruby runtime)
-    pub fn new() -> Result<Self> {
-        // Just verify cache exists
-        use crate::cache::RbsCache;
-        RbsCache::load().context(
-            "Failed to load RBS cache. Please run from Ruby first to generate cache:\n\
+    pub fn new(config: Config) -> Result<Self> {
+        let rbs_cache = RbsCache::load(&config.signature_paths).context(
+            "Failed to load RBS cache. Please run from Ruby in the project first to generate cache:\n\
              ruby -rmethodray -e 'MethodRay::Analyzer.new(\".\").infer_types(\"x=1\")'",
         )?;
 
-        Ok(Self {})
+        Ok(Self::with_rbs_cache(config, rbs_cache))
+    }
+
+    /// Create a FileChecker with an already loaded RBS cache
+    pub fn with_rbs_cache(config: Config, rbs_cache: RbsCache) -> Self {
+        Self {
+            config,
+            open_class_policies: Vec::new(),
+            rbs_cache,
+        }
+    }
+
+    /// Set how undefined methods on an open class are reported (`*` for all classes)
+    pub fn with_open_class_policy(mut self, class_name: &str, policy: OpenClassPolicy) -> Self {
+        self.open_class_policies
+            .push((class_name.to_string(), policy));
+        self
     }
 
     /// Check a single Ruby file
@@ -30,16 +57,30 @@ impl FileChecker {
         let source = std::fs::read_to_string(file_path)
             .with_context(|| format!("Failed to read {}", file_path.display()))?;
 
-        // Parse file
-        let parse_result = parser::parse_ruby_file(file_path)
-            .with_context(|| format!("Failed to parse {}", file_path.display()))?;
+        self.check_source(&source, file_path)
+    }
+
+    /// Check Ruby source code, such as an unsaved editor buffer
+    ///
+    /// `file_path` is the file the source belongs to; it is used for
+    /// diagnostic locations and is not read.
+    pub fn check_source(&self, source: &str, file_path: &Path) -> Result<Vec<Diagnostic>> {
+        // Parse source; syntax errors are reported and the recovered AST analyzed
+        let parse_result = parser::parse_ruby_source_tolerant(source);
 
         // Create fresh GlobalEnv for this analysis
         let mut genv = GlobalEnv::new();
-        load_rbs_from_cache(&mut genv)?;
+        load_rbs_from_cache(&mut genv, &self.rbs_cache);
+        // Policies given explicitly override the configuration
+        for (class_name, policy) in &self.config.method_missing {
+            genv.set_open_class_policy(class_name, *policy);
+        }
+        for (class_name, policy) in &self.open_class_policies {
+            genv.set_open_class_policy(class_name, *policy);
+        }
 
         let mut lenv = LocalEnv::new();
-        let mut installer = AstInstaller::new(&mut genv, &mut lenv, &source);
+        let mut installer = AstInstaller::new(&mut genv, &mut lenv, source);
 
         // Process AST
         let root = parse_result.node();
@@ -52,36 +93,47 @@ impl FileChecker {
 
         installer.finish();
 
-        // Collect diagnostics
-        let diagnostics = collect_diagnostics(&genv, file_path);
+        // Collect diagnostics, minus those silenced by `# methodray:` comments
+        let line_index = LineIndex::new(source);
+        let comments: Vec<(String, SourceLocation)> = parse_result
+            .comments()
+            .map(|comment| {
+                let text = String::from_utf8_lossy(comment.text()).to_string();
+                let location = SourceLocation::from_prism_location_with_index(
+                    &comment.location(),
+                    &line_index,
+                );
+                (text, location)
+            })
+            .collect();
+        let suppressions = Suppressions::parse(
+            source,
+            comments
+                .iter()
+                .map(|(text, location)| (text.as_str(), location.clone())),
+        );
+        let mut diagnostics = syntax_diagnostics(&parse_result, source, file_path);
+        diagnostics.extend(collect_diagnostics(&genv, file_path));
+        let diagnostics = suppressions.apply(diagnostics, file_path);
+        let diagnostics = self.config.apply_rule_severities(diagnostics);
 
         Ok(diagnostics)
     }
 }
 
-/// Load RBS methods from cache (CLI mode without Ruby runtime)
-fn load_rbs_from_cache(genv: &mut GlobalEnv) -> Result<()> {
-    use crate::cache::RbsCache;
+/// Register the RBS methods of the cache (CLI mode without Ruby runtime)
+fn load_rbs_from_cache(genv: &mut GlobalEnv, cache: &RbsCache) {
     use crate::rbs::converter::RbsTypeConverter;
     use crate::types::Type;
 
-    let cache = RbsCache::load().context(
-        "Failed to load RBS cache. Please run from Ruby first to generate cache:\n\
-         ruby -rmethodray -e 'MethodRay::Analyzer.new(\".\").infer_types(\"x=1\")'",
-    )?;
-
-    let methods = cache.methods();
-
-    for method_info in methods {
+    for method_info in cache.methods() {
         let receiver_type = Type::instance(&method_info.receiver_class);
 
         // Convert block param type strings to Type enums
-        let block_param_types = method_info.block_param_types.as_ref().map(|types| {
-            types
-                .iter()
-                .map(|s| RbsTypeConverter::parse(s))
-                .collect()
-        });
+        let block_param_types = method_info
+            .block_param_types
+            .as_ref()
+            .map(|types| types.iter().map(|s| RbsTypeConverter::parse(s)).collect());
 
         genv.register_builtin_method_with_block(
             receiver_type,
@@ -90,8 +142,27 @@ fn load_rbs_from_cache(genv: &mut GlobalEnv) -> Result<()> {
             block_param_types,
         );
     }
+}
+
+/// Convert prism syntax errors and warnings to diagnostics
+fn syntax_diagnostics(
+    parse_result: &ruby_prism::ParseResult,
+    source: &str,
+    file_path: &Path,
+) -> Vec<Diagnostic> {
+    use crate::diagnostics::Location;
 
-    Ok(())
+    parser::syntax_problems(parse_result, source)
+        .into_iter()
+        .map(|problem| {
+            let location = Location::from_source(file_path, &problem.location);
+            if problem.is_error {
+                Diagnostic::syntax_error(location, &problem.message)
+            } else {
+                Diagnostic::syntax_warning(location, &problem.message)
+            }
+        })
+        .collect()
 }
 
 /// Collect type error diagnostics from GlobalEnv
@@ -105,31 +176,74 @@ fn collect_diagnostics(genv: &GlobalEnv, file_path: &Path) -> Vec<Diagnostic> {
     for type_error in &genv.type_errors {
         // Use actual location from TypeError if available
         let location = if let Some(source_loc) = &type_error.location {
-            Location {
-                file: PathBuf::from(file_path),
-                line: source_loc.line,
-                column: source_loc.column,
-                length: Some(source_loc.length),
-            }
+            Location::from_source(file_path, source_loc)
         } else {
             // Fallback to placeholder
             Location {
                 file: PathBuf::from(file_path),
                 line: 1,
                 column: 1,
-                length: None,
+                end_line: 1,
+                end_column: 1,
             }
         };
 
-        let diagnostic = Diagnostic::undefined_method(
-            location,
-            &type_error.receiver_type.show(),
-            &type_error.method_name,
-        );
+        let receiver_type = type_error.receiver_type.show();
+        let mut diagnostic =
+            Diagnostic::undefined_method(location, &receiver_type, &type_error.method_name);
+        if let Some(receiver_loc) = &type_error.receiver_location {
+            diagnostic = diagnostic.with_label(
+                Location::from_source(file_path, receiver_loc),
+                format!("receiver inferred as {} here", receiver_type),
+            );
+        }
+        diagnostic = diagnostic.with_suggestions(type_error.suggestions.clone());
+        // Where the receiver type came from
+        for origin in &type_error.origins {
+            let message = if origin.is_source {
+                format!("{} originates here", receiver_type)
+            } else {
+                format!("{} flows from here", receiver_type)
+            };
+            diagnostic =
+                diagnostic.with_label(Location::from_source(file_path, &origin.location), message);
+        }
 
         diagnostics.push(diagnostic);
     }
 
+    // `super` calls no ancestor answers
+    for super_error in &genv.missing_super_methods {
+        let Some(source_loc) = &super_error.location else {
+            continue;
+        };
+        let location = Location::from_source(file_path, source_loc);
+        diagnostics.push(Diagnostic::no_superclass_method(
+            location,
+            &super_error.receiver_type.show(),
+            &super_error.method_name,
+        ));
+    }
+
+    // Calls answered by method_missing on open classes
+    for dynamic_call in &genv.dynamic_calls {
+        let Some(source_loc) = &dynamic_call.location else {
+            continue;
+        };
+        let location = Location::from_source(file_path, source_loc);
+        diagnostics.push(Diagnostic::dynamic_method_call(
+            location,
+            &dynamic_call.receiver_type.show(),
+            &dynamic_call.method_name,
+        ));
+    }
+
+    // Statements after an unconditional return/raise
+    for source_loc in &genv.unreachable_code {
+        let location = Location::from_source(file_path, source_loc);
+        diagnostics.push(Diagnostic::unreachable_code(location));
+    }
+
     diagnostics
 }
 
@@ -141,7 +255,68 @@ mod tests {
     fn test_file_checker_creation() {
         // This test will fail if RBS cache doesn't exist
         // That's expected - cache should be generated from Ruby side first
-        let result = FileChecker::new();
+        let result = FileChecker::new(Config::default());
         assert!(result.is_ok() || result.is_err()); // Just check it doesn't panic
     }
+
+    #[test]
+    fn test_signature_paths_load_project_methods() {
+        use crate::cache::rbs_cache::{SerializableMethodInfo, CACHE_FORMAT_VERSION};
+        use std::time::SystemTime;
+
+        let project = tempfile::tempdir().unwrap();
+        let cache_dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            project.path().join(".methodray.yml"),
+            "signature_paths: [sig]\n",
+        )
+        .unwrap();
+        let config = Config::discover(project.path()).unwrap();
+
+        // The cache the Ruby loader writes for the project's signatures
+        let cache_for = |signature_paths: &[std::path::PathBuf], methods| RbsCache {
+            format_version: CACHE_FORMAT_VERSION,
+            version: "0.1.0".to_string(),
+            rbs_version: "3.7.0".to_string(),
+            methods,
+            timestamp: SystemTime::now(),
+            signature_paths: signature_paths.to_vec(),
+        };
+        let method = |receiver_class: &str, method_name: &str| SerializableMethodInfo {
+            receiver_class: receiver_class.to_string(),
+            method_name: method_name.to_string(),
+            return_type_str: "Integer".to_string(),
+            block_param_types: None,
+        };
+        cache_for(&[], vec![method("Integer", "abs")])
+            .save_to(&RbsCache::cache_file(cache_dir.path(), &[]))
+            .unwrap();
+        cache_for(
+            &config.signature_paths,
+            vec![method("Integer", "abs"), method("Greeter", "greet")],
+        )
+        .save_to(&RbsCache::cache_file(
+            cache_dir.path(),
+            &config.signature_paths,
+        ))
+        .unwrap();
+
+        let rbs_cache = RbsCache::read(&RbsCache::cache_file(
+            cache_dir.path(),
+            &config.signature_paths,
+        ))
+        .unwrap();
+        assert!(rbs_cache.has_signature_paths(&config.signature_paths));
+        let checker = FileChecker::with_rbs_cache(config, rbs_cache);
+        let diagnostics = checker
+            .check_source(
+                "class Greeter\n  def run\n    greet.upcase\n  end\nend\n",
+                Path::new("app.rb"),
+            )
+            .unwrap();
+
+        // `greet` is declared in sig/, so its Integer return type is known
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(messages, vec!["undefined method `upcase` for Integer"]);
+    }
 }
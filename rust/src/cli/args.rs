@@ -3,6 +3,9 @@
 use clap::{Parser, Subcommand};
 use std::path::PathBuf;
 
+use crate::diagnostics::{ColorChoice, OutputFormat};
+use crate::env::method_registry::OpenClassPolicy;
+
 /// MethodRay - Fast Ruby type checker
 #[derive(Parser)]
 #[command(name = "methodray")]
@@ -23,6 +26,35 @@ pub enum Commands {
         /// Show detailed output
         #[arg(short, long)]
         verbose: bool,
+
+        /// Report undefined methods on a class with method_missing as `info` or `ignore` them
+        /// (`*` sets the default, e.g. `--method-missing Proxy=info`)
+        #[arg(long = "method-missing", value_name = "CLASS=POLICY", value_parser = parse_open_class_policy)]
+        method_missing: Vec<(String, OpenClassPolicy)>,
+
+        /// Output format: text, json, sarif, junit or github (default: config or text)
+        #[arg(long, value_name = "FORMAT")]
+        format: Option<OutputFormat>,
+
+        /// Configuration file (default: .methodray.yml or methodray.toml found upward)
+        #[arg(long, value_name = "PATH")]
+        config: Option<PathBuf>,
+
+        /// Baseline file of known diagnostics (default: config or .methodray-baseline.json)
+        #[arg(long, value_name = "PATH")]
+        baseline: Option<PathBuf>,
+
+        /// Record the current diagnostics in the baseline instead of reporting them
+        #[arg(long)]
+        update_baseline: bool,
+
+        /// Color the text output: auto, always or never
+        #[arg(long, value_name = "WHEN", default_value = "auto")]
+        color: ColorChoice,
+
+        /// Source lines shown around each diagnostic in the text output
+        #[arg(long, value_name = "N", default_value_t = 0)]
+        context: usize,
     },
 
     /// Watch a Ruby file and re-check on changes
@@ -30,6 +62,20 @@ pub enum Commands {
         /// Ruby file to watch
         #[arg(value_name = "FILE")]
         file: PathBuf,
+
+        /// Configuration file (default: .methodray.yml or methodray.toml found upward)
+        #[arg(long, value_name = "PATH")]
+        config: Option<PathBuf>,
+    },
+
+    /// List the diagnostic rules and their codes
+    Rules,
+
+    /// Explain a diagnostic rule with an example
+    Explain {
+        /// Rule code or name (e.g., E001 or undefined-method)
+        #[arg(value_name = "CODE")]
+        code: String,
     },
 
     /// Show version information
@@ -38,3 +84,30 @@ pub enum Commands {
     /// Clear RBS cache
     ClearCache,
 }
+
+/// Parse `CLASS=POLICY` for `--method-missing`
+fn parse_open_class_policy(value: &str) -> Result<(String, OpenClassPolicy), String> {
+    let (class_name, policy) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected CLASS=POLICY, got `{}`", value))?;
+    Ok((class_name.to_string(), policy.parse()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_open_class_policy() {
+        assert_eq!(
+            parse_open_class_policy("Proxy=info"),
+            Ok(("Proxy".to_string(), OpenClassPolicy::Info))
+        );
+        assert_eq!(
+            parse_open_class_policy("*=ignore"),
+            Ok(("*".to_string(), OpenClassPolicy::Ignore))
+        );
+        assert!(parse_open_class_policy("Proxy").is_err());
+        assert!(parse_open_class_policy("Proxy=warn").is_err());
+    }
+}
@@ -1,45 +1,219 @@
 //! CLI command implementations
 
 use anyhow::Result;
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
 
 use crate::cache::RbsCache;
 use crate::checker::FileChecker;
-use crate::diagnostics;
+use crate::config::Config;
+use crate::diagnostics::{
+    self, rules, Baseline, ColorChoice, Diagnostic, FormatOptions, OutputFormat,
+};
+use crate::env::method_registry::OpenClassPolicy;
+
+/// Options of `methodray check`
+#[derive(Debug, Clone, Default)]
+pub struct CheckOptions {
+    /// Report files without diagnostics (text format)
+    pub verbose: bool,
+    /// Output format
+    pub format: OutputFormat,
+    /// How undefined methods on classes with `method_missing` are reported
+    pub open_class_policies: Vec<(String, OpenClassPolicy)>,
+    /// Project configuration
+    pub config: Config,
+    /// Baseline file of known diagnostics (default: from the configuration)
+    pub baseline: Option<PathBuf>,
+    /// Record the diagnostics in the baseline instead of reporting them
+    pub update_baseline: bool,
+    /// Color and context lines of the text format
+    pub text: FormatOptions,
+}
+
+impl CheckOptions {
+    /// Create a FileChecker with the configuration and policies
+    fn file_checker(&self) -> Result<FileChecker> {
+        let mut checker = FileChecker::new(self.config.clone())?;
+        for (class_name, policy) in &self.open_class_policies {
+            checker = checker.with_open_class_policy(class_name, *policy);
+        }
+        Ok(checker)
+    }
+
+    /// Baseline file: the given one or the configured one
+    fn baseline_path(&self) -> PathBuf {
+        self.baseline
+            .clone()
+            .unwrap_or_else(|| self.config.baseline_path())
+    }
+}
+
+/// File path of a diagnostic in the baseline and in reports (relative to
+/// the project root)
+fn project_file(config: &Config, file_path: &Path) -> String {
+    config
+        .relative_path(file_path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// Diagnostics with their files relative to the project root
+///
+/// Files are checked by absolute path, but SARIF uploads and GitHub
+/// annotations must name files in the repository.
+fn project_diagnostics(config: &Config, diagnostics: &[Diagnostic]) -> Vec<Diagnostic> {
+    diagnostics
+        .iter()
+        .cloned()
+        .map(|mut diagnostic| {
+            diagnostic.location.file = project_file(config, &diagnostic.location.file).into();
+            for label in &mut diagnostic.labels {
+                label.location.file = project_file(config, &label.location.file).into();
+            }
+            diagnostic
+        })
+        .collect()
+}
 
 /// Check a single Ruby file for type errors
 /// Returns Ok(true) if no errors, Ok(false) if errors found
-pub fn check_single_file(file_path: &PathBuf, verbose: bool) -> Result<bool> {
-    let checker = FileChecker::new()?;
-    let diagnostics = checker.check_file(file_path)?;
+pub fn check_single_file(file_path: &PathBuf, options: &CheckOptions) -> Result<bool> {
+    check_files(std::slice::from_ref(file_path), options)
+}
 
-    if diagnostics.is_empty() {
-        if verbose {
-            println!("{}: No errors found", file_path.display());
-        }
-        Ok(true)
+/// Check the Ruby files of the project selected by the configuration
+/// Returns Ok(true) if no errors, Ok(false) if errors found
+pub fn check_project(options: &CheckOptions) -> Result<bool> {
+    let files = options.config.files();
+    if files.is_empty() {
+        // stderr, so machine-readable formats still print an (empty) report
+        eprintln!(
+            "No Ruby files to check in {}",
+            options.config.root.display()
+        );
+    }
+    check_files(&files, options)
+}
+
+/// Check Ruby files and print their diagnostics
+///
+/// Diagnostics recorded in the baseline are not reported.
+fn check_files(files: &[PathBuf], options: &CheckOptions) -> Result<bool> {
+    if options.update_baseline {
+        return update_baseline(files, options);
+    }
+
+    let checker = options.file_checker()?;
+    let baseline_path = options.baseline_path();
+    let mut baseline = if baseline_path.exists() {
+        Some(Baseline::load(&baseline_path)?.matcher())
     } else {
-        let output = diagnostics::format_diagnostics_with_file(&diagnostics, file_path);
-        println!("{}", output);
+        None
+    };
+
+    let mut all_diagnostics = Vec::new();
+    let mut failed = false;
+    for file_path in files {
+        // A file that cannot be read or parsed does not stop the others
+        let diagnostics = match checker.check_file(file_path) {
+            Ok(diagnostics) => diagnostics,
+            Err(e) => {
+                eprintln!("Error: {:#}", e);
+                failed = true;
+                continue;
+            }
+        };
+        let diagnostics = match &mut baseline {
+            Some(baseline) => {
+                let source = fs::read_to_string(file_path).unwrap_or_default();
+                let file = project_file(&options.config, file_path);
+                baseline.filter(&file, &source, diagnostics)
+            }
+            None => diagnostics,
+        };
+
+        if options.format == OutputFormat::Text {
+            if diagnostics.is_empty() {
+                if options.verbose {
+                    println!("{}: No errors found", file_path.display());
+                }
+            } else {
+                let output = diagnostics::format_diagnostics_with_file(
+                    &diagnostics,
+                    file_path,
+                    &options.text,
+                );
+                println!("{}", output);
+            }
+        }
+        all_diagnostics.extend(diagnostics);
+    }
 
-        let has_errors = diagnostics
-            .iter()
-            .any(|d| d.level == diagnostics::DiagnosticLevel::Error);
+    if let Some(baseline) = &baseline {
+        if baseline.matched > 0 && options.format == OutputFormat::Text {
+            eprintln!(
+                "{} known diagnostic(s) hidden by baseline {}",
+                baseline.matched,
+                baseline_path.display()
+            );
+        }
+    }
 
-        Ok(!has_errors)
+    // Machine-readable formats are printed even without diagnostics
+    let report_diagnostics = project_diagnostics(&options.config, &all_diagnostics);
+    let report_files: Vec<PathBuf> = files
+        .iter()
+        .map(|file_path| project_file(&options.config, file_path).into())
+        .collect();
+    let report_files: Vec<&Path> = report_files.iter().map(PathBuf::as_path).collect();
+    if let Some(report) =
+        diagnostics::format_report(options.format, &report_diagnostics, &report_files)
+    {
+        println!("{}", report);
     }
+
+    let has_errors = all_diagnostics
+        .iter()
+        .any(|d| d.level == diagnostics::DiagnosticLevel::Error);
+    Ok(!has_errors && !failed)
 }
 
-/// Check all Ruby files in the project
-pub fn check_project(_verbose: bool) -> Result<()> {
-    println!("Project-wide checking not yet implemented");
-    println!("Use: methodray check <file> to check a single file");
-    Ok(())
+/// Record the diagnostics of the files in the baseline
+///
+/// Entries of other files are kept, so checking a single file updates
+/// only its part of the baseline.
+fn update_baseline(files: &[PathBuf], options: &CheckOptions) -> Result<bool> {
+    let checker = options.file_checker()?;
+    let baseline_path = options.baseline_path();
+    let mut baseline = if baseline_path.exists() {
+        Baseline::load(&baseline_path)?
+    } else {
+        Baseline::default()
+    };
+
+    for file_path in files {
+        let diagnostics = checker.check_file(file_path)?;
+        let source = fs::read_to_string(file_path)?;
+        baseline.record(
+            &project_file(&options.config, file_path),
+            &source,
+            &diagnostics,
+        );
+    }
+
+    baseline.save(&baseline_path)?;
+    println!(
+        "Baseline written to {} ({} diagnostic(s))",
+        baseline_path.display(),
+        baseline.diagnostic_count()
+    );
+    Ok(true)
 }
 
 /// Watch a file for changes and re-check on modifications
-pub fn watch_file(file_path: &PathBuf) -> Result<()> {
-    use notify::{Config, RecommendedWatcher, RecursiveMode, Watcher};
+pub fn watch_file(file_path: &PathBuf, config: Config) -> Result<()> {
+    use notify::{RecommendedWatcher, RecursiveMode, Watcher};
     use std::sync::mpsc::channel;
     use std::time::Duration;
 
@@ -53,9 +227,19 @@ pub fn watch_file(file_path: &PathBuf) -> Result<()> {
     );
     println!();
 
+    let watch_options = CheckOptions {
+        verbose: true,
+        config,
+        text: FormatOptions {
+            color: ColorChoice::Auto.enabled(),
+            context: 0,
+        },
+        ..CheckOptions::default()
+    };
+
     // Initial check
     println!("Initial check:");
-    let mut had_errors = match check_single_file(file_path, true) {
+    let mut had_errors = match check_single_file(file_path, &watch_options) {
         Ok(success) => !success,
         Err(e) => {
             eprintln!("Error during initial check: {}", e);
@@ -73,7 +257,7 @@ pub fn watch_file(file_path: &PathBuf) -> Result<()> {
                 let _ = tx.send(event);
             }
         },
-        Config::default().with_poll_interval(Duration::from_millis(500)),
+        notify::Config::default().with_poll_interval(Duration::from_millis(500)),
     )?;
 
     watcher.watch(file_path.as_ref(), RecursiveMode::NonRecursive)?;
@@ -87,7 +271,7 @@ pub fn watch_file(file_path: &PathBuf) -> Result<()> {
 
                     std::thread::sleep(Duration::from_millis(100));
 
-                    match check_single_file(file_path, true) {
+                    match check_single_file(file_path, &watch_options) {
                         Ok(success) => {
                             if success && had_errors {
                                 println!("✓ All errors fixed!");
@@ -114,21 +298,49 @@ pub fn watch_file(file_path: &PathBuf) -> Result<()> {
     Ok(())
 }
 
-/// Clear the RBS cache
+/// Print the rule catalog
+pub fn print_rules() {
+    println!("{}", rules::format_rules());
+}
+
+/// Print the explanation of a rule
+pub fn explain_rule(code: &str) -> Result<()> {
+    let rule = rules::find_rule(code).ok_or_else(|| {
+        anyhow::anyhow!(
+            "Unknown rule: {} (run `methodray rules` for the list)",
+            code
+        )
+    })?;
+    println!("{}", rules::format_explanation(rule));
+    Ok(())
+}
+
+/// Clear the RBS caches (the default one and those with signature paths)
 pub fn clear_cache() -> Result<()> {
-    match RbsCache::cache_path() {
-        Ok(path) => {
-            if path.exists() {
-                std::fs::remove_file(&path)?;
-                println!("Cache cleared: {}", path.display());
-            } else {
-                println!("No cache file found");
-            }
-        }
+    let cache_dir = match RbsCache::cache_dir() {
+        Ok(cache_dir) => cache_dir,
         Err(e) => {
             eprintln!("Failed to get cache path: {}", e);
+            return Ok(());
+        }
+    };
+
+    let mut cleared = false;
+    for entry in std::fs::read_dir(&cache_dir)? {
+        let path = entry?.path();
+        let is_cache = path.file_name().is_some_and(|name| {
+            let name = name.to_string_lossy();
+            name.starts_with("rbs_cache") && name.ends_with(".bin")
+        });
+        if is_cache {
+            std::fs::remove_file(&path)?;
+            println!("Cache cleared: {}", path.display());
+            cleared = true;
         }
     }
+    if !cleared {
+        println!("No cache file found");
+    }
 
     Ok(())
 }
@@ -137,3 +349,45 @@ pub fn clear_cache() -> Result<()> {
 pub fn print_version() {
     println!("MethodRay {}", env!("CARGO_PKG_VERSION"));
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::Location;
+
+    #[test]
+    fn test_project_diagnostics_are_relative_to_root() {
+        let config = Config {
+            root: PathBuf::from("/work/app"),
+            ..Config::default()
+        };
+        let mut diagnostic = Diagnostic::test_at("E001", 3, 1)
+            .with_label(
+                Location::test_at(2, 1, 1),
+                "String originates here".to_string(),
+            )
+            .with_label(
+                Location::test_at(1, 1, 1),
+                "String flows from here".to_string(),
+            );
+        diagnostic.location.file = PathBuf::from("/work/app/models/user.rb");
+        diagnostic.labels[0].location.file = PathBuf::from("/work/app/models/user.rb");
+        diagnostic.labels[1].location.file = PathBuf::from("/outside/lib.rb");
+
+        let diagnostics = project_diagnostics(&config, &[diagnostic]);
+
+        assert_eq!(
+            diagnostics[0].location.file,
+            PathBuf::from("models/user.rb")
+        );
+        assert_eq!(
+            diagnostics[0].labels[0].location.file,
+            PathBuf::from("models/user.rb")
+        );
+        // Files outside the project keep their path
+        assert_eq!(
+            diagnostics[0].labels[1].location.file,
+            PathBuf::from("/outside/lib.rb")
+        );
+    }
+}
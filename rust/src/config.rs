@@ -0,0 +1,398 @@
+//! Project configuration - `.methodray.yml` / `methodray.toml`
+//!
+//! The configuration file is discovered from a directory upward and shared
+//! by the CLI, the LSP server and the Ruby gem.
+//!
+//! ```yaml
+//! paths: [app, lib]
+//! exclude: [vendor/**, db/schema.rb]
+//! rules:
+//!   E002: error
+//!   unreachable-code: off
+//! signature_paths: [sig]
+//! format: github
+//! method_missing:
+//!   Proxy: info
+//! baseline: .methodray-baseline.json
+//! ```
+
+use crate::diagnostics::baseline::BASELINE_FILE_NAME;
+use crate::diagnostics::rules::find_rule;
+use crate::diagnostics::{Diagnostic, DiagnosticLevel, OutputFormat};
+use crate::env::method_registry::OpenClassPolicy;
+use anyhow::{Context, Result};
+use glob::{MatchOptions, Pattern};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Configuration file names, in lookup order within a directory
+pub const CONFIG_FILE_NAMES: &[&str] = &[".methodray.yml", ".methodray.yaml", "methodray.toml"];
+
+/// Severity of a rule set in the configuration
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleSeverity {
+    Error,
+    Warning,
+    Info,
+    /// The rule is not reported
+    Off,
+}
+
+impl RuleSeverity {
+    /// Diagnostic level of the rule, `None` when turned off
+    pub fn level(self) -> Option<DiagnosticLevel> {
+        match self {
+            RuleSeverity::Error => Some(DiagnosticLevel::Error),
+            RuleSeverity::Warning => Some(DiagnosticLevel::Warning),
+            RuleSeverity::Info => Some(DiagnosticLevel::Info),
+            RuleSeverity::Off => None,
+        }
+    }
+}
+
+/// Project configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Files or directories to check, as globs relative to the project root
+    pub paths: Vec<String>,
+    /// Files or directories to skip, as globs relative to the project root
+    pub exclude: Vec<String>,
+    /// Severity by rule code (or name)
+    pub rules: BTreeMap<String, RuleSeverity>,
+    /// Extra RBS signature directories, loaded when the RBS cache is built
+    pub signature_paths: Vec<PathBuf>,
+    /// Default output format of `methodray check`
+    pub format: Option<OutputFormat>,
+    /// Reporting policies of open classes (`method_missing`), by class name
+    pub method_missing: BTreeMap<String, OpenClassPolicy>,
+    /// Baseline file of known diagnostics (default: `.methodray-baseline.json`)
+    pub baseline: Option<PathBuf>,
+    /// Directory of the configuration file (or the start directory without one)
+    #[serde(skip)]
+    pub root: PathBuf,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            paths: vec!["**/*.rb".to_string()],
+            exclude: vec!["vendor/**".to_string(), "node_modules/**".to_string()],
+            rules: BTreeMap::new(),
+            signature_paths: Vec::new(),
+            format: None,
+            method_missing: BTreeMap::new(),
+            baseline: None,
+            root: PathBuf::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Find the configuration file in `start_dir` or its ancestors
+    pub fn find(start_dir: &Path) -> Option<PathBuf> {
+        start_dir.ancestors().find_map(|dir| {
+            CONFIG_FILE_NAMES
+                .iter()
+                .map(|name| dir.join(name))
+                .find(|path| path.is_file())
+        })
+    }
+
+    /// Load the configuration of `start_dir`
+    ///
+    /// Falls back to the default configuration rooted at `start_dir` when
+    /// no configuration file is found.
+    pub fn discover(start_dir: &Path) -> Result<Self> {
+        let start_dir = std::path::absolute(start_dir)
+            .with_context(|| format!("Invalid directory: {}", start_dir.display()))?;
+        match Self::find(&start_dir) {
+            Some(path) => Self::load(&path),
+            None => Ok(Self {
+                root: start_dir,
+                ..Self::default()
+            }),
+        }
+    }
+
+    /// Load the given configuration file, or discover one from the working directory
+    pub fn load_or_discover(path: Option<&Path>) -> Result<Self> {
+        match path {
+            Some(path) => Self::load(path),
+            None => Self::discover(&std::env::current_dir()?),
+        }
+    }
+
+    /// Load a configuration file (`.toml` as TOML, anything else as YAML)
+    pub fn load(path: &Path) -> Result<Self> {
+        let path = std::path::absolute(path)
+            .with_context(|| format!("Invalid config path: {}", path.display()))?;
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read config {}", path.display()))?;
+
+        let mut config = Self::parse(&content, &path)
+            .with_context(|| format!("Invalid config {}", path.display()))?;
+        config.root = path
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| PathBuf::from("/"));
+        config.signature_paths = config
+            .signature_paths
+            .iter()
+            .map(|sig_path| config.root.join(sig_path))
+            .collect();
+        config.baseline = config.baseline.map(|baseline| config.root.join(baseline));
+        Ok(config)
+    }
+
+    /// Parse and validate configuration content
+    fn parse(content: &str, path: &Path) -> Result<Self> {
+        let mut config: Self = if path.extension().is_some_and(|ext| ext == "toml") {
+            toml::from_str(content)?
+        } else if content.trim().is_empty() {
+            Self::default()
+        } else {
+            serde_yaml::from_str(content)?
+        };
+
+        // Rule names are accepted and stored by code
+        let mut rules = BTreeMap::new();
+        for (key, severity) in &config.rules {
+            let rule = find_rule(key).ok_or_else(|| {
+                anyhow::anyhow!(
+                    "unknown rule `{}` (run `methodray rules` for the list)",
+                    key
+                )
+            })?;
+            rules.insert(rule.code.to_string(), *severity);
+        }
+        config.rules = rules;
+
+        for glob in config.paths.iter().chain(&config.exclude) {
+            Pattern::new(glob).with_context(|| format!("invalid glob `{}`", glob))?;
+        }
+
+        Ok(config)
+    }
+
+    /// Baseline file: the configured one or `.methodray-baseline.json` in the root
+    pub fn baseline_path(&self) -> PathBuf {
+        self.baseline
+            .clone()
+            .unwrap_or_else(|| self.root.join(BASELINE_FILE_NAME))
+    }
+
+    /// Path relative to the project root, when the path is inside it
+    pub fn relative_path(&self, path: &Path) -> PathBuf {
+        let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+        absolute
+            .strip_prefix(&self.root)
+            .map(Path::to_path_buf)
+            .unwrap_or(absolute)
+    }
+
+    /// Whether a path matches one of the globs
+    ///
+    /// A glob without wildcards matches the file itself and everything
+    /// below it, so `app` and `db/schema.rb` work as written.
+    fn matches_any(globs: &[String], relative: &Path) -> bool {
+        let options = MatchOptions {
+            require_literal_separator: true,
+            ..MatchOptions::default()
+        };
+        globs.iter().any(|glob| {
+            let glob = glob.trim_end_matches('/');
+            let Ok(pattern) = Pattern::new(glob) else {
+                return false;
+            };
+            if pattern.matches_path_with(relative, options) {
+                return true;
+            }
+            let is_literal = !glob.contains(['*', '?', '[']);
+            is_literal && relative.starts_with(glob)
+        })
+    }
+
+    /// Whether a file is skipped by `exclude`
+    #[allow(dead_code)]
+    pub fn is_excluded(&self, path: &Path) -> bool {
+        Self::matches_any(&self.exclude, &self.relative_path(path))
+    }
+
+    /// Whether a file is checked: matched by `paths` and not excluded
+    pub fn is_included(&self, path: &Path) -> bool {
+        let relative = self.relative_path(path);
+        Self::matches_any(&self.paths, &relative) && !Self::matches_any(&self.exclude, &relative)
+    }
+
+    /// Ruby files of the project to check, sorted by path
+    ///
+    /// Hidden directories (`.git`, `.bundle`, ...) are skipped.
+    pub fn files(&self) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = WalkDir::new(&self.root)
+            .into_iter()
+            .filter_entry(|entry| {
+                entry.depth() == 0 || !entry.file_name().to_string_lossy().starts_with('.')
+            })
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_file())
+            .map(|entry| entry.into_path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "rb"))
+            .filter(|path| self.is_included(path))
+            .collect();
+        files.sort();
+        files
+    }
+
+    /// Apply the configured rule severities, dropping rules turned off
+    pub fn apply_rule_severities(&self, diagnostics: Vec<Diagnostic>) -> Vec<Diagnostic> {
+        diagnostics
+            .into_iter()
+            .filter_map(|mut diag| {
+                let severity = diag.code.as_ref().and_then(|code| self.rules.get(code));
+                if let Some(severity) = severity {
+                    diag.level = severity.level()?;
+                }
+                Some(diag)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_parse_yaml() {
+        let content = "\
+paths: [app]
+exclude: [db/schema.rb]
+rules:
+  unreachable-code: off
+  E001: warning
+format: github
+method_missing:
+  Proxy: info
+";
+        let config = Config::parse(content, Path::new(".methodray.yml")).unwrap();
+        assert_eq!(config.paths, vec!["app".to_string()]);
+        assert_eq!(config.rules.get("E004"), Some(&RuleSeverity::Off));
+        assert_eq!(config.rules.get("E001"), Some(&RuleSeverity::Warning));
+        assert_eq!(config.format, Some(OutputFormat::Github));
+        assert_eq!(
+            config.method_missing.get("Proxy"),
+            Some(&OpenClassPolicy::Info)
+        );
+    }
+
+    #[test]
+    fn test_parse_toml() {
+        let content = "\
+exclude = [\"vendor/**\", \"db/schema.rb\"]
+signature_paths = [\"sig\"]
+
+[rules]
+E002 = \"error\"
+";
+        let config = Config::parse(content, Path::new("methodray.toml")).unwrap();
+        assert_eq!(config.exclude.len(), 2);
+        assert_eq!(config.signature_paths, vec![PathBuf::from("sig")]);
+        assert_eq!(config.rules.get("E002"), Some(&RuleSeverity::Error));
+        // Unset keys keep their defaults
+        assert_eq!(config.paths, vec!["**/*.rb".to_string()]);
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_config() {
+        let yml = Path::new(".methodray.yml");
+        assert!(Config::parse("rules:\n  E999: off\n", yml).is_err());
+        assert!(Config::parse("rules:\n  E001: fatal\n", yml).is_err());
+        assert!(Config::parse("unknown_key: 1\n", yml).is_err());
+        assert!(Config::parse("", yml).is_ok());
+    }
+
+    #[test]
+    fn test_discover_from_nested_directory() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join(".methodray.yml"), "signature_paths: [sig]\n").unwrap();
+        fs::create_dir_all(root.join("app/models")).unwrap();
+
+        let config = Config::discover(&root.join("app/models")).unwrap();
+        assert_eq!(config.root, std::path::absolute(root).unwrap());
+        assert_eq!(config.signature_paths, vec![config.root.join("sig")]);
+        assert_eq!(
+            config.baseline_path(),
+            config.root.join(".methodray-baseline.json")
+        );
+    }
+
+    #[test]
+    fn test_discover_without_config_uses_defaults() {
+        let temp_dir = tempdir().unwrap();
+        let config = Config::discover(temp_dir.path()).unwrap();
+        assert_eq!(config.paths, Config::default().paths);
+        assert_eq!(config.root, std::path::absolute(temp_dir.path()).unwrap());
+    }
+
+    #[test]
+    fn test_files_respect_paths_and_exclude() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        for file in [
+            "app/user.rb",
+            "app/admin/role.rb",
+            "db/schema.rb",
+            "db/seeds.rb",
+            "vendor/gem/lib.rb",
+            "app/notes.txt",
+        ] {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+        fs::write(
+            root.join(".methodray.yml"),
+            "paths: [app, db]\nexclude: [vendor/**, db/schema.rb]\n",
+        )
+        .unwrap();
+
+        let config = Config::discover(root).unwrap();
+        let files: Vec<PathBuf> = config
+            .files()
+            .iter()
+            .map(|file| file.strip_prefix(&config.root).unwrap().to_path_buf())
+            .collect();
+        assert_eq!(
+            files,
+            vec![
+                PathBuf::from("app/admin/role.rb"),
+                PathBuf::from("app/user.rb"),
+                PathBuf::from("db/seeds.rb"),
+            ]
+        );
+        assert!(config.is_excluded(&root.join("db/schema.rb")));
+    }
+
+    #[test]
+    fn test_apply_rule_severities() {
+        let mut config = Config::default();
+        config
+            .rules
+            .insert("E001".to_string(), RuleSeverity::Warning);
+        config.rules.insert("E004".to_string(), RuleSeverity::Off);
+
+        let diagnostics = config.apply_rule_severities(vec![
+            Diagnostic::test_at("E001", 1, 1),
+            Diagnostic::test_at("E004", 1, 1),
+        ]);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].level, DiagnosticLevel::Warning);
+    }
+}
@@ -0,0 +1,276 @@
+//! Baseline - Known diagnostics of a legacy codebase
+//!
+//! `methodray check --update-baseline` records the current diagnostics;
+//! later runs report only diagnostics missing from the baseline. Entries
+//! are keyed by file, code, method name and a fingerprint of the source
+//! line, so they survive code moving up or down in the file.
+
+use super::diagnostic::Diagnostic;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Default baseline file name, in the project root
+pub const BASELINE_FILE_NAME: &str = ".methodray-baseline.json";
+
+const BASELINE_VERSION: u32 = 1;
+
+/// Known diagnostics of one kind on one line
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BaselineEntry {
+    /// File path relative to the project root
+    pub file: String,
+    pub code: String,
+    /// Method named in the message (empty when none)
+    pub method: String,
+    /// Hash of the whitespace-normalized source line
+    pub fingerprint: String,
+    /// Number of identical diagnostics
+    pub count: usize,
+}
+
+impl BaselineEntry {
+    fn key(&self) -> BaselineKey {
+        (
+            self.file.clone(),
+            self.code.clone(),
+            self.method.clone(),
+            self.fingerprint.clone(),
+        )
+    }
+}
+
+type BaselineKey = (String, String, String, String);
+
+/// Baseline file content
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Baseline {
+    pub version: u32,
+    pub entries: Vec<BaselineEntry>,
+}
+
+impl Baseline {
+    /// Load a baseline file
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read baseline {}", path.display()))?;
+        let baseline: Self = serde_json::from_str(&content)
+            .with_context(|| format!("Invalid baseline {}", path.display()))?;
+        if baseline.version != BASELINE_VERSION {
+            anyhow::bail!(
+                "Unsupported baseline version {} in {} (regenerate it with --update-baseline)",
+                baseline.version,
+                path.display()
+            );
+        }
+        Ok(baseline)
+    }
+
+    /// Write the baseline file (entries sorted, for stable diffs)
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut baseline = self.clone();
+        baseline.version = BASELINE_VERSION;
+        baseline.entries.sort_by_key(BaselineEntry::key);
+        let content = serde_json::to_string_pretty(&baseline)?;
+        fs::write(path, content + "\n")
+            .with_context(|| format!("Failed to write baseline {}", path.display()))
+    }
+
+    /// Replace the entries of a file with its current diagnostics
+    ///
+    /// `file` is the path relative to the project root and `source` the
+    /// file content the diagnostics point into.
+    pub fn record(&mut self, file: &str, source: &str, diagnostics: &[Diagnostic]) {
+        self.entries.retain(|entry| entry.file != file);
+
+        let mut counts: HashMap<BaselineKey, usize> = HashMap::new();
+        for diag in diagnostics {
+            *counts
+                .entry(diagnostic_key(file, source, diag))
+                .or_default() += 1;
+        }
+        self.entries.extend(counts.into_iter().map(
+            |((file, code, method, fingerprint), count)| BaselineEntry {
+                file,
+                code,
+                method,
+                fingerprint,
+                count,
+            },
+        ));
+    }
+
+    /// Number of recorded diagnostics
+    pub fn diagnostic_count(&self) -> usize {
+        self.entries.iter().map(|entry| entry.count).sum()
+    }
+
+    /// Remaining counts of each entry, for filtering a run
+    pub fn matcher(&self) -> BaselineMatcher {
+        let mut remaining: HashMap<BaselineKey, usize> = HashMap::new();
+        for entry in &self.entries {
+            *remaining.entry(entry.key()).or_default() += entry.count;
+        }
+        BaselineMatcher {
+            remaining,
+            matched: 0,
+        }
+    }
+}
+
+/// Removes known diagnostics, each baseline entry matching at most `count` times
+#[derive(Debug, Clone)]
+pub struct BaselineMatcher {
+    remaining: HashMap<BaselineKey, usize>,
+    /// Diagnostics removed so far
+    pub matched: usize,
+}
+
+impl BaselineMatcher {
+    /// Keep only the diagnostics missing from the baseline
+    pub fn filter(
+        &mut self,
+        file: &str,
+        source: &str,
+        diagnostics: Vec<Diagnostic>,
+    ) -> Vec<Diagnostic> {
+        diagnostics
+            .into_iter()
+            .filter(|diag| {
+                let key = diagnostic_key(file, source, diag);
+                match self.remaining.get_mut(&key) {
+                    Some(count) if *count > 0 => {
+                        *count -= 1;
+                        self.matched += 1;
+                        false
+                    }
+                    _ => true,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Baseline key of a diagnostic
+fn diagnostic_key(file: &str, source: &str, diag: &Diagnostic) -> BaselineKey {
+    let line = source
+        .lines()
+        .nth(diag.location.line.saturating_sub(1))
+        .unwrap_or("");
+    (
+        file.to_string(),
+        diag.code.clone().unwrap_or_default(),
+        method_name(&diag.message).unwrap_or_default().to_string(),
+        line_fingerprint(line),
+    )
+}
+
+/// First name quoted in backticks (e.g., "undefined method `abs` for String")
+fn method_name(message: &str) -> Option<&str> {
+    let start = message.find('`')? + 1;
+    let end = message[start..].find('`')? + start;
+    Some(&message[start..end])
+}
+
+/// FNV-1a hash of the line with whitespace collapsed
+///
+/// Indentation and spacing changes keep the fingerprint; a stable hash is
+/// used so baselines match across builds and platforms.
+fn line_fingerprint(line: &str) -> String {
+    let normalized = line.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in normalized.bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}", hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_line_fingerprint_ignores_whitespace() {
+        assert_eq!(
+            line_fingerprint("  name.abs"),
+            line_fingerprint("name.abs  ")
+        );
+        assert_ne!(line_fingerprint("name.abs"), line_fingerprint("title.abs"));
+    }
+
+    #[test]
+    fn test_known_diagnostics_survive_moved_lines() {
+        let before = "name = 'a'\nname.abs\n";
+        let mut baseline = Baseline::default();
+        baseline.record("app/user.rb", before, &[Diagnostic::test_at("E001", 2, 3)]);
+
+        // Two lines inserted above, and a new diagnostic below
+        let after = "# comment\n\nname = 'a'\nname.abs\nname.foo\n";
+        let mut matcher = baseline.matcher();
+        let kept = matcher.filter(
+            "app/user.rb",
+            after,
+            vec![
+                Diagnostic::test_at("E001", 4, 3),
+                Diagnostic::test_at("E004", 5, 1),
+            ],
+        );
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].location.line, 5);
+        assert_eq!(matcher.matched, 1);
+    }
+
+    #[test]
+    fn test_entries_match_at_most_count_times() {
+        let source = "name.abs\nname.abs\n";
+        let mut baseline = Baseline::default();
+        baseline.record("app/user.rb", source, &[Diagnostic::test_at("E001", 1, 3)]);
+        assert_eq!(baseline.diagnostic_count(), 1);
+
+        let kept = baseline.matcher().filter(
+            "app/user.rb",
+            source,
+            vec![
+                Diagnostic::test_at("E001", 1, 3),
+                Diagnostic::test_at("E001", 2, 3),
+            ],
+        );
+        assert_eq!(kept.len(), 1);
+    }
+
+    #[test]
+    fn test_record_replaces_entries_of_the_file_only() {
+        let mut baseline = Baseline::default();
+        baseline.record("app/a.rb", "x.abs\n", &[Diagnostic::test_at("E001", 1, 3)]);
+        baseline.record("app/b.rb", "x.abs\n", &[Diagnostic::test_at("E001", 1, 3)]);
+        baseline.record("app/a.rb", "x.abs\n", &[]);
+
+        assert_eq!(baseline.entries.len(), 1);
+        assert_eq!(baseline.entries[0].file, "app/b.rb");
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join(BASELINE_FILE_NAME);
+
+        let mut baseline = Baseline::default();
+        baseline.record(
+            "app/user.rb",
+            "x.abs\n",
+            &[Diagnostic::test_at("E001", 1, 3)],
+        );
+        baseline.save(&path).unwrap();
+
+        let loaded = Baseline::load(&path).unwrap();
+        assert_eq!(loaded.version, BASELINE_VERSION);
+        assert_eq!(loaded.entries, baseline.entries);
+
+        fs::write(&path, "{\"version\": 9, \"entries\": []}").unwrap();
+        assert!(Baseline::load(&path).is_err());
+    }
+}
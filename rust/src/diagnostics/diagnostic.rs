@@ -1,4 +1,6 @@
-use std::path::PathBuf;
+use super::rules::{self, Rule};
+use crate::source_map::SourceLocation;
+use std::path::{Path, PathBuf};
 
 /// Diagnostic severity level (LSP compatible)
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -6,6 +8,7 @@ use std::path::PathBuf;
 pub enum DiagnosticLevel {
     Error,
     Warning,
+    Info,
 }
 
 impl DiagnosticLevel {
@@ -13,17 +16,48 @@ impl DiagnosticLevel {
         match self {
             DiagnosticLevel::Error => "error",
             DiagnosticLevel::Warning => "warning",
+            DiagnosticLevel::Info => "info",
         }
     }
 }
 
-/// Source code location
+/// Source code range in a file
+///
+/// Lines and character columns are 1-indexed; the end is exclusive. An
+/// empty range marks a position whose extent is unknown.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Location {
     pub file: PathBuf,
     pub line: usize,
     pub column: usize,
-    pub length: Option<usize>, // Character length of the error span
+    pub end_line: usize,
+    pub end_column: usize,
+}
+
+impl Location {
+    /// Location of an analyzer range in a file
+    pub fn from_source(file: &Path, location: &SourceLocation) -> Self {
+        Self {
+            file: file.to_path_buf(),
+            line: location.line,
+            column: location.column,
+            end_line: location.end_line,
+            end_column: location.end_column,
+        }
+    }
+
+    /// Whether the range covers no character
+    pub fn is_empty(&self) -> bool {
+        (self.end_line, self.end_column) <= (self.line, self.column)
+    }
+}
+
+/// Secondary location explaining a diagnostic
+/// (e.g., "receiver inferred as Integer here")
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Label {
+    pub location: Location,
+    pub message: String,
 }
 
 /// Type checking diagnostic
@@ -33,7 +67,10 @@ pub struct Diagnostic {
     pub location: Location,
     pub level: DiagnosticLevel,
     pub message: String,
-    pub code: Option<String>, // e.g., "E001"
+    pub code: Option<String>, // e.g., "E001", see rules.rs
+    pub labels: Vec<Label>,
+    /// Replacements of the text at `location` (e.g., "did you mean `upcase`?")
+    pub suggestions: Vec<String>,
 }
 
 #[allow(dead_code)]
@@ -45,6 +82,8 @@ impl Diagnostic {
             level: DiagnosticLevel::Error,
             message,
             code: None,
+            labels: Vec::new(),
+            suggestions: Vec::new(),
         }
     }
 
@@ -55,12 +94,65 @@ impl Diagnostic {
             level: DiagnosticLevel::Warning,
             message,
             code: None,
+            labels: Vec::new(),
+            suggestions: Vec::new(),
+        }
+    }
+
+    /// Create an informational diagnostic
+    pub fn info(location: Location, message: String) -> Self {
+        Self {
+            location,
+            level: DiagnosticLevel::Info,
+            message,
+            code: None,
+            labels: Vec::new(),
+            suggestions: Vec::new(),
+        }
+    }
+
+    /// Create a diagnostic of a rule, with its code and default level
+    pub fn from_rule(rule: &Rule, location: Location, message: String) -> Self {
+        Self {
+            location,
+            level: rule.level,
+            message,
+            code: Some(rule.code.to_string()),
+            labels: Vec::new(),
+            suggestions: Vec::new(),
+        }
+    }
+
+    /// Attach a secondary label
+    pub fn with_label(mut self, location: Location, message: String) -> Self {
+        self.labels.push(Label { location, message });
+        self
+    }
+
+    /// Attach replacements of the text at the diagnostic's location
+    pub fn with_suggestions(mut self, suggestions: Vec<String>) -> Self {
+        self.suggestions = suggestions;
+        self
+    }
+
+    /// "did you mean `a` or `b`?" for the suggestions, if any
+    pub fn suggestion_message(&self) -> Option<String> {
+        let quoted: Vec<String> = self
+            .suggestions
+            .iter()
+            .map(|suggestion| format!("`{}`", suggestion))
+            .collect();
+        match quoted.as_slice() {
+            [] => None,
+            [only] => Some(format!("did you mean {}?", only)),
+            [rest @ .., last] => Some(format!("did you mean {} or {}?", rest.join(", "), last)),
         }
     }
 
     /// Create undefined method error
     pub fn undefined_method(location: Location, receiver_type: &str, method_name: &str) -> Self {
-        Self::error(
+        Self::from_rule(
+            &rules::UNDEFINED_METHOD,
             location,
             format!("undefined method `{}` for {}", method_name, receiver_type),
         )
@@ -79,7 +171,92 @@ impl Diagnostic {
             valid_types.join(", "),
             invalid_types.join(", ")
         );
-        Self::warning(location, message)
+        Self::from_rule(&rules::UNION_PARTIAL_METHOD, location, message)
+    }
+
+    /// Create error for `super` without a superclass method
+    pub fn no_superclass_method(
+        location: Location,
+        receiver_type: &str,
+        method_name: &str,
+    ) -> Self {
+        Self::from_rule(
+            &rules::NO_SUPERCLASS_METHOD,
+            location,
+            format!(
+                "super: no superclass method `{}` for {}",
+                method_name, receiver_type
+            ),
+        )
+    }
+
+    /// Create info for a call answered by `method_missing`
+    pub fn dynamic_method_call(location: Location, receiver_type: &str, method_name: &str) -> Self {
+        Self::from_rule(
+            &rules::METHOD_MISSING_CALL,
+            location,
+            format!(
+                "method `{}` is not defined for {}; handled by method_missing",
+                method_name, receiver_type
+            ),
+        )
+    }
+
+    /// Create unreachable code warning
+    pub fn unreachable_code(location: Location) -> Self {
+        Self::from_rule(
+            &rules::UNREACHABLE_CODE,
+            location,
+            "unreachable code".to_string(),
+        )
+    }
+
+    /// Create syntax error reported by the parser
+    pub fn syntax_error(location: Location, message: &str) -> Self {
+        Self::from_rule(&rules::SYNTAX_ERROR, location, message.to_string())
+    }
+
+    /// Create syntax warning reported by the parser
+    pub fn syntax_warning(location: Location, message: &str) -> Self {
+        Self::from_rule(&rules::SYNTAX_WARNING, location, message.to_string())
+    }
+
+    /// Create warning for a suppression comment that silenced nothing
+    pub fn unused_suppression(location: Location, code: Option<&str>) -> Self {
+        let message = match code {
+            Some(code) => format!("unused suppression of {}", code),
+            None => "unused suppression".to_string(),
+        };
+        Self::from_rule(&rules::UNUSED_SUPPRESSION, location, message)
+    }
+}
+
+#[cfg(test)]
+impl Location {
+    /// `width` characters on a line of `test.rb`
+    pub fn test_at(line: usize, column: usize, width: usize) -> Self {
+        Self {
+            file: PathBuf::from("test.rb"),
+            line,
+            column,
+            end_line: line,
+            end_column: column + width,
+        }
+    }
+}
+
+#[cfg(test)]
+impl Diagnostic {
+    /// Diagnostic of a rule at a line and column of `test.rb`
+    ///
+    /// E001 is an undefined `abs` for String; E004 is unreachable code.
+    pub fn test_at(code: &str, line: usize, column: usize) -> Self {
+        let location = Location::test_at(line, column, 3);
+        match code {
+            "E001" => Self::undefined_method(location, "String", "abs"),
+            "E004" => Self::unreachable_code(location),
+            _ => panic!("no test diagnostic for {}", code),
+        }
     }
 }
 
@@ -93,11 +270,13 @@ mod tests {
             file: PathBuf::from("test.rb"),
             line: 10,
             column: 5,
-            length: None,
+            end_line: 10,
+            end_column: 5,
         };
 
         let diag = Diagnostic::undefined_method(loc.clone(), "Integer", "upcase");
         assert_eq!(diag.level, DiagnosticLevel::Error);
+        assert_eq!(diag.code.as_deref(), Some("E001"));
         assert_eq!(diag.message, "undefined method `upcase` for Integer");
     }
 
@@ -107,7 +286,8 @@ mod tests {
             file: PathBuf::from("test.rb"),
             line: 15,
             column: 3,
-            length: None,
+            end_line: 15,
+            end_column: 3,
         };
 
         let diag = Diagnostic::union_partial_error(
@@ -122,4 +302,98 @@ mod tests {
             .message
             .contains("method `upcase` is defined for String but not for Integer"));
     }
+
+    #[test]
+    fn test_unreachable_code() {
+        let loc = Location {
+            file: PathBuf::from("test.rb"),
+            line: 3,
+            column: 5,
+            end_line: 3,
+            end_column: 13,
+        };
+
+        let diag = Diagnostic::unreachable_code(loc);
+        assert_eq!(diag.level, DiagnosticLevel::Warning);
+        assert_eq!(diag.code.as_deref(), Some("E004"));
+        assert_eq!(diag.message, "unreachable code");
+    }
+
+    #[test]
+    fn test_no_superclass_method() {
+        let loc = Location {
+            file: PathBuf::from("test.rb"),
+            line: 4,
+            column: 5,
+            end_line: 4,
+            end_column: 10,
+        };
+
+        let diag = Diagnostic::no_superclass_method(loc, "User", "greet");
+        assert_eq!(diag.level, DiagnosticLevel::Error);
+        assert_eq!(diag.message, "super: no superclass method `greet` for User");
+    }
+
+    #[test]
+    fn test_dynamic_method_call() {
+        let loc = Location {
+            file: PathBuf::from("test.rb"),
+            line: 2,
+            column: 1,
+            end_line: 2,
+            end_column: 5,
+        };
+
+        let diag = Diagnostic::dynamic_method_call(loc, "Proxy", "name");
+        assert_eq!(diag.level, DiagnosticLevel::Info);
+        assert_eq!(
+            diag.message,
+            "method `name` is not defined for Proxy; handled by method_missing"
+        );
+    }
+
+    #[test]
+    fn test_syntax_error_and_warning() {
+        let loc = Location {
+            file: PathBuf::from("test.rb"),
+            line: 2,
+            column: 5,
+            end_line: 2,
+            end_column: 6,
+        };
+
+        let error = Diagnostic::syntax_error(loc.clone(), "unexpected ')'");
+        assert_eq!(error.level, DiagnosticLevel::Error);
+        assert_eq!(error.code.as_deref(), Some("E007"));
+
+        let warning = Diagnostic::syntax_warning(loc, "assigned but unused variable - x");
+        assert_eq!(warning.level, DiagnosticLevel::Warning);
+        assert_eq!(warning.code.as_deref(), Some("E008"));
+    }
+
+    #[test]
+    fn test_suggestion_message() {
+        let loc = Location {
+            file: PathBuf::from("test.rb"),
+            line: 1,
+            column: 3,
+            end_line: 1,
+            end_column: 8,
+        };
+
+        let diag = Diagnostic::undefined_method(loc, "String", "upcse");
+        assert_eq!(diag.suggestion_message(), None);
+
+        let diag = diag.with_suggestions(vec!["upcase".to_string()]);
+        assert_eq!(
+            diag.suggestion_message().as_deref(),
+            Some("did you mean `upcase`?")
+        );
+
+        let diag = diag.with_suggestions(vec!["upcase".into(), "upcase!".into(), "update".into()]);
+        assert_eq!(
+            diag.suggestion_message().as_deref(),
+            Some("did you mean `upcase`, `upcase!` or `update`?")
+        );
+    }
 }
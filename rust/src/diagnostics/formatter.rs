@@ -1,69 +1,152 @@
-use super::diagnostic::Diagnostic;
+use super::diagnostic::{Diagnostic, DiagnosticLevel, Location};
 use std::fs;
+use std::io::IsTerminal;
 use std::path::Path;
 
+/// When to color terminal output (`--color`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorChoice {
+    /// Color when stdout is a terminal and `NO_COLOR` is unset
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl std::str::FromStr for ColorChoice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            "always" => Ok(Self::Always),
+            "never" => Ok(Self::Never),
+            _ => Err(format!(
+                "unknown color choice `{}` (expected auto, always or never)",
+                s
+            )),
+        }
+    }
+}
+
+impl ColorChoice {
+    /// Whether to emit ANSI colors on stdout
+    pub fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Auto => {
+                std::env::var_os("NO_COLOR").is_none() && std::io::stdout().is_terminal()
+            }
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        }
+    }
+}
+
+/// Options of the text output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FormatOptions {
+    /// Emit ANSI colors
+    pub color: bool,
+    /// Source lines shown before and after each marked line
+    pub context: usize,
+}
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const BLUE: &str = "\x1b[1;34m";
+
+/// ANSI style of a severity
+fn level_style(level: DiagnosticLevel) -> &'static str {
+    match level {
+        DiagnosticLevel::Error => "\x1b[1;31m",
+        DiagnosticLevel::Warning => "\x1b[1;33m",
+        DiagnosticLevel::Info => "\x1b[1;36m",
+    }
+}
+
+/// Wrap text in an ANSI style when colors are enabled
+fn paint(text: &str, style: &str, color: bool) -> String {
+    if color && !text.is_empty() {
+        format!("{}{}{}", style, text, RESET)
+    } else {
+        text.to_string()
+    }
+}
+
+/// Format the first line of a diagnostic: location, level, message and code
+fn format_header(diag: &Diagnostic, color: bool) -> String {
+    let location = format!(
+        "{}:{}:{}:",
+        diag.location.file.display(),
+        diag.location.line,
+        diag.location.column
+    );
+    let mut header = format!(
+        "{} {} {}",
+        paint(&location, BOLD, color),
+        paint(
+            &format!("{}:", diag.level.as_str()),
+            level_style(diag.level),
+            color
+        ),
+        paint(&diag.message, BOLD, color)
+    );
+    if let Some(code) = &diag.code {
+        header.push_str(&format!(" [{}]", code));
+    }
+    header
+}
+
 /// Format diagnostics in LSP-compatible format
 ///
 /// Example output:
 /// ```text
-/// app/models/user.rb:10:5: error: undefined method `upcase` for Integer
+/// app/models/user.rb:10:5: error: undefined method `upcase` for Integer [E001]
 /// ```
 #[allow(dead_code)]
 pub fn format_diagnostics(diagnostics: &[Diagnostic]) -> String {
     diagnostics
         .iter()
-        .map(|diag| {
-            format!(
-                "{}:{}:{}: {}: {}",
-                diag.location.file.display(),
-                diag.location.line,
-                diag.location.column,
-                diag.level.as_str(),
-                diag.message
-            )
-        })
+        .map(|diag| format_header(diag, false))
         .collect::<Vec<_>>()
         .join("\n")
 }
 
-/// Format diagnostics with code snippet
+/// Format diagnostics with code snippet (no color, no context lines)
+#[allow(dead_code)]
+pub fn format_diagnostics_with_source(diagnostics: &[Diagnostic], source_code: &str) -> String {
+    format_diagnostics_with_options(diagnostics, source_code, &FormatOptions::default())
+}
+
+/// Format diagnostics with code snippet, underlines and labels
+///
+/// The primary span is underlined with `^`, secondary labels with `-`;
+/// suggestions follow as a `help` line.
 ///
 /// Example output:
 /// ```text
-/// app/models/user.rb:10:5: error: undefined method `upcase` for Integer
-///    x.upcase
-///      ^^^^^^
+/// app/models/user.rb:10:7: error: undefined method `upcase` for Integer [E001]
+///    |
+/// 10 |     x.upcase
+///    |     - ^^^^^^
+///    |     |
+///    |     receiver inferred as Integer here
 /// ```
-pub fn format_diagnostics_with_source(diagnostics: &[Diagnostic], source_code: &str) -> String {
+pub fn format_diagnostics_with_options(
+    diagnostics: &[Diagnostic],
+    source_code: &str,
+    options: &FormatOptions,
+) -> String {
     let lines: Vec<&str> = source_code.lines().collect();
 
     diagnostics
         .iter()
         .map(|diag| {
-            let mut output = format!(
-                "{}:{}:{}: {}: {}",
-                diag.location.file.display(),
-                diag.location.line,
-                diag.location.column,
-                diag.level.as_str(),
-                diag.message
-            );
-
-            // Add code snippet
-            if diag.location.line > 0 && diag.location.line <= lines.len() {
-                let line_index = diag.location.line - 1; // Convert to 0-indexed
-                let source_line = lines[line_index];
-
+            let mut output = format_header(diag, options.color);
+            let snippet = format_snippet(diag, &lines, options);
+            if !snippet.is_empty() {
                 output.push('\n');
-                output.push_str("   ");
-                output.push_str(source_line);
-                output.push('\n');
-
-                // Add caret indicator
-                let column = diag.location.column.saturating_sub(1); // Convert to 0-indexed
-                output.push_str("   ");
-                output.push_str(&" ".repeat(column));
-                output.push('^');
+                output.push_str(&snippet);
             }
 
             output
@@ -73,39 +156,234 @@ pub fn format_diagnostics_with_source(diagnostics: &[Diagnostic], source_code: &
 }
 
 /// Read source file and format diagnostics with code snippet
-pub fn format_diagnostics_with_file(diagnostics: &[Diagnostic], file_path: &Path) -> String {
+pub fn format_diagnostics_with_file(
+    diagnostics: &[Diagnostic],
+    file_path: &Path,
+    options: &FormatOptions,
+) -> String {
     match fs::read_to_string(file_path) {
-        Ok(source) => format_diagnostics_with_source(diagnostics, &source),
+        Ok(source) => format_diagnostics_with_options(diagnostics, &source, options),
         Err(_) => format_diagnostics(diagnostics), // Fallback to simple format
     }
 }
 
+/// An underlined span on a source line
+struct Marker<'a> {
+    /// 0-indexed character column
+    start: usize,
+    width: usize,
+    /// `None` for the primary span
+    label: Option<&'a str>,
+}
+
+/// One output row below a source line; cells keep their ANSI style
+struct Row<'a> {
+    /// Source line, to copy tabs so markers stay aligned
+    line: &'a str,
+    cells: Vec<(char, &'static str)>,
+}
+
+impl<'a> Row<'a> {
+    fn new(line: &'a str) -> Self {
+        Self {
+            line,
+            cells: Vec::new(),
+        }
+    }
+
+    fn put(&mut self, column: usize, text: &str, style: &'static str) {
+        while self.cells.len() < column {
+            let filler = match self.line.chars().nth(self.cells.len()) {
+                Some('\t') => '\t',
+                _ => ' ',
+            };
+            self.cells.push((filler, ""));
+        }
+        for (offset, ch) in text.chars().enumerate() {
+            let cell = (ch, style);
+            match self.cells.get_mut(column + offset) {
+                Some(existing) => *existing = cell,
+                None => self.cells.push(cell),
+            }
+        }
+    }
+
+    fn render(&self, color: bool) -> String {
+        let mut output = String::new();
+        let mut run = String::new();
+        let mut run_style = "";
+        for &(ch, style) in &self.cells {
+            if style != run_style {
+                output.push_str(&paint(&run, run_style, color));
+                run.clear();
+                run_style = style;
+            }
+            run.push(ch);
+        }
+        output.push_str(&paint(&run, run_style, color));
+        output
+    }
+}
+
+/// Number of characters a span covers on its first line
+///
+/// Spans running past the end of the line are cut there; at least one
+/// character is marked.
+fn span_width(line: &str, location: &Location) -> usize {
+    let line_end = line.chars().count() + 1;
+    let end_column = if location.end_line > location.line {
+        line_end
+    } else {
+        location.end_column.min(line_end)
+    };
+    end_column.saturating_sub(location.column).max(1)
+}
+
+/// Source lines around the diagnostic with its underlines and labels
+fn format_snippet(diag: &Diagnostic, lines: &[&str], options: &FormatOptions) -> String {
+    let in_source = |location: &Location| location.line > 0 && location.line <= lines.len();
+    if !in_source(&diag.location) {
+        return String::new();
+    }
+
+    // Markers by line number; labels of other files are not shown
+    let mut marked: Vec<(usize, Marker)> = vec![(
+        diag.location.line,
+        Marker {
+            start: diag.location.column.saturating_sub(1),
+            width: span_width(lines[diag.location.line - 1], &diag.location),
+            label: None,
+        },
+    )];
+    for label in &diag.labels {
+        if label.location.file == diag.location.file && in_source(&label.location) {
+            marked.push((
+                label.location.line,
+                Marker {
+                    start: label.location.column.saturating_sub(1),
+                    width: span_width(lines[label.location.line - 1], &label.location),
+                    label: Some(&label.message),
+                },
+            ));
+        }
+    }
+
+    // Marked lines with their context, merged into one sorted list
+    let mut shown: Vec<usize> = marked
+        .iter()
+        .flat_map(|(line, _)| {
+            line.saturating_sub(options.context).max(1)..=(line + options.context).min(lines.len())
+        })
+        .collect();
+    shown.sort_unstable();
+    shown.dedup();
+
+    let gutter_width = shown.last().map_or(1, |line| line.to_string().len());
+    let color = options.color;
+    let gutter = |number: &str| paint(&format!("{:>w$} |", number, w = gutter_width), BLUE, color);
+    let primary_style = level_style(diag.level);
+
+    let mut output = vec![gutter("")];
+    let mut previous: Option<usize> = None;
+    for &number in &shown {
+        if previous.is_some_and(|previous| number > previous + 1) {
+            output.push(paint("...", BLUE, color));
+        }
+        previous = Some(number);
+
+        let line = lines[number - 1];
+        output.push(
+            format!("{} {}", gutter(&number.to_string()), line)
+                .trim_end()
+                .to_string(),
+        );
+
+        let mut markers: Vec<&Marker> = marked
+            .iter()
+            .filter(|(line, _)| *line == number)
+            .map(|(_, marker)| marker)
+            .collect();
+        if markers.is_empty() {
+            continue;
+        }
+        markers.sort_by_key(|marker| marker.start);
+
+        // Underlines, with the message of the rightmost label inline
+        let mut underline = Row::new(line);
+        for marker in &markers {
+            let (ch, style) = match marker.label {
+                Some(_) => ('-', BLUE),
+                None => ('^', primary_style),
+            };
+            let text = ch.to_string().repeat(marker.width);
+            underline.put(marker.start, &text, style);
+        }
+        let rightmost = markers
+            .iter()
+            .max_by_key(|marker| marker.start + marker.width)
+            .filter(|marker| marker.label.is_some());
+        if let Some(marker) = rightmost {
+            let end = underline.cells.len();
+            underline.put(end + 1, marker.label.unwrap_or_default(), BLUE);
+        }
+        let mut rows = vec![underline];
+
+        // Remaining labels hang below their span, rightmost first
+        let hanging: Vec<&Marker> = markers
+            .iter()
+            .filter(|marker| marker.label.is_some())
+            .filter(|marker| !rightmost.is_some_and(|inline| std::ptr::eq(*inline, **marker)))
+            .copied()
+            .collect();
+        if !hanging.is_empty() {
+            let mut bars = Row::new(line);
+            for marker in &hanging {
+                bars.put(marker.start, "|", BLUE);
+            }
+            rows.push(bars);
+            for index in (0..hanging.len()).rev() {
+                let mut row = Row::new(line);
+                for marker in &hanging[..index] {
+                    row.put(marker.start, "|", BLUE);
+                }
+                row.put(
+                    hanging[index].start,
+                    hanging[index].label.unwrap_or_default(),
+                    BLUE,
+                );
+                rows.push(row);
+            }
+        }
+
+        for row in rows {
+            output.push(format!("{} {}", gutter(""), row.render(color)));
+        }
+    }
+
+    if let Some(help) = diag.suggestion_message() {
+        output.push(format!(
+            "{:>w$} {} {}",
+            "",
+            paint("= help:", BOLD, color),
+            help,
+            w = gutter_width
+        ));
+    }
+
+    output.join("\n")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::diagnostics::diagnostic::{Diagnostic, Location};
-    use std::path::PathBuf;
 
     #[test]
     fn test_format_diagnostics() {
         let diagnostics = vec![
-            Diagnostic::undefined_method(
-                Location {
-                    file: PathBuf::from("test.rb"),
-                    line: 10,
-                    column: 5,
-                    length: None,
-                },
-                "Integer",
-                "upcase",
-            ),
+            Diagnostic::undefined_method(Location::test_at(10, 5, 0), "Integer", "upcase"),
             Diagnostic::union_partial_error(
-                Location {
-                    file: PathBuf::from("test.rb"),
-                    line: 15,
-                    column: 3,
-                    length: None,
-                },
+                Location::test_at(15, 3, 0),
                 vec!["String".to_string()],
                 vec!["Integer".to_string()],
                 "upcase",
@@ -115,5 +393,134 @@ mod tests {
         let output = format_diagnostics(&diagnostics);
         assert!(output.contains("test.rb:10:5: error:"));
         assert!(output.contains("test.rb:15:3: warning:"));
+        assert!(output.contains("for Integer [E001]"));
+    }
+
+    #[test]
+    fn test_underline_covers_span() {
+        let source = "x = 1\nx.upcase\n";
+        let diagnostics = vec![Diagnostic::undefined_method(
+            Location::test_at(2, 3, 6),
+            "Integer",
+            "upcase",
+        )];
+
+        let output = format_diagnostics_with_source(&diagnostics, source);
+        assert_eq!(
+            output,
+            "test.rb:2:3: error: undefined method `upcase` for Integer [E001]\n\
+             \x20 |\n\
+             2 | x.upcase\n\
+             \x20 |   ^^^^^^"
+        );
+    }
+
+    #[test]
+    fn test_secondary_label() {
+        let source = "x.upcase\n";
+        let diagnostic =
+            Diagnostic::undefined_method(Location::test_at(1, 3, 6), "Integer", "upcase")
+                .with_label(
+                    Location::test_at(1, 1, 1),
+                    "receiver inferred as Integer here".into(),
+                );
+
+        let output = format_diagnostics_with_source(&[diagnostic], source);
+        let snippet: Vec<&str> = output.lines().skip(1).collect();
+        assert_eq!(
+            snippet,
+            vec![
+                "  |",
+                "1 | x.upcase",
+                "  | - ^^^^^^",
+                "  | |",
+                "  | receiver inferred as Integer here",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_label_on_another_line_is_inline() {
+        let source = "x = 1\nx.upcase\n";
+        let diagnostic =
+            Diagnostic::undefined_method(Location::test_at(2, 3, 6), "Integer", "upcase")
+                .with_label(Location::test_at(1, 5, 1), "Integer assigned here".into());
+
+        let output = format_diagnostics_with_source(&[diagnostic], source);
+        assert!(output.contains("1 | x = 1\n  |     - Integer assigned here\n2 | x.upcase"));
+    }
+
+    #[test]
+    fn test_context_lines() {
+        let source = "a\nb\nc\nd\ne\nf\n";
+        let diagnostic = Diagnostic::unreachable_code(Location::test_at(5, 1, 1))
+            .with_label(Location::test_at(1, 1, 1), "here".into());
+        let options = FormatOptions {
+            color: false,
+            context: 1,
+        };
+
+        let output = format_diagnostics_with_options(&[diagnostic], source, &options);
+        let numbers: Vec<&str> = output
+            .lines()
+            .skip(2)
+            .filter(|line| !line.starts_with("  |"))
+            .collect();
+        assert_eq!(
+            numbers,
+            vec!["1 | a", "2 | b", "...", "4 | d", "5 | e", "6 | f"]
+        );
+    }
+
+    #[test]
+    fn test_span_width() {
+        assert_eq!(span_width("x.café()", &Location::test_at(1, 3, 4)), 4);
+        // Cut at the end of the line
+        assert_eq!(span_width("foo(", &Location::test_at(1, 1, 20)), 4);
+        let multiline = Location {
+            end_line: 3,
+            end_column: 2,
+            ..Location::test_at(1, 2, 0)
+        };
+        assert_eq!(span_width("foo(", &multiline), 3);
+        // Unknown extent
+        assert_eq!(span_width("foo", &Location::test_at(1, 1, 0)), 1);
+    }
+
+    #[test]
+    fn test_color() {
+        let diagnostics = vec![Diagnostic::undefined_method(
+            Location::test_at(1, 3, 6),
+            "Integer",
+            "upcase",
+        )];
+        let colored = FormatOptions {
+            color: true,
+            context: 0,
+        };
+
+        let output = format_diagnostics_with_options(&diagnostics, "x.upcase\n", &colored);
+        assert!(output.contains("\x1b[1;31merror:\x1b[0m"));
+        assert!(output.contains("\x1b[1;31m^^^^^^\x1b[0m"));
+        assert!(!format_diagnostics_with_source(&diagnostics, "x.upcase\n").contains('\x1b'));
+    }
+
+    #[test]
+    fn test_color_choice_from_str() {
+        assert_eq!("always".parse(), Ok(ColorChoice::Always));
+        assert_eq!("never".parse(), Ok(ColorChoice::Never));
+        assert!("sometimes".parse::<ColorChoice>().is_err());
+        assert!(ColorChoice::Always.enabled());
+        assert!(!ColorChoice::Never.enabled());
+    }
+
+    #[test]
+    fn test_suggestion_help_line() {
+        let diagnostic =
+            Diagnostic::undefined_method(Location::test_at(1, 3, 5), "String", "upcse")
+                .with_suggestions(vec!["upcase".to_string()]);
+
+        let output = format_diagnostics_with_source(&[diagnostic], "x.upcse\n");
+        assert!(output.ends_with("  |   ^^^^^\n  = help: did you mean `upcase`?"));
     }
 }
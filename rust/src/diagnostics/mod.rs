@@ -1,5 +1,12 @@
+pub mod baseline;
 pub mod diagnostic;
 pub mod formatter;
+pub mod report;
+pub mod rules;
+pub mod suppression;
 
+pub use baseline::Baseline;
 pub use diagnostic::{Diagnostic, DiagnosticLevel, Location};
-pub use formatter::format_diagnostics_with_file;
+pub use formatter::{format_diagnostics_with_file, ColorChoice, FormatOptions};
+pub use report::{format_report, OutputFormat};
+pub use suppression::Suppressions;
@@ -0,0 +1,457 @@
+//! Machine-readable diagnostic reports
+//!
+//! Renders `Diagnostic` values as JSON, SARIF 2.1.0 (code scanning),
+//! JUnit XML (CI test reports) or GitHub Actions workflow commands
+//! (pull request annotations).
+
+use super::diagnostic::{Diagnostic, DiagnosticLevel, Location};
+use super::rules::find_rule;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::Path;
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const TOOL_NAME: &str = "methodray";
+const TOOL_URI: &str = "https://github.com/dak2/method-ray";
+
+/// Output format of `methodray check`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// `file:line:col: level: message` with a source snippet
+    #[default]
+    Text,
+    /// JSON array of diagnostics
+    Json,
+    /// SARIF 2.1.0 log
+    Sarif,
+    /// JUnit XML test report
+    Junit,
+    /// GitHub Actions `::error` / `::warning` commands
+    Github,
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            "sarif" => Ok(Self::Sarif),
+            "junit" => Ok(Self::Junit),
+            "github" => Ok(Self::Github),
+            _ => Err(format!(
+                "unknown format `{}` (expected text, json, sarif, junit or github)",
+                s
+            )),
+        }
+    }
+}
+
+/// Render diagnostics in a machine-readable format
+///
+/// `files` are the checked files; JUnit reports a passing test case for
+/// files without diagnostics. Returns `None` for `OutputFormat::Text`,
+/// which is rendered by the formatter with source snippets.
+pub fn format_report(
+    format: OutputFormat,
+    diagnostics: &[Diagnostic],
+    files: &[&Path],
+) -> Option<String> {
+    match format {
+        OutputFormat::Text => None,
+        OutputFormat::Json => Some(format_json(diagnostics)),
+        OutputFormat::Sarif => Some(format_sarif(diagnostics)),
+        OutputFormat::Junit => Some(format_junit(diagnostics, files)),
+        OutputFormat::Github => Some(format_github(diagnostics)),
+    }
+}
+
+/// End column of a location (exclusive), when its extent is known
+fn end_column(location: &Location) -> Option<usize> {
+    (!location.is_empty()).then_some(location.end_column)
+}
+
+/// JSON array with the code, level, range and message of each diagnostic
+///
+/// ```json
+/// [{"file": "user.rb", "line": 3, "column": 5, "end_line": 3, "end_column": 11,
+///   "level": "error", "code": "E001", "message": "undefined method `upcase` for Integer",
+///   "related": [{"file": "user.rb", "line": 2, "column": 5, "end_line": 2, "end_column": 7,
+///                "message": "Integer originates here"}]}]
+/// ```
+pub fn format_json(diagnostics: &[Diagnostic]) -> String {
+    let entries: Vec<Value> = diagnostics
+        .iter()
+        .map(|diag| {
+            json!({
+                "file": diag.location.file.display().to_string(),
+                "line": diag.location.line,
+                "column": diag.location.column,
+                "end_line": diag.location.end_line,
+                "end_column": end_column(&diag.location),
+                "level": diag.level.as_str(),
+                "code": diag.code,
+                "message": diag.message,
+                "suggestions": diag.suggestions,
+                "related": diag.labels.iter().map(|label| json!({
+                    "file": label.location.file.display().to_string(),
+                    "line": label.location.line,
+                    "column": label.location.column,
+                    "end_line": label.location.end_line,
+                    "end_column": end_column(&label.location),
+                    "message": label.message,
+                })).collect::<Vec<_>>(),
+            })
+        })
+        .collect();
+    serde_json::to_string_pretty(&entries).unwrap_or_default()
+}
+
+/// SARIF level of a diagnostic
+fn sarif_level(level: DiagnosticLevel) -> &'static str {
+    match level {
+        DiagnosticLevel::Error => "error",
+        DiagnosticLevel::Warning => "warning",
+        DiagnosticLevel::Info => "note",
+    }
+}
+
+/// SARIF physical location of a diagnostic or label
+fn sarif_physical_location(location: &Location) -> Value {
+    let mut region = json!({
+        "startLine": location.line,
+        "startColumn": location.column,
+        "endLine": location.end_line,
+    });
+    if let Some(end_column) = end_column(location) {
+        region["endColumn"] = json!(end_column);
+    }
+    json!({
+        "artifactLocation": { "uri": artifact_uri(&location.file) },
+        "region": region,
+    })
+}
+
+/// SARIF 2.1.0 log with one run
+///
+/// Each distinct code becomes a rule of the tool driver, described from
+/// the rule catalog; diagnostics without a code are reported under the
+/// tool name.
+pub fn format_sarif(diagnostics: &[Diagnostic]) -> String {
+    let rule_id = |diag: &Diagnostic| diag.code.clone().unwrap_or_else(|| TOOL_NAME.to_string());
+
+    let mut rule_ids: Vec<String> = diagnostics.iter().map(rule_id).collect();
+    rule_ids.sort();
+    rule_ids.dedup();
+    let rules: Vec<Value> = rule_ids
+        .iter()
+        .map(|id| match find_rule(id) {
+            Some(rule) => json!({
+                "id": id,
+                "name": rule.name,
+                "shortDescription": { "text": rule.summary },
+                "fullDescription": { "text": rule.description },
+            }),
+            None => json!({ "id": id }),
+        })
+        .collect();
+
+    let results: Vec<Value> = diagnostics
+        .iter()
+        .map(|diag| {
+            let mut result = json!({
+                "ruleId": rule_id(diag),
+                "level": sarif_level(diag.level),
+                "message": { "text": diag.message },
+                "locations": [{ "physicalLocation": sarif_physical_location(&diag.location) }],
+            });
+            if !diag.labels.is_empty() {
+                let related: Vec<Value> = diag
+                    .labels
+                    .iter()
+                    .enumerate()
+                    .map(|(id, label)| {
+                        json!({
+                            "id": id,
+                            "physicalLocation": sarif_physical_location(&label.location),
+                            "message": { "text": label.message },
+                        })
+                    })
+                    .collect();
+                result["relatedLocations"] = json!(related);
+            }
+            result
+        })
+        .collect();
+
+    let log = json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": TOOL_NAME,
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": TOOL_URI,
+                    "rules": rules,
+                }
+            },
+            // Columns count characters, not UTF-16 code units (the SARIF default)
+            "columnKind": "unicodeCodePoints",
+            "results": results,
+        }],
+    });
+    serde_json::to_string_pretty(&log).unwrap_or_default()
+}
+
+/// Relative URI of a file for SARIF (forward slashes, as code scanning expects)
+fn artifact_uri(file: &Path) -> String {
+    file.display().to_string().replace('\\', "/")
+}
+
+/// JUnit XML report with one test case per diagnostic
+///
+/// Errors are failures; warnings and infos are reported in `system-out`
+/// so they show up without failing the build.
+pub fn format_junit(diagnostics: &[Diagnostic], files: &[&Path]) -> String {
+    let failures = diagnostics
+        .iter()
+        .filter(|diag| diag.level == DiagnosticLevel::Error)
+        .count();
+
+    let mut test_cases = Vec::new();
+    for diag in diagnostics {
+        let file = diag.location.file.display().to_string();
+        let name = format!("{}:{}:{}", file, diag.location.line, diag.location.column);
+        let detail = format!("{}: {}", name, diag.message);
+        let kind = diag.code.as_deref().unwrap_or(diag.level.as_str());
+        let body = if diag.level == DiagnosticLevel::Error {
+            format!(
+                "      <failure message=\"{}\" type=\"{}\">{}</failure>\n",
+                xml_escape(&diag.message),
+                xml_escape(kind),
+                xml_escape(&detail)
+            )
+        } else {
+            format!(
+                "      <system-out>{}: {}</system-out>\n",
+                diag.level.as_str(),
+                xml_escape(&detail)
+            )
+        };
+        test_cases.push(format!(
+            "    <testcase classname=\"{}\" name=\"{}\">\n{}    </testcase>\n",
+            xml_escape(&file),
+            xml_escape(&name),
+            body
+        ));
+    }
+
+    // Files without diagnostics pass
+    for file in files {
+        let has_diagnostics = diagnostics
+            .iter()
+            .any(|diag| diag.location.file.as_path() == *file);
+        if !has_diagnostics {
+            let file = xml_escape(&file.display().to_string());
+            test_cases.push(format!(
+                "    <testcase classname=\"{}\" name=\"{}\"/>\n",
+                file, file
+            ));
+        }
+    }
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <testsuites name=\"{name}\" tests=\"{tests}\" failures=\"{failures}\">\n\
+         \x20 <testsuite name=\"{name}\" tests=\"{tests}\" failures=\"{failures}\">\n\
+         {cases}\x20 </testsuite>\n\
+         </testsuites>",
+        name = TOOL_NAME,
+        tests = test_cases.len(),
+        failures = failures,
+        cases = test_cases.concat()
+    )
+}
+
+/// Escape text for XML attributes and content
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// GitHub Actions workflow commands, one per diagnostic
+///
+/// ```text
+/// ::error file=app/user.rb,line=3,col=5,endColumn=11,title=E001::undefined method `upcase` for Integer
+/// ```
+pub fn format_github(diagnostics: &[Diagnostic]) -> String {
+    diagnostics
+        .iter()
+        .map(|diag| {
+            let command = match diag.level {
+                DiagnosticLevel::Error => "error",
+                DiagnosticLevel::Warning => "warning",
+                DiagnosticLevel::Info => "notice",
+            };
+            let mut properties = vec![
+                format!(
+                    "file={}",
+                    github_escape_property(&diag.location.file.display().to_string())
+                ),
+                format!("line={}", diag.location.line),
+                format!("col={}", diag.location.column),
+            ];
+            // Columns of multi-line annotations are ignored by GitHub
+            if diag.location.end_line > diag.location.line {
+                properties.push(format!("endLine={}", diag.location.end_line));
+            } else if let Some(end_column) = end_column(&diag.location) {
+                properties.push(format!("endColumn={}", end_column));
+            }
+            let title = diag.code.as_deref().unwrap_or(TOOL_NAME);
+            properties.push(format!("title={}", github_escape_property(title)));
+
+            format!(
+                "::{} {}::{}",
+                command,
+                properties.join(","),
+                github_escape_data(&diag.message)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Escape the message of a workflow command
+fn github_escape_data(text: &str) -> String {
+    text.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escape a property value of a workflow command
+fn github_escape_property(text: &str) -> String {
+    github_escape_data(text)
+        .replace(':', "%3A")
+        .replace(',', "%2C")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::diagnostic::Location;
+    use std::path::PathBuf;
+
+    fn sample_diagnostics() -> Vec<Diagnostic> {
+        vec![
+            Diagnostic::undefined_method(
+                Location {
+                    file: PathBuf::from("app/user.rb"),
+                    line: 3,
+                    column: 5,
+                    end_line: 3,
+                    end_column: 11,
+                },
+                "Integer",
+                "upcase",
+            )
+            .with_label(
+                Location {
+                    file: PathBuf::from("app/user.rb"),
+                    line: 2,
+                    column: 5,
+                    end_line: 2,
+                    end_column: 7,
+                },
+                "Integer originates here".to_string(),
+            ),
+            Diagnostic::unreachable_code(Location {
+                file: PathBuf::from("app/user.rb"),
+                line: 7,
+                column: 3,
+                end_line: 7,
+                end_column: 3,
+            }),
+        ]
+    }
+
+    #[test]
+    fn test_parse_output_format() {
+        assert_eq!("sarif".parse(), Ok(OutputFormat::Sarif));
+        assert_eq!("github".parse(), Ok(OutputFormat::Github));
+        assert!("xml".parse::<OutputFormat>().is_err());
+    }
+
+    #[test]
+    fn test_format_json() {
+        let output = format_json(&sample_diagnostics());
+        let value: Value = serde_json::from_str(&output).unwrap();
+
+        assert_eq!(value[0]["file"], "app/user.rb");
+        assert_eq!(value[0]["line"], 3);
+        assert_eq!(value[0]["end_column"], 11);
+        assert_eq!(value[0]["level"], "error");
+        assert_eq!(value[0]["message"], "undefined method `upcase` for Integer");
+        assert_eq!(value[0]["suggestions"], json!([]));
+        assert_eq!(value[0]["related"][0]["line"], 2);
+        assert_eq!(value[0]["related"][0]["message"], "Integer originates here");
+        assert_eq!(value[1]["end_column"], Value::Null);
+    }
+
+    #[test]
+    fn test_format_sarif() {
+        let output = format_sarif(&sample_diagnostics());
+        let value: Value = serde_json::from_str(&output).unwrap();
+
+        assert_eq!(value["version"], "2.1.0");
+        let run = &value["runs"][0];
+        assert_eq!(run["tool"]["driver"]["name"], "methodray");
+        assert_eq!(run["columnKind"], "unicodeCodePoints");
+        assert_eq!(run["tool"]["driver"]["rules"][0]["id"], "E001");
+        assert_eq!(
+            run["tool"]["driver"]["rules"][0]["name"],
+            "undefined-method"
+        );
+        assert_eq!(run["results"][0]["ruleId"], "E001");
+        let result = &run["results"][0];
+        assert_eq!(result["level"], "error");
+        let location = &result["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "app/user.rb");
+        assert_eq!(location["region"]["startLine"], 3);
+        assert_eq!(location["region"]["endColumn"], 11);
+        let related = &result["relatedLocations"][0];
+        assert_eq!(related["physicalLocation"]["region"]["startLine"], 2);
+        assert_eq!(related["message"]["text"], "Integer originates here");
+        assert_eq!(run["results"][1]["level"], "warning");
+    }
+
+    #[test]
+    fn test_format_junit() {
+        let clean = Path::new("app/clean.rb");
+        let output = format_junit(&sample_diagnostics(), &[Path::new("app/user.rb"), clean]);
+
+        assert!(output.contains("tests=\"3\" failures=\"1\""));
+        assert!(output
+            .contains("<failure message=\"undefined method `upcase` for Integer\" type=\"E001\">"));
+        assert!(output.contains("<system-out>warning: app/user.rb:7:3: unreachable code"));
+        assert!(output.contains("<testcase classname=\"app/clean.rb\" name=\"app/clean.rb\"/>"));
+    }
+
+    #[test]
+    fn test_format_github() {
+        let output = format_github(&sample_diagnostics());
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert_eq!(
+            lines[0],
+            "::error file=app/user.rb,line=3,col=5,endColumn=11,title=E001::undefined method `upcase` for Integer"
+        );
+        assert!(lines[1].starts_with("::warning file=app/user.rb,line=7,col=3,title=E004::"));
+        assert_eq!(github_escape_property("a:b,c"), "a%3Ab%2Cc");
+    }
+}
@@ -0,0 +1,199 @@
+//! Rule catalog - stable codes of diagnostic kinds
+//!
+//! Every diagnostic carries the code of its rule. Codes never change meaning
+//! once released, so they can be used in suppression comments, configuration
+//! and CI reports.
+
+use super::diagnostic::DiagnosticLevel;
+
+/// A kind of diagnostic reported by MethodRay
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rule {
+    /// Stable code (e.g., "E001")
+    pub code: &'static str,
+    /// Readable name (e.g., "undefined-method")
+    pub name: &'static str,
+    /// Default level
+    pub level: DiagnosticLevel,
+    /// One-line summary
+    pub summary: &'static str,
+    /// What the rule checks and how to fix it
+    pub description: &'static str,
+    /// Ruby code triggering the rule
+    pub example: &'static str,
+}
+
+pub const UNDEFINED_METHOD: Rule = Rule {
+    code: "E001",
+    name: "undefined-method",
+    level: DiagnosticLevel::Error,
+    summary: "Method is not defined for the receiver's type",
+    description: "The method is not defined by the receiver's class, its RBS signature \
+                  or the analyzed source. Check the spelling of the method name and the \
+                  type of the receiver.",
+    example: "name = \"Alice\"\nname.abs  # undefined method `abs` for String",
+};
+
+pub const UNION_PARTIAL_METHOD: Rule = Rule {
+    code: "E002",
+    name: "union-partial-method",
+    level: DiagnosticLevel::Warning,
+    summary: "Method is defined for only some types of a union",
+    description: "The receiver may have several types and the method is missing on some \
+                  of them. Narrow the receiver (e.g., with `is_a?` or `nil?`) before the call.",
+    example: "value = ready? ? \"Alice\" : 1\nvalue.upcase  # not defined for Integer",
+};
+
+pub const NO_SUPERCLASS_METHOD: Rule = Rule {
+    code: "E003",
+    name: "no-superclass-method",
+    level: DiagnosticLevel::Error,
+    summary: "`super` has no method to call in the ancestors",
+    description: "No superclass or included module of the enclosing class defines the \
+                  method calling `super`. Remove the `super` call or fix the superclass.",
+    example:
+        "class User\n  def greet\n    super  # super: no superclass method `greet`\n  end\nend",
+};
+
+pub const UNREACHABLE_CODE: Rule = Rule {
+    code: "E004",
+    name: "unreachable-code",
+    level: DiagnosticLevel::Warning,
+    summary: "Code after `return`, `raise`, `next` or `break` never runs",
+    description: "Statements following an unconditional jump are never executed. Remove \
+                  them or make the jump conditional.",
+    example: "def label\n  return \"x\"\n  \"y\"  # unreachable code\nend",
+};
+
+pub const METHOD_MISSING_CALL: Rule = Rule {
+    code: "E005",
+    name: "method-missing-call",
+    level: DiagnosticLevel::Info,
+    summary: "Undefined method handled by `method_missing`",
+    description: "The receiver's class defines `method_missing` or `respond_to_missing?`, \
+                  so the call is answered dynamically. Only reported for classes given \
+                  the `info` policy (`--method-missing CLASS=info`).",
+    example: "class Proxy\n  def method_missing(name, *args) = name\nend\n\
+              Proxy.new.anything  # handled by method_missing",
+};
+
+pub const UNUSED_SUPPRESSION: Rule = Rule {
+    code: "E006",
+    name: "unused-suppression",
+    level: DiagnosticLevel::Warning,
+    summary: "Suppression comment silences no diagnostic",
+    description: "A `# methodray:disable` comment (or its `-next-line` / `-file` forms) \
+                  matches no diagnostic, usually because the underlying issue was fixed. \
+                  Remove the comment or the stale code from it.",
+    example: "name = \"Alice\"\nname.upcase  # methodray:disable E001  (unused suppression)",
+};
+
+pub const SYNTAX_ERROR: Rule = Rule {
+    code: "E007",
+    name: "syntax-error",
+    level: DiagnosticLevel::Error,
+    summary: "Ruby source does not parse",
+    description: "The parser found a syntax error. The rest of the file is still analyzed \
+                  from the recovered syntax tree, so other diagnostics may be caused by the \
+                  error; fix it first.",
+    example: "def greet(name\n  \"Hello, #{name}\"\nend  # unexpected end-of-input",
+};
+
+pub const SYNTAX_WARNING: Rule = Rule {
+    code: "E008",
+    name: "syntax-warning",
+    level: DiagnosticLevel::Warning,
+    summary: "Ruby would warn about this code",
+    description: "The parser reports code Ruby warns about, like unused local variables \
+                  or ambiguous arguments.",
+    example: "def label\n  unused = 1  # assigned but unused variable - unused\n  2\nend",
+};
+
+/// All rules, ordered by code
+pub const RULES: &[Rule] = &[
+    UNDEFINED_METHOD,
+    UNION_PARTIAL_METHOD,
+    NO_SUPERCLASS_METHOD,
+    UNREACHABLE_CODE,
+    METHOD_MISSING_CALL,
+    UNUSED_SUPPRESSION,
+    SYNTAX_ERROR,
+    SYNTAX_WARNING,
+];
+
+/// Find a rule by code (`E001`, case-insensitive) or name (`undefined-method`)
+pub fn find_rule(code_or_name: &str) -> Option<&'static Rule> {
+    RULES
+        .iter()
+        .find(|rule| rule.code.eq_ignore_ascii_case(code_or_name) || rule.name == code_or_name)
+}
+
+/// One line per rule: code, name, default level and summary
+pub fn format_rules() -> String {
+    RULES
+        .iter()
+        .map(|rule| {
+            format!(
+                "{}  {:<22} {:<8} {}",
+                rule.code,
+                rule.name,
+                rule.level.as_str(),
+                rule.summary
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Full explanation of a rule with its example
+pub fn format_explanation(rule: &Rule) -> String {
+    let example = rule
+        .example
+        .lines()
+        .map(|line| format!("    {}", line))
+        .collect::<Vec<_>>()
+        .join("\n");
+    format!(
+        "{} ({}, {})\n\n{}\n\n{}\n\nExample:\n\n{}",
+        rule.code,
+        rule.name,
+        rule.level.as_str(),
+        rule.summary,
+        rule.description,
+        example
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codes_are_unique_and_ordered() {
+        let codes: Vec<&str> = RULES.iter().map(|rule| rule.code).collect();
+        let mut sorted = codes.clone();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(codes, sorted);
+    }
+
+    #[test]
+    fn test_find_rule_by_code_or_name() {
+        assert_eq!(find_rule("E001"), Some(&UNDEFINED_METHOD));
+        assert_eq!(find_rule("e003"), Some(&NO_SUPERCLASS_METHOD));
+        assert_eq!(find_rule("unreachable-code"), Some(&UNREACHABLE_CODE));
+        assert_eq!(find_rule("E999"), None);
+    }
+
+    #[test]
+    fn test_format_explanation() {
+        let explanation = format_explanation(&UNDEFINED_METHOD);
+        assert!(explanation.starts_with("E001 (undefined-method, error)"));
+        assert!(explanation.contains("    name.abs"));
+    }
+
+    #[test]
+    fn test_format_rules_lists_every_rule() {
+        assert_eq!(format_rules().lines().count(), RULES.len());
+    }
+}
@@ -0,0 +1,324 @@
+//! Suppression comments - Silencing diagnostics from the source
+//!
+//! Supported comments (codes are optional; without codes every rule is
+//! suppressed):
+//!
+//! ```ruby
+//! name.abs # methodray:disable E001          (this line)
+//! # methodray:disable-next-line E001          (the following line)
+//! # methodray:disable-file E002               (the whole file)
+//! # methodray:disable E001, E004              (until `enable` or end of file)
+//! # methodray:enable E001, E004
+//! ```
+//!
+//! Text after `--` is a free-form reason. A suppression that silences
+//! nothing is reported as an unused suppression (E006).
+
+use super::diagnostic::{Diagnostic, Location};
+use super::rules::find_rule;
+use crate::source_map::SourceLocation;
+use std::path::Path;
+
+const PREFIX: &str = "methodray:";
+
+/// Lines a suppression applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scope {
+    /// A single line
+    Line(usize),
+    /// From the first line to the last line (inclusive)
+    Lines(usize, usize),
+    /// The whole file
+    File,
+}
+
+impl Scope {
+    fn contains(&self, line: usize) -> bool {
+        match *self {
+            Scope::Line(target) => line == target,
+            Scope::Lines(first, last) => first <= line && line <= last,
+            Scope::File => true,
+        }
+    }
+}
+
+/// A suppressed code (or every code) in a scope
+#[derive(Debug, Clone)]
+struct Suppression {
+    /// Location of the comment
+    location: SourceLocation,
+    scope: Scope,
+    /// `None` suppresses every rule
+    code: Option<String>,
+    used: bool,
+}
+
+impl Suppression {
+    fn matches(&self, diag: &Diagnostic) -> bool {
+        let code_matches = match &self.code {
+            Some(code) => diag.code.as_deref() == Some(code.as_str()),
+            None => true,
+        };
+        code_matches && self.scope.contains(diag.location.line)
+    }
+}
+
+/// Suppression comments of a file
+#[derive(Debug, Clone, Default)]
+pub struct Suppressions {
+    suppressions: Vec<Suppression>,
+}
+
+impl Suppressions {
+    /// Collect suppressions from the comments of a file
+    ///
+    /// `comments` are the text and location of each comment, as found by
+    /// the parser. Block `disable` comments still open at the end of the
+    /// file extend to its last line.
+    pub fn parse<'a>(
+        source: &str,
+        comments: impl IntoIterator<Item = (&'a str, SourceLocation)>,
+    ) -> Self {
+        let lines: Vec<&str> = source.lines().collect();
+        let last_line = lines.len().max(1);
+
+        let mut suppressions = Vec::new();
+        // Indices of block suppressions waiting for `enable`
+        let mut open_blocks: Vec<usize> = Vec::new();
+
+        for (text, location) in comments {
+            let Some((directive, codes)) = parse_directive(text) else {
+                continue;
+            };
+            let line = location.line;
+            let own_line = lines
+                .get(line.saturating_sub(1))
+                .map(|text| {
+                    text.chars()
+                        .take(location.column.saturating_sub(1))
+                        .all(char::is_whitespace)
+                })
+                .unwrap_or(true);
+
+            let scope = match directive {
+                "disable" if own_line => Scope::Lines(line + 1, last_line),
+                "disable" => Scope::Line(line),
+                "disable-next-line" => Scope::Line(line + 1),
+                "disable-file" => Scope::File,
+                "enable" => {
+                    open_blocks.retain(|&index| {
+                        let suppression: &mut Suppression = &mut suppressions[index];
+                        let closes = codes.is_empty() || codes.contains(&suppression.code);
+                        if closes {
+                            if let Scope::Lines(first, _) = suppression.scope {
+                                suppression.scope = Scope::Lines(first, line);
+                            }
+                        }
+                        !closes
+                    });
+                    continue;
+                }
+                _ => continue,
+            };
+
+            let codes = if codes.is_empty() { vec![None] } else { codes };
+            for code in codes {
+                if matches!(scope, Scope::Lines(..)) {
+                    open_blocks.push(suppressions.len());
+                }
+                suppressions.push(Suppression {
+                    location: location.clone(),
+                    scope,
+                    code,
+                    used: false,
+                });
+            }
+        }
+
+        Self { suppressions }
+    }
+
+    /// Remove suppressed diagnostics and report unused suppressions
+    ///
+    /// Unused suppression warnings cannot be suppressed themselves; remove
+    /// the stale comment instead.
+    pub fn apply(mut self, diagnostics: Vec<Diagnostic>, file_path: &Path) -> Vec<Diagnostic> {
+        let mut kept: Vec<Diagnostic> = diagnostics
+            .into_iter()
+            .filter(|diag| {
+                let mut suppressed = false;
+                for suppression in &mut self.suppressions {
+                    if suppression.matches(diag) {
+                        suppression.used = true;
+                        suppressed = true;
+                    }
+                }
+                !suppressed
+            })
+            .collect();
+
+        for suppression in self.suppressions.iter().filter(|s| !s.used) {
+            let location = Location::from_source(file_path, &suppression.location);
+            kept.push(Diagnostic::unused_suppression(
+                location,
+                suppression.code.as_deref(),
+            ));
+        }
+        kept
+    }
+}
+
+/// Split a `# methodray:<directive> <codes> -- <reason>` comment
+///
+/// Codes are separated by commas or spaces; rule names are accepted and
+/// turned into codes.
+fn parse_directive(text: &str) -> Option<(&str, Vec<Option<String>>)> {
+    let body = text.strip_prefix('#')?.trim_start();
+    let body = body.strip_prefix(PREFIX)?;
+    let body = match body.find("--") {
+        Some(reason) => &body[..reason],
+        None => body,
+    };
+
+    let mut words = body.split(|c: char| c == ',' || c.is_whitespace());
+    let directive = words.next()?;
+    let codes = words
+        .filter(|word| !word.is_empty())
+        .map(|word| match find_rule(word) {
+            Some(rule) => Some(rule.code.to_string()),
+            None => Some(word.to_ascii_uppercase()),
+        })
+        .collect();
+    Some((directive, codes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Find comments by their `#` on each line (good enough for the tests)
+    fn suppressions_of(source: &str) -> Suppressions {
+        let comments: Vec<(&str, SourceLocation)> = source
+            .lines()
+            .enumerate()
+            .filter_map(|(index, line)| {
+                let column = line.find('#')?;
+                let text = &line[column..];
+                let end_column = column + 1 + text.chars().count();
+                Some((
+                    text,
+                    SourceLocation::new(index + 1, column + 1, index + 1, end_column),
+                ))
+            })
+            .collect();
+        Suppressions::parse(source, comments)
+    }
+
+    fn apply(source: &str, diagnostics: Vec<Diagnostic>) -> Vec<Diagnostic> {
+        suppressions_of(source).apply(diagnostics, Path::new("test.rb"))
+    }
+
+    #[test]
+    fn test_parse_directive() {
+        assert_eq!(
+            parse_directive("# methodray:disable E001, unreachable-code -- known issue"),
+            Some((
+                "disable",
+                vec![Some("E001".to_string()), Some("E004".to_string())]
+            ))
+        );
+        assert_eq!(
+            parse_directive("#methodray:disable-file"),
+            Some(("disable-file", vec![]))
+        );
+        assert_eq!(parse_directive("# rubocop:disable Style/Foo"), None);
+    }
+
+    #[test]
+    fn test_trailing_disable_suppresses_its_line() {
+        let source = "x.abs # methodray:disable E001\nx.abs\n";
+        let kept = apply(
+            source,
+            vec![
+                Diagnostic::test_at("E001", 1, 1),
+                Diagnostic::test_at("E001", 2, 1),
+            ],
+        );
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].location.line, 2);
+    }
+
+    #[test]
+    fn test_disable_next_line() {
+        let source = "# methodray:disable-next-line\nx.abs\nx.abs\n";
+        let kept = apply(
+            source,
+            vec![
+                Diagnostic::test_at("E001", 2, 1),
+                Diagnostic::test_at("E001", 3, 1),
+            ],
+        );
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].location.line, 3);
+    }
+
+    #[test]
+    fn test_disable_file_only_for_its_code() {
+        let source = "# methodray:disable-file E004\nx.abs\nreturn\n";
+        let kept = apply(
+            source,
+            vec![
+                Diagnostic::test_at("E001", 2, 1),
+                Diagnostic::test_at("E004", 3, 1),
+            ],
+        );
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].code.as_deref(), Some("E001"));
+    }
+
+    #[test]
+    fn test_block_disable_until_enable() {
+        let source = "\
+# methodray:disable E001
+x.abs
+x.abs
+# methodray:enable E001
+x.abs
+";
+        let kept = apply(
+            source,
+            vec![
+                Diagnostic::test_at("E001", 2, 1),
+                Diagnostic::test_at("E001", 3, 1),
+                Diagnostic::test_at("E001", 5, 1),
+            ],
+        );
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].location.line, 5);
+    }
+
+    #[test]
+    fn test_block_disable_without_enable_runs_to_end_of_file() {
+        let source = "x.abs\n# methodray:disable\nx.abs\nx.abs\n";
+        let kept = apply(
+            source,
+            vec![
+                Diagnostic::test_at("E001", 1, 1),
+                Diagnostic::test_at("E001", 3, 1),
+                Diagnostic::test_at("E001", 4, 1),
+            ],
+        );
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].location.line, 1);
+    }
+
+    #[test]
+    fn test_unused_suppression_is_reported() {
+        let source = "x.abs # methodray:disable E001, E004\n";
+        let kept = apply(source, vec![Diagnostic::test_at("E001", 1, 1)]);
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].code.as_deref(), Some("E006"));
+        assert_eq!(kept[0].message, "unused suppression of E004");
+        assert_eq!(kept[0].location.column, 7);
+    }
+}
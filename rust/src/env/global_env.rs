@@ -4,14 +4,17 @@
 //! methods, type errors, and scopes during type inference.
 
 use crate::env::box_manager::BoxManager;
-use crate::env::method_registry::{MethodInfo, MethodRegistry};
+use crate::env::method_registry::{MethodInfo, MethodRegistry, OpenClassPolicy, SuperMethod};
 use crate::env::scope::{Scope, ScopeId, ScopeKind, ScopeManager};
-use crate::env::type_error::TypeError;
+use crate::env::type_error::{TypeError, TypeOrigin};
 use crate::env::vertex_manager::VertexManager;
 use crate::graph::{BoxId, BoxTrait, ChangeSet, EdgeUpdate, Source, Vertex, VertexId};
 use crate::source_map::SourceLocation;
 use crate::types::Type;
 
+/// Maximum number of related locations explaining a receiver type
+const MAX_TYPE_ORIGINS: usize = 5;
+
 /// Global environment: core of the type inference engine
 ///
 /// This is a facade that coordinates the various subsystems:
@@ -33,6 +36,15 @@ pub struct GlobalEnv {
     /// Type errors collected during analysis
     pub type_errors: Vec<TypeError>,
 
+    /// Statements after an unconditional `return`/`raise`/`next`/`break`
+    pub unreachable_code: Vec<SourceLocation>,
+
+    /// `super` calls no ancestor can answer
+    pub missing_super_methods: Vec<TypeError>,
+
+    /// Undefined methods called on open classes with the `info` policy
+    pub dynamic_calls: Vec<TypeError>,
+
     /// Scope management
     pub scope_manager: ScopeManager,
 }
@@ -45,6 +57,9 @@ impl GlobalEnv {
             box_manager: BoxManager::new(),
             method_registry: MethodRegistry::new(),
             type_errors: Vec::new(),
+            unreachable_code: Vec::new(),
+            missing_super_methods: Vec::new(),
+            dynamic_calls: Vec::new(),
             scope_manager: ScopeManager::new(),
         }
     }
@@ -71,6 +86,38 @@ impl GlobalEnv {
         self.vertex_manager.get_source(id)
     }
 
+    /// Record the expression that produced a vertex (the first one is kept)
+    pub fn record_vertex_location(&mut self, id: VertexId, location: SourceLocation) {
+        self.vertex_manager.set_location(id, location);
+    }
+
+    /// Source locations a type reached a vertex through, nearest first
+    ///
+    /// Locations equal to the vertex's own location are skipped, and at
+    /// most `MAX_TYPE_ORIGINS` are returned.
+    pub fn type_origins(&self, id: VertexId, ty: &Type) -> Vec<TypeOrigin> {
+        let own_location = self.vertex_manager.location(id);
+        let mut origins: Vec<TypeOrigin> = Vec::new();
+        for source in self.vertex_manager.type_provenance(id, ty) {
+            let Some(location) = self.vertex_manager.location(source) else {
+                continue;
+            };
+            if Some(location) == own_location
+                || origins.iter().any(|origin| &origin.location == location)
+            {
+                continue;
+            }
+            origins.push(TypeOrigin {
+                location: location.clone(),
+                is_source: self.vertex_manager.get_source(source).is_some(),
+            });
+            if origins.len() == MAX_TYPE_ORIGINS {
+                break;
+            }
+        }
+        origins
+    }
+
     /// Add edge (immediate type propagation)
     pub fn add_edge(&mut self, src: VertexId, dst: VertexId) {
         self.vertex_manager.add_edge(src, dst);
@@ -145,6 +192,11 @@ impl GlobalEnv {
         self.method_registry.resolve(recv_ty, method_name)
     }
 
+    /// Whether instances of a class are `is_a?` another class or module
+    pub fn is_kind_of(&self, class_name: &str, ancestor: &str) -> bool {
+        self.method_registry.is_kind_of(class_name, ancestor)
+    }
+
     /// Register built-in method
     pub fn register_builtin_method(&mut self, recv_ty: Type, method_name: &str, ret_ty: Type) {
         self.method_registry.register(recv_ty, method_name, ret_ty);
@@ -162,40 +214,245 @@ impl GlobalEnv {
             .register_with_block(recv_ty, method_name, ret_ty, block_param_types);
     }
 
+    /// Register user-defined method with the vertices receiving its blocks and return values
+    pub fn register_user_method(
+        &mut self,
+        recv_ty: Type,
+        method_name: &str,
+        block_vtx: VertexId,
+        return_vtx: VertexId,
+    ) {
+        self.method_registry
+            .register_user_method(recv_ty, method_name, block_vtx, return_vtx);
+    }
+
+    /// Register a method defined in the source whose signature is unknown
+    pub fn register_untyped_method(&mut self, recv_ty: Type, method_name: &str) {
+        self.method_registry.register_untyped(recv_ty, method_name);
+    }
+
+    /// Set the positional parameter vertices of a user-defined method
+    pub fn register_method_parameters(
+        &mut self,
+        recv_ty: Type,
+        method_name: &str,
+        param_vtxs: Vec<VertexId>,
+    ) {
+        self.method_registry
+            .set_param_vertices(recv_ty, method_name, param_vtxs);
+    }
+
+    /// Set the reporting policy of an open class (`*` sets the default)
+    pub fn set_open_class_policy(&mut self, class_name: &str, policy: OpenClassPolicy) {
+        self.method_registry
+            .set_open_class_policy(class_name, policy);
+    }
+
+    /// Get the reporting policy for undefined methods on a receiver type
+    ///
+    /// Returns `None` unless the receiver's class is open (defines `method_missing`).
+    pub fn open_class_policy(&self, recv_ty: &Type) -> Option<OpenClassPolicy> {
+        let class_name = recv_ty.base_class_name()?;
+        self.method_registry.open_class_policy(class_name)
+    }
+
+    /// Register `alias new old` / `alias_method :new, :old` on the current class
+    ///
+    /// The alias shares the signature and vertices of the original, which may
+    /// be inherited. An original that can't be found is registered untyped so
+    /// calls to the alias are not reported.
+    pub fn register_alias(&mut self, new_name: &str, old_name: &str) {
+        let recv_ty = self.current_self_type();
+        let info = self
+            .method_registry
+            .resolve(&recv_ty, old_name)
+            .cloned()
+            .or_else(|| {
+                let class_name = self.scope_manager.current_qualified_name()?;
+                match self.method_registry.resolve_super(&class_name, old_name) {
+                    SuperMethod::Found(info) => Some(info),
+                    _ => None,
+                }
+            });
+        match info {
+            Some(info) => self.method_registry.register_alias(recv_ty, new_name, info),
+            None => self.register_untyped_method(recv_ty, new_name),
+        }
+    }
+
+    /// Get the block vertex of the enclosing user-defined method (target of `yield`)
+    pub fn current_method_block_vertex(&self) -> Option<VertexId> {
+        self.current_user_method()?.block_vertex
+    }
+
+    /// Get the return vertex of the enclosing user-defined method (target of `return`)
+    pub fn current_method_return_vertex(&self) -> Option<VertexId> {
+        self.current_user_method()?.return_vertex
+    }
+
+    /// Get the positional parameter vertices of the enclosing user-defined method
+    pub fn current_method_param_vertices(&self) -> Vec<VertexId> {
+        self.current_user_method()
+            .map(|info| info.param_vertices.clone())
+            .unwrap_or_default()
+    }
+
+    /// Resolve the method `super` calls from the enclosing method
+    ///
+    /// Returns `None` outside a method body. Singleton methods are not
+    /// tracked, so their `super` is unknown.
+    pub fn resolve_current_super_method(&self) -> Option<(String, SuperMethod)> {
+        let method_name = self.scope_manager.current_method_name()?;
+        if self.scope_manager.in_singleton_method() {
+            return Some((method_name, SuperMethod::Unknown));
+        }
+        let class_name = self
+            .scope_manager
+            .current_qualified_name()
+            .unwrap_or_else(|| "Object".to_string());
+        let super_method = self
+            .method_registry
+            .resolve_super(&class_name, &method_name);
+        Some((method_name, super_method))
+    }
+
+    /// Register `include SomeModule` in the current class or module body
+    pub fn register_include(&mut self, module_name: &str) {
+        if let Some(name) = self.scope_manager.current_qualified_name() {
+            let module_name = self.resolve_constant_name(module_name);
+            self.method_registry.register_include(&name, module_name);
+        }
+    }
+
+    /// Resolve a constant name against the enclosing namespaces
+    ///
+    /// `Base` inside `module Api` is `Api::Base` when that class or module
+    /// is defined in the source; otherwise the name is kept as written.
+    fn resolve_constant_name(&self, name: &str) -> String {
+        let mut namespace = self.scope_manager.current_qualified_name();
+        while let Some(prefix) = namespace {
+            let candidate = format!("{}::{}", prefix, name);
+            if self.method_registry.is_user_defined(&candidate) {
+                return candidate;
+            }
+            namespace = prefix.rsplit_once("::").map(|(outer, _)| outer.to_string());
+        }
+        name.to_string()
+    }
+
+    /// Type of an exception class named in `rescue`
+    ///
+    /// None unless the class or one of its ancestors is loaded from RBS
+    /// (e.g., `MyError < StandardError`): the methods of other classes are
+    /// unknown.
+    pub fn exception_type(&self, name: &str) -> Option<Type> {
+        let name = self.resolve_constant_name(name);
+        self.method_registry
+            .has_rbs_ancestor(&name)
+            .then(|| Type::instance(&name))
+    }
+
+    /// Get the enclosing user-defined method
+    fn current_user_method(&self) -> Option<&MethodInfo> {
+        let method_name = self.scope_manager.current_method_name()?;
+        self.method_registry
+            .resolve(&self.current_self_type(), &method_name)
+    }
+
     // ===== Type Errors =====
 
     /// Record a type error (undefined method)
+    ///
+    /// `receiver_location` is the receiver expression, labelled with the
+    /// inferred type in diagnostics; the locations the type flowed through
+    /// are traced back from `recv_vtx`. Similarly spelled methods of the
+    /// receiver type are suggested.
     pub fn record_type_error(
         &mut self,
+        recv_vtx: VertexId,
         receiver_type: Type,
         method_name: String,
         location: Option<SourceLocation>,
+        receiver_location: Option<SourceLocation>,
     ) {
-        self.type_errors
+        let origins = self.type_origins(recv_vtx, &receiver_type);
+        let suggestions = self
+            .method_registry
+            .similar_methods(&receiver_type, &method_name);
+        self.type_errors.push(
+            TypeError::new(receiver_type, method_name, location)
+                .with_receiver_location(receiver_location)
+                .with_origins(origins)
+                .with_suggestions(suggestions),
+        );
+    }
+
+    /// Record a `super` call without a superclass method
+    pub fn record_missing_super_method(
+        &mut self,
+        receiver_type: Type,
+        method_name: String,
+        location: Option<SourceLocation>,
+    ) {
+        self.missing_super_methods
             .push(TypeError::new(receiver_type, method_name, location));
     }
 
+    /// Record a call answered by `method_missing` on an open class
+    pub fn record_dynamic_call(
+        &mut self,
+        receiver_type: Type,
+        method_name: String,
+        location: Option<SourceLocation>,
+    ) {
+        self.dynamic_calls
+            .push(TypeError::new(receiver_type, method_name, location));
+    }
+
+    /// Record the first statement of unreachable code
+    pub fn record_unreachable_code(&mut self, location: SourceLocation) {
+        self.unreachable_code.push(location);
+    }
+
     // ===== Scope Management =====
 
-    /// Enter a class scope
-    pub fn enter_class(&mut self, name: String) -> ScopeId {
+    /// Enter a class scope and register the class with its superclass
+    pub fn enter_class(&mut self, name: String, superclass: Option<String>) -> ScopeId {
+        // The superclass is looked up from the enclosing namespace
+        let superclass = superclass.map(|superclass| self.resolve_constant_name(&superclass));
         let scope_id = self.scope_manager.new_scope(ScopeKind::Class {
             name,
-            superclass: None,
+            superclass: superclass.clone(),
         });
         self.scope_manager.enter_scope(scope_id);
+        if let Some(qualified_name) = self.scope_manager.current_qualified_name() {
+            self.method_registry
+                .register_class(&qualified_name, superclass);
+        }
         scope_id
     }
 
-    /// Enter a module scope
+    /// Enter a module scope and register the module
     pub fn enter_module(&mut self, name: String) -> ScopeId {
         let scope_id = self.scope_manager.new_scope(ScopeKind::Module { name });
         self.scope_manager.enter_scope(scope_id);
+        if let Some(qualified_name) = self.scope_manager.current_qualified_name() {
+            self.method_registry.register_module(&qualified_name);
+        }
         scope_id
     }
 
     /// Enter a method scope
     pub fn enter_method(&mut self, name: String) -> ScopeId {
+        self.enter_method_scope(name, false)
+    }
+
+    /// Enter a singleton method scope (`def self.name`)
+    pub fn enter_singleton_method(&mut self, name: String) -> ScopeId {
+        self.enter_method_scope(name, true)
+    }
+
+    fn enter_method_scope(&mut self, name: String, singleton: bool) -> ScopeId {
         // Look for class or module context
         let receiver_type = self
             .scope_manager
@@ -204,11 +461,21 @@ impl GlobalEnv {
         let scope_id = self.scope_manager.new_scope(ScopeKind::Method {
             name,
             receiver_type,
+            singleton,
         });
         self.scope_manager.enter_scope(scope_id);
         scope_id
     }
 
+    /// Get the type of `self` in the current scope
+    /// Uses the fully qualified name if available (e.g., Api::V1::User instead of just User)
+    pub fn current_self_type(&self) -> Type {
+        match self.scope_manager.current_qualified_name() {
+            Some(qualified_name) => Type::instance(&qualified_name),
+            None => Type::instance("Object"),
+        }
+    }
+
     /// Exit current scope
     pub fn exit_scope(&mut self) {
         self.scope_manager.exit_scope();
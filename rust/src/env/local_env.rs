@@ -2,6 +2,9 @@ use crate::graph::VertexId;
 use std::collections::HashMap;
 
 /// Local environment: mapping of local variable names to VertexIDs
+///
+/// Records every local bound in the file (latest binding wins) for reporting
+/// inferred types. Lookups during analysis are scoped through `ScopeManager`.
 pub struct LocalEnv {
     locals: HashMap<String, VertexId>,
 }
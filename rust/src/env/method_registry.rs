@@ -1,19 +1,208 @@
 //! Method registration and resolution
 
+use crate::graph::VertexId;
 use crate::types::Type;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Instance methods every object inherits from Object, Kernel and BasicObject
+///
+/// These classes are not loaded from RBS, so `super` reaching the implicit
+/// `Object` superclass is checked against this list.
+const OBJECT_METHODS: &[&str] = &[
+    "initialize",
+    "initialize_copy",
+    "initialize_dup",
+    "initialize_clone",
+    "method_missing",
+    "respond_to_missing?",
+    "respond_to?",
+    "to_s",
+    "inspect",
+    "==",
+    "!=",
+    "===",
+    "=~",
+    "!~",
+    "<=>",
+    "eql?",
+    "equal?",
+    "hash",
+    "freeze",
+    "frozen?",
+    "dup",
+    "clone",
+    "send",
+    "public_send",
+    "__send__",
+    "is_a?",
+    "kind_of?",
+    "instance_of?",
+    "nil?",
+    "class",
+    "object_id",
+    "method",
+    "methods",
+    "instance_variable_get",
+    "instance_variable_set",
+    "instance_variables",
+    "extend",
+    "tap",
+    "then",
+    "display",
+    "instance_eval",
+    "instance_exec",
+    "singleton_method_added",
+    "new",
+    "allocate",
+    "inherited",
+    "included",
+    "extended",
+    "prepended",
+    "method_added",
+    "const_missing",
+];
+
+/// Superclasses of built-in classes; other classes inherit from Object
+const CORE_SUPERCLASSES: &[(&str, &str)] = &[
+    ("Object", "BasicObject"),
+    ("Integer", "Numeric"),
+    ("Float", "Numeric"),
+    ("Rational", "Numeric"),
+    ("Complex", "Numeric"),
+    ("Class", "Module"),
+    ("StandardError", "Exception"),
+    ("ScriptError", "Exception"),
+    ("SignalException", "Exception"),
+    ("Interrupt", "SignalException"),
+    ("SystemExit", "Exception"),
+    ("NoMemoryError", "Exception"),
+    ("SecurityError", "Exception"),
+    ("SystemStackError", "Exception"),
+    ("LoadError", "ScriptError"),
+    ("NotImplementedError", "ScriptError"),
+    ("SyntaxError", "ScriptError"),
+    ("ArgumentError", "StandardError"),
+    ("UncaughtThrowError", "ArgumentError"),
+    ("EncodingError", "StandardError"),
+    ("FiberError", "StandardError"),
+    ("IOError", "StandardError"),
+    ("EOFError", "IOError"),
+    ("IndexError", "StandardError"),
+    ("KeyError", "IndexError"),
+    ("StopIteration", "IndexError"),
+    ("LocalJumpError", "StandardError"),
+    ("NameError", "StandardError"),
+    ("NoMethodError", "NameError"),
+    ("RangeError", "StandardError"),
+    ("FloatDomainError", "RangeError"),
+    ("RegexpError", "StandardError"),
+    ("RuntimeError", "StandardError"),
+    ("FrozenError", "RuntimeError"),
+    ("SystemCallError", "StandardError"),
+    ("ThreadError", "StandardError"),
+    ("TypeError", "StandardError"),
+    ("ZeroDivisionError", "StandardError"),
+];
+
+/// Modules included by built-in classes
+const CORE_INCLUDES: &[(&str, &[&str])] = &[
+    ("Object", &["Kernel"]),
+    ("Numeric", &["Comparable"]),
+    ("String", &["Comparable"]),
+    ("Symbol", &["Comparable"]),
+    ("Array", &["Enumerable"]),
+    ("Hash", &["Enumerable"]),
+    ("Range", &["Enumerable"]),
+    ("Struct", &["Enumerable"]),
+];
+
+/// Built-in modules
+const CORE_MODULES: &[&str] = &["Kernel", "Comparable", "Enumerable"];
+
+/// Maximum number of "did you mean?" suggestions
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Whether a method name is an identifier (optionally ending in `?` or `!`)
+fn is_plain_method_name(name: &str) -> bool {
+    let body = name.strip_suffix(['?', '!']).unwrap_or(name);
+    body.starts_with(|c: char| c.is_alphabetic() || c == '_')
+        && body.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
 
 /// Method information
 #[derive(Debug, Clone)]
 pub struct MethodInfo {
     pub return_type: Type,
     pub block_param_types: Option<Vec<Type>>,
+    /// Vertex receiving the blocks passed at call sites (user-defined methods only)
+    ///
+    /// `yield` and `&block` inside the method body read from this vertex.
+    pub block_vertex: Option<VertexId>,
+    /// Vertex receiving the values returned by the method body (user-defined methods only)
+    pub return_vertex: Option<VertexId>,
+    /// Vertices of the positional parameters (user-defined methods only)
+    ///
+    /// Implicit `super` forwards these to the overridden method.
+    pub param_vertices: Vec<VertexId>,
+}
+
+/// Methods that answer calls to undefined methods
+const DYNAMIC_DISPATCH_METHODS: &[&str] = &["method_missing", "respond_to_missing?"];
+
+/// How calls to undefined methods on an open class are reported
+///
+/// A class is open when it (or an ancestor) defines `method_missing` or
+/// `respond_to_missing?`, like proxies, decorators and OpenStruct-likes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OpenClassPolicy {
+    /// Not reported
+    #[default]
+    Ignore,
+    /// Reported as an informational diagnostic
+    Info,
+}
+
+impl std::str::FromStr for OpenClassPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ignore" => Ok(Self::Ignore),
+            "info" => Ok(Self::Info),
+            _ => Err(format!("unknown policy `{}` (expected ignore or info)", s)),
+        }
+    }
+}
+
+/// Result of resolving `super` against the ancestors of a class
+#[derive(Debug, Clone)]
+pub enum SuperMethod {
+    /// The nearest ancestor defining the method
+    Found(MethodInfo),
+    /// No ancestor defines the method
+    Missing,
+    /// The ancestors are not fully known (library superclass, module body, etc.)
+    Unknown,
 }
 
 /// Registry for method definitions
 #[derive(Debug, Default)]
 pub struct MethodRegistry {
     methods: HashMap<(Type, String), MethodInfo>,
+    /// Classes defined in the analyzed source and their superclass, if written
+    classes: HashMap<String, Option<String>>,
+    /// Modules defined in the analyzed source
+    modules: HashSet<String>,
+    /// Modules included by each class or module, in order
+    includes: HashMap<String, Vec<String>>,
+    /// Reporting policy of open classes, by class name
+    open_class_policies: HashMap<String, OpenClassPolicy>,
+    /// Reporting policy of open classes without their own policy
+    default_open_class_policy: OpenClassPolicy,
+    /// Classes with instance methods loaded from RBS
+    rbs_classes: HashSet<String>,
 }
 
 impl MethodRegistry {
@@ -21,6 +210,12 @@ impl MethodRegistry {
     pub fn new() -> Self {
         Self {
             methods: HashMap::new(),
+            classes: HashMap::new(),
+            modules: HashSet::new(),
+            includes: HashMap::new(),
+            open_class_policies: HashMap::new(),
+            default_open_class_policy: OpenClassPolicy::default(),
+            rbs_classes: HashSet::new(),
         }
     }
 
@@ -36,20 +231,330 @@ impl MethodRegistry {
         method_name: &str,
         ret_ty: Type,
         block_param_types: Option<Vec<Type>>,
+    ) {
+        if let Type::Instance { name } = &recv_ty {
+            self.rbs_classes.insert(name.full_name().to_string());
+        }
+        self.insert_method(recv_ty, method_name, ret_ty, block_param_types);
+    }
+
+    /// Register a method defined in the analyzed source whose signature is unknown
+    ///
+    /// Calls to it are not reported, and unlike RBS methods it does not make
+    /// the class count as loaded.
+    pub fn register_untyped(&mut self, recv_ty: Type, method_name: &str) {
+        self.insert_method(recv_ty, method_name, Type::Bot, None);
+    }
+
+    fn insert_method(
+        &mut self,
+        recv_ty: Type,
+        method_name: &str,
+        ret_ty: Type,
+        block_param_types: Option<Vec<Type>>,
     ) {
         self.methods.insert(
             (recv_ty, method_name.to_string()),
             MethodInfo {
                 return_type: ret_ty,
                 block_param_types,
+                block_vertex: None,
+                return_vertex: None,
+                param_vertices: Vec::new(),
             },
         );
     }
 
+    /// Register a user-defined method
+    ///
+    /// The return type is inferred from the values flowing into `return_vtx`,
+    /// so it is registered as `Bot`. Reopening a class with an RBS definition
+    /// keeps the RBS signature.
+    pub fn register_user_method(
+        &mut self,
+        recv_ty: Type,
+        method_name: &str,
+        block_vtx: VertexId,
+        return_vtx: VertexId,
+    ) {
+        let info = self
+            .methods
+            .entry((recv_ty, method_name.to_string()))
+            .or_insert_with(|| MethodInfo {
+                return_type: Type::Bot,
+                block_param_types: None,
+                block_vertex: None,
+                return_vertex: None,
+                param_vertices: Vec::new(),
+            });
+        info.block_vertex = Some(block_vtx);
+        info.return_vertex = Some(return_vtx);
+    }
+
+    /// Register a method under another name with the same signature and vertices
+    ///
+    /// Used for `alias` and `alias_method`.
+    pub fn register_alias(&mut self, recv_ty: Type, method_name: &str, info: MethodInfo) {
+        self.methods
+            .insert((recv_ty, method_name.to_string()), info);
+    }
+
+    /// Set the positional parameter vertices of a user-defined method
+    pub fn set_param_vertices(&mut self, recv_ty: Type, method_name: &str, vtxs: Vec<VertexId>) {
+        if let Some(info) = self.methods.get_mut(&(recv_ty, method_name.to_string())) {
+            info.param_vertices = vtxs;
+        }
+    }
+
+    /// Register a class defined in the analyzed source
+    ///
+    /// Reopening a class without a superclass keeps the one already registered.
+    pub fn register_class(&mut self, name: &str, superclass: Option<String>) {
+        let entry = self.classes.entry(name.to_string()).or_insert(None);
+        if superclass.is_some() {
+            *entry = superclass;
+        }
+    }
+
+    /// Register a module defined in the analyzed source
+    pub fn register_module(&mut self, name: &str) {
+        self.modules.insert(name.to_string());
+    }
+
+    /// Register `include SomeModule` in a class or module body
+    pub fn register_include(&mut self, name: &str, module_name: String) {
+        let modules = self.includes.entry(name.to_string()).or_default();
+        if !modules.contains(&module_name) {
+            modules.push(module_name);
+        }
+    }
+
+    /// Whether a class or module is defined in the analyzed source
+    pub fn is_user_defined(&self, name: &str) -> bool {
+        self.classes.contains_key(name) || self.modules.contains(name)
+    }
+
+    /// Resolve the method `super` calls from `method_name` in `class_name`
+    ///
+    /// Walks the included modules (last included first) and then the
+    /// superclass of each class in the chain. RBS classes are loaded with
+    /// their inherited methods, so reaching one ends the search. A class
+    /// whose superclass is not written in the source is not searched past.
+    pub fn resolve_super(&self, class_name: &str, method_name: &str) -> SuperMethod {
+        // The ancestors of a module depend on where it is included
+        if self.modules.contains(class_name) {
+            return SuperMethod::Unknown;
+        }
+
+        let mut visited = vec![class_name.to_string()];
+        let mut current = class_name.to_string();
+        loop {
+            if let Some(modules) = self.includes.get(&current) {
+                for module_name in modules.iter().rev() {
+                    if let Some(info) = self.lookup(module_name, method_name) {
+                        return SuperMethod::Found(info.clone());
+                    }
+                    if !self.is_user_defined(module_name) && !self.is_loaded(module_name) {
+                        return SuperMethod::Unknown;
+                    }
+                }
+            }
+
+            // A class without a written superclass may get one where it is
+            // defined elsewhere
+            let Some(Some(superclass)) = self.classes.get(&current).cloned() else {
+                return SuperMethod::Unknown;
+            };
+            if visited.contains(&superclass) {
+                return SuperMethod::Unknown;
+            }
+
+            if let Some(info) = self.lookup(&superclass, method_name) {
+                return SuperMethod::Found(info.clone());
+            }
+            if superclass == "Object" && !self.classes.contains_key("Object") {
+                return if OBJECT_METHODS.contains(&method_name) {
+                    SuperMethod::Unknown
+                } else {
+                    SuperMethod::Missing
+                };
+            }
+            if !self.classes.contains_key(&superclass) {
+                return if self.is_loaded(&superclass) {
+                    SuperMethod::Missing
+                } else {
+                    SuperMethod::Unknown
+                };
+            }
+
+            visited.push(superclass.clone());
+            current = superclass;
+        }
+    }
+
+    /// Set the reporting policy of an open class (`*` sets the default)
+    pub fn set_open_class_policy(&mut self, name: &str, policy: OpenClassPolicy) {
+        if name == "*" {
+            self.default_open_class_policy = policy;
+        } else {
+            self.open_class_policies.insert(name.to_string(), policy);
+        }
+    }
+
+    /// Get the reporting policy for undefined methods on an open class
+    ///
+    /// Returns `None` when the class is not open and undefined methods are errors.
+    pub fn open_class_policy(&self, name: &str) -> Option<OpenClassPolicy> {
+        if !self.is_open_class(name) {
+            return None;
+        }
+        Some(
+            self.open_class_policies
+                .get(name)
+                .copied()
+                .unwrap_or(self.default_open_class_policy),
+        )
+    }
+
+    /// Whether a class or one of its ancestors defines `method_missing` or `respond_to_missing?`
+    fn is_open_class(&self, name: &str) -> bool {
+        self.ancestors(name).iter().any(|class_name| {
+            DYNAMIC_DISPATCH_METHODS
+                .iter()
+                .any(|method_name| self.lookup(class_name, method_name).is_some())
+        })
+    }
+
+    /// A class followed by its included modules, then its superclass and so on
+    ///
+    /// Built-in classes use the core class hierarchy, and every class ends
+    /// in Object, Kernel and BasicObject.
+    fn ancestors(&self, name: &str) -> Vec<String> {
+        let mut ancestors: Vec<String> = Vec::new();
+        let mut current = Some(name.to_string());
+        while let Some(class_name) = current {
+            if ancestors.contains(&class_name) {
+                break;
+            }
+            current = self.superclass(&class_name);
+            ancestors.push(class_name.clone());
+            if let Some(modules) = self.includes.get(&class_name) {
+                ancestors.extend(modules.iter().cloned());
+            }
+            if let Some((_, modules)) = CORE_INCLUDES.iter().find(|(core, _)| *core == class_name) {
+                ancestors.extend(modules.iter().map(|module_name| module_name.to_string()));
+            }
+        }
+        ancestors
+    }
+
+    /// Superclass of a class; None for modules and BasicObject
+    fn superclass(&self, name: &str) -> Option<String> {
+        if self.modules.contains(name) || CORE_MODULES.contains(&name) || name == "BasicObject" {
+            return None;
+        }
+        if let Some(Some(superclass)) = self.classes.get(name) {
+            return Some(superclass.clone());
+        }
+        let superclass = CORE_SUPERCLASSES
+            .iter()
+            .find(|(class_name, _)| *class_name == name)
+            .map_or("Object", |(_, superclass)| superclass);
+        Some(superclass.to_string())
+    }
+
+    /// Whether a class or one of its ancestors has methods loaded from RBS
+    pub fn has_rbs_ancestor(&self, name: &str) -> bool {
+        self.ancestors(name)
+            .iter()
+            .any(|class_name| self.rbs_classes.contains(class_name))
+    }
+
+    /// Whether instances of a class are `is_a?` another class or module
+    pub fn is_kind_of(&self, name: &str, ancestor: &str) -> bool {
+        self.ancestors(name)
+            .iter()
+            .any(|class_name| class_name == ancestor)
+    }
+
+    /// Names of the methods known for a receiver type, sorted
+    ///
+    /// Includes the methods of its class's ancestors.
+    pub fn method_names(&self, recv_ty: &Type) -> Vec<&str> {
+        let class_name = match recv_ty {
+            Type::Instance { .. } | Type::Generic { .. } => recv_ty.base_class_name(),
+            Type::Nil => Some("NilClass"),
+            Type::Tuple(_) => Some("Array"),
+            Type::Proc { .. } => Some("Proc"),
+            _ => None,
+        };
+        let mut receivers = vec![recv_ty.clone()];
+        if let Some(class_name) = class_name {
+            receivers.extend(
+                self.ancestors(class_name)
+                    .iter()
+                    .map(|name| Type::instance(name)),
+            );
+        }
+
+        let mut names: Vec<&str> = self
+            .methods
+            .keys()
+            .filter(|(ty, _)| receivers.contains(ty))
+            .map(|(_, method_name)| method_name.as_str())
+            .collect();
+        names.sort_unstable();
+        names.dedup();
+        names
+    }
+
+    /// Known methods of a receiver type spelled like `method_name`, closest first
+    ///
+    /// Only plain method names are compared (not operators or setters); a
+    /// candidate may differ by about one edit per four characters.
+    pub fn similar_methods(&self, recv_ty: &Type, method_name: &str) -> Vec<String> {
+        if !is_plain_method_name(method_name) {
+            return Vec::new();
+        }
+        let max_distance = method_name.chars().count().div_ceil(4).max(1);
+
+        let mut candidates: Vec<(usize, &str)> = self
+            .method_names(recv_ty)
+            .into_iter()
+            .filter(|candidate| *candidate != method_name && is_plain_method_name(candidate))
+            .map(|candidate| {
+                (
+                    strsim::damerau_levenshtein(method_name, candidate),
+                    candidate,
+                )
+            })
+            .filter(|(distance, _)| *distance <= max_distance)
+            .collect();
+        candidates.sort_unstable();
+        candidates
+            .into_iter()
+            .take(MAX_SUGGESTIONS)
+            .map(|(_, candidate)| candidate.to_string())
+            .collect()
+    }
+
+    /// Look up a method defined directly on a class or module
+    fn lookup(&self, name: &str, method_name: &str) -> Option<&MethodInfo> {
+        self.methods
+            .get(&(Type::instance(name), method_name.to_string()))
+    }
+
+    /// Whether any method of a class was loaded from RBS
+    fn is_loaded(&self, name: &str) -> bool {
+        self.rbs_classes.contains(name)
+    }
+
     /// Resolve a method for a receiver type
     ///
     /// For generic types like `Array[Integer]`, first tries exact match,
     /// then falls back to base class match (`Array`).
+    /// `nil` receivers are resolved against `NilClass`, tuples against `Array`.
+    /// Instances fall back to the included modules and superclasses of their class.
     pub fn resolve(&self, recv_ty: &Type, method_name: &str) -> Option<&MethodInfo> {
         // First, try exact match
         if let Some(info) = self
@@ -59,13 +564,30 @@ impl MethodRegistry {
             return Some(info);
         }
 
-        // For generic types, fall back to base class
-        if let Type::Generic { name, .. } = recv_ty {
-            let base_type = Type::Instance { name: name.clone() };
-            return self.methods.get(&(base_type, method_name.to_string()));
+        match recv_ty {
+            // For generic types, fall back to base class
+            Type::Generic { name, .. } => {
+                let base_type = Type::Instance { name: name.clone() };
+                self.methods.get(&(base_type, method_name.to_string()))
+            }
+            // nil is an instance of NilClass
+            Type::Nil => self
+                .methods
+                .get(&(Type::instance("NilClass"), method_name.to_string())),
+            // Tuples are arrays
+            Type::Tuple(_) => self.methods.get(&(Type::array(), method_name.to_string())),
+            // Lambdas, procs and blocks are instances of Proc
+            Type::Proc { .. } => self
+                .methods
+                .get(&(Type::instance("Proc"), method_name.to_string())),
+            // Inherited methods
+            Type::Instance { name } => self
+                .ancestors(name.full_name())
+                .iter()
+                .skip(1)
+                .find_map(|ancestor| self.lookup(ancestor, method_name)),
+            _ => None,
         }
-
-        None
     }
 }
 
@@ -82,9 +604,292 @@ mod tests {
         assert_eq!(info.return_type.base_class_name(), Some("Integer"));
     }
 
+    #[test]
+    fn test_resolve_nil_as_nil_class() {
+        let mut registry = MethodRegistry::new();
+        registry.register(Type::instance("NilClass"), "to_s", Type::string());
+
+        let info = registry.resolve(&Type::Nil, "to_s").unwrap();
+        assert_eq!(info.return_type.base_class_name(), Some("String"));
+        assert!(registry.resolve(&Type::Nil, "+").is_none());
+    }
+
+    #[test]
+    fn test_resolve_tuple_as_array() {
+        let mut registry = MethodRegistry::new();
+        registry.register(Type::array(), "first", Type::Bot);
+
+        let tuple = Type::tuple(vec![Type::string(), Type::integer()]);
+        assert!(registry.resolve(&tuple, "first").is_some());
+    }
+
+    #[test]
+    fn test_register_user_method() {
+        let mut registry = MethodRegistry::new();
+        registry.register_user_method(
+            Type::instance("User"),
+            "each_item",
+            VertexId(7),
+            VertexId(8),
+        );
+
+        let info = registry
+            .resolve(&Type::instance("User"), "each_item")
+            .unwrap();
+        assert_eq!(info.block_vertex, Some(VertexId(7)));
+        assert_eq!(info.return_vertex, Some(VertexId(8)));
+        assert_eq!(info.return_type, Type::Bot);
+    }
+
+    #[test]
+    fn test_register_user_method_keeps_rbs_signature() {
+        let mut registry = MethodRegistry::new();
+        registry.register(Type::string(), "upcase", Type::string());
+        registry.register_user_method(Type::string(), "upcase", VertexId(3), VertexId(4));
+
+        let info = registry.resolve(&Type::string(), "upcase").unwrap();
+        assert_eq!(info.return_type, Type::string());
+        assert_eq!(info.block_vertex, Some(VertexId(3)));
+    }
+
     #[test]
     fn test_resolve_not_found() {
         let registry = MethodRegistry::new();
         assert!(registry.resolve(&Type::string(), "unknown").is_none());
     }
+
+    #[test]
+    fn test_resolve_inherited_method() {
+        let mut registry = MethodRegistry::new();
+        registry.register_class("Base", None);
+        registry.register_module("Greeting");
+        registry.register_class("User", Some("Base".to_string()));
+        registry.register_include("Base", "Greeting".to_string());
+        registry.register(Type::instance("Base"), "save", Type::Nil);
+        registry.register(Type::instance("Greeting"), "greet", Type::string());
+
+        let user = Type::instance("User");
+        assert!(registry.resolve(&user, "save").is_some());
+        assert_eq!(
+            registry.resolve(&user, "greet").unwrap().return_type,
+            Type::string()
+        );
+        assert!(registry.resolve(&user, "destroy").is_none());
+        // Superclasses do not see methods of their subclasses
+        registry.register(user, "update", Type::Nil);
+        assert!(registry
+            .resolve(&Type::instance("Base"), "update")
+            .is_none());
+    }
+
+    #[test]
+    fn test_is_kind_of() {
+        let mut registry = MethodRegistry::new();
+        registry.register_class("Base", None);
+        registry.register_module("Greeting");
+        registry.register_class("User", Some("Base".to_string()));
+        registry.register_include("Base", "Greeting".to_string());
+
+        assert!(registry.is_kind_of("User", "User"));
+        assert!(registry.is_kind_of("User", "Base"));
+        assert!(registry.is_kind_of("User", "Greeting"));
+        assert!(registry.is_kind_of("User", "Object"));
+        assert!(!registry.is_kind_of("Base", "User"));
+        assert!(!registry.is_kind_of("Greeting", "Object"));
+
+        // Built-in classes
+        assert!(registry.is_kind_of("Integer", "Numeric"));
+        assert!(registry.is_kind_of("Integer", "Comparable"));
+        assert!(registry.is_kind_of("String", "Comparable"));
+        assert!(registry.is_kind_of("Array", "Enumerable"));
+        assert!(registry.is_kind_of("KeyError", "StandardError"));
+        assert!(registry.is_kind_of("NilClass", "Kernel"));
+        assert!(!registry.is_kind_of("String", "Numeric"));
+        assert!(!registry.is_kind_of("Exception", "StandardError"));
+
+        // A reopened built-in class keeps its superclass
+        registry.register_class("Integer", None);
+        assert!(registry.is_kind_of("Integer", "Numeric"));
+    }
+
+    #[test]
+    fn test_has_rbs_ancestor() {
+        let mut registry = MethodRegistry::new();
+        registry.register(Type::instance("StandardError"), "message", Type::string());
+        registry.register(Type::instance("RuntimeError"), "message", Type::string());
+        registry.register_class("MyError", Some("StandardError".to_string()));
+        registry.register_class("LibError", Some("Lib::Error".to_string()));
+        registry.register_user_method(Type::instance("LibError"), "code", VertexId(1), VertexId(2));
+        registry.register_untyped(Type::instance("LibError"), "status");
+
+        assert!(registry.has_rbs_ancestor("StandardError"));
+        assert!(registry.has_rbs_ancestor("MyError"));
+        // Built-in subclass of a loaded class
+        assert!(registry.has_rbs_ancestor("FrozenError"));
+        assert!(!registry.has_rbs_ancestor("LibError"));
+        assert!(!registry.has_rbs_ancestor("Errno::ENOENT"));
+    }
+
+    #[test]
+    fn test_resolve_super_walks_includes_and_superclasses() {
+        let mut registry = MethodRegistry::new();
+        registry.register_module("Greeting");
+        registry.register_class("Base", Some("Object".to_string()));
+        registry.register_class("User", Some("Base".to_string()));
+        registry.register_include("User", "Greeting".to_string());
+        registry.register_user_method(
+            Type::instance("Greeting"),
+            "hello",
+            VertexId(1),
+            VertexId(2),
+        );
+        registry.register_user_method(Type::instance("Base"), "id", VertexId(3), VertexId(4));
+
+        let hello = registry.resolve_super("User", "hello");
+        assert!(
+            matches!(hello, SuperMethod::Found(info) if info.return_vertex == Some(VertexId(2)))
+        );
+        let id = registry.resolve_super("User", "id");
+        assert!(matches!(id, SuperMethod::Found(info) if info.return_vertex == Some(VertexId(4))));
+        assert!(matches!(
+            registry.resolve_super("User", "save"),
+            SuperMethod::Missing
+        ));
+        assert!(matches!(
+            registry.resolve_super("User", "to_s"),
+            SuperMethod::Unknown
+        ));
+    }
+
+    #[test]
+    fn test_resolve_super_stops_at_unknown_superclass() {
+        let mut registry = MethodRegistry::new();
+        registry.register(Type::instance("StandardError"), "message", Type::string());
+        registry.register_class("AppError", Some("StandardError".to_string()));
+        registry.register_class("Record", Some("ActiveRecord::Base".to_string()));
+        registry.register_class("User", None);
+
+        assert!(matches!(
+            registry.resolve_super("AppError", "message"),
+            SuperMethod::Found(_)
+        ));
+        assert!(matches!(
+            registry.resolve_super("AppError", "save"),
+            SuperMethod::Missing
+        ));
+        assert!(matches!(
+            registry.resolve_super("Record", "save"),
+            SuperMethod::Unknown
+        ));
+        // The superclass may be written where the class is defined elsewhere
+        assert!(matches!(
+            registry.resolve_super("User", "save"),
+            SuperMethod::Unknown
+        ));
+    }
+
+    #[test]
+    fn test_register_alias_copies_signature() {
+        let mut registry = MethodRegistry::new();
+        registry.register_user_method(Type::instance("User"), "name", VertexId(1), VertexId(2));
+
+        let info = registry
+            .resolve(&Type::instance("User"), "name")
+            .unwrap()
+            .clone();
+        registry.register_alias(Type::instance("User"), "full_name", info);
+
+        let alias = registry
+            .resolve(&Type::instance("User"), "full_name")
+            .unwrap();
+        assert_eq!(alias.return_vertex, Some(VertexId(2)));
+    }
+
+    #[test]
+    fn test_open_class_policy() {
+        let mut registry = MethodRegistry::new();
+        registry.register_class("Proxy", None);
+        registry.register_class("LoggingProxy", Some("Proxy".to_string()));
+        registry.register_class("User", None);
+        registry.register_user_method(
+            Type::instance("Proxy"),
+            "method_missing",
+            VertexId(1),
+            VertexId(2),
+        );
+
+        assert_eq!(
+            registry.open_class_policy("Proxy"),
+            Some(OpenClassPolicy::Ignore)
+        );
+        assert_eq!(
+            registry.open_class_policy("LoggingProxy"),
+            Some(OpenClassPolicy::Ignore)
+        );
+        assert_eq!(registry.open_class_policy("User"), None);
+
+        registry.set_open_class_policy("LoggingProxy", OpenClassPolicy::Info);
+        assert_eq!(
+            registry.open_class_policy("LoggingProxy"),
+            Some(OpenClassPolicy::Info)
+        );
+        assert_eq!(
+            registry.open_class_policy("Proxy"),
+            Some(OpenClassPolicy::Ignore)
+        );
+
+        registry.set_open_class_policy("*", OpenClassPolicy::Info);
+        assert_eq!(
+            registry.open_class_policy("Proxy"),
+            Some(OpenClassPolicy::Info)
+        );
+    }
+
+    #[test]
+    fn test_rbs_class_with_method_missing_is_open() {
+        let mut registry = MethodRegistry::new();
+        registry.register(Type::instance("OpenStruct"), "method_missing", Type::Bot);
+
+        assert_eq!(
+            registry.open_class_policy("OpenStruct"),
+            Some(OpenClassPolicy::Ignore)
+        );
+        assert_eq!("info".parse(), Ok(OpenClassPolicy::Info));
+        assert!("warn".parse::<OpenClassPolicy>().is_err());
+    }
+
+    #[test]
+    fn test_similar_methods() {
+        let mut registry = MethodRegistry::new();
+        for method_name in ["upcase", "upcase!", "downcase", "length", "+"] {
+            registry.register(Type::string(), method_name, Type::string());
+        }
+
+        assert_eq!(
+            registry.similar_methods(&Type::string(), "upcse"),
+            vec!["upcase", "upcase!"]
+        );
+        assert_eq!(
+            registry.similar_methods(&Type::string(), "lenght"),
+            vec!["length"]
+        );
+        assert!(registry.similar_methods(&Type::string(), "zzz").is_empty());
+        // Operators are not spell-checked
+        assert!(registry.similar_methods(&Type::string(), "-").is_empty());
+    }
+
+    #[test]
+    fn test_similar_methods_of_ancestors() {
+        let mut registry = MethodRegistry::new();
+        registry.register_class("Base", None);
+        registry.register_module("Greeting");
+        registry.register_class("User", Some("Base".to_string()));
+        registry.register_include("User", "Greeting".to_string());
+        registry.register(Type::instance("Base"), "save", Type::Nil);
+        registry.register(Type::instance("Greeting"), "greet", Type::Nil);
+
+        let user = Type::instance("User");
+        assert_eq!(registry.similar_methods(&user, "sav"), vec!["save"]);
+        assert_eq!(registry.similar_methods(&user, "gret"), vec!["greet"]);
+    }
 }
@@ -20,10 +20,23 @@ pub enum ScopeKind {
     Method {
         name: String,
         receiver_type: Option<String>, // Receiver class/module name
+        singleton: bool,               // `def self.name`
     },
     Block,
 }
 
+impl ScopeKind {
+    /// Whether the scope starts a new set of locals (`def`, `class`, `module`)
+    ///
+    /// Only blocks see the locals of enclosing scopes.
+    pub fn is_hard_scope(&self) -> bool {
+        matches!(
+            self,
+            ScopeKind::Class { .. } | ScopeKind::Module { .. } | ScopeKind::Method { .. }
+        )
+    }
+}
+
 /// Scope information
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
@@ -145,18 +158,79 @@ impl ScopeManager {
     }
 
     /// Lookup variable in current scope or parent scopes
+    ///
+    /// Blocks see the locals of enclosing scopes, while a method (`def`),
+    /// class or module body starts a new local scope: lookup stops there.
     pub fn lookup_var(&self, name: &str) -> Option<VertexId> {
+        let scope_id = self.defining_scope(name)?;
+        self.scopes.get(&scope_id)?.get_local_var(name)
+    }
+
+    /// Assign a local variable (x = value)
+    ///
+    /// Rebinds the variable in the visible scope that defines it (e.g., a block
+    /// assigning to an enclosing method's local), otherwise binds it in the
+    /// current scope.
+    pub fn assign_var(&mut self, name: String, vtx: VertexId) {
+        let scope_id = self.defining_scope(&name).unwrap_or(self.current_scope);
+        if let Some(scope) = self.scopes.get_mut(&scope_id) {
+            scope.set_local_var(name, vtx);
+        }
+    }
+
+    /// Get all visible local variables (inner scopes shadow outer ones)
+    pub fn visible_vars(&self) -> HashMap<String, VertexId> {
+        let mut vars = HashMap::new();
         let mut current = Some(self.current_scope);
 
         while let Some(scope_id) = current {
-            if let Some(scope) = self.scopes.get(&scope_id) {
-                if let Some(vtx) = scope.get_local_var(name) {
-                    return Some(vtx);
-                }
-                current = scope.parent;
-            } else {
+            let Some(scope) = self.scopes.get(&scope_id) else {
+                break;
+            };
+            for (name, vtx) in &scope.local_vars {
+                vars.entry(name.clone()).or_insert(*vtx);
+            }
+            if scope.kind.is_hard_scope() {
                 break;
             }
+            current = scope.parent;
+        }
+
+        vars
+    }
+
+    /// Restore visible local variables to a snapshot taken by `visible_vars`
+    ///
+    /// Variables bound after the snapshot are removed.
+    pub fn restore_vars(&mut self, snapshot: &HashMap<String, VertexId>) {
+        for name in self.visible_vars().into_keys() {
+            match snapshot.get(&name) {
+                Some(vtx) => self.assign_var(name, *vtx),
+                None => {
+                    if let Some(scope_id) = self.defining_scope(&name) {
+                        if let Some(scope) = self.scopes.get_mut(&scope_id) {
+                            scope.local_vars.remove(&name);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Find the visible scope that defines a local variable
+    fn defining_scope(&self, name: &str) -> Option<ScopeId> {
+        let mut current = Some(self.current_scope);
+
+        while let Some(scope_id) = current {
+            let scope = self.scopes.get(&scope_id)?;
+            if scope.get_local_var(name).is_some() {
+                return Some(scope_id);
+            }
+            if scope.kind.is_hard_scope() {
+                // def, class and module are hard scopes: outer locals are not visible
+                return None;
+            }
+            current = scope.parent;
         }
 
         None
@@ -245,6 +319,42 @@ impl ScopeManager {
         None
     }
 
+    /// Get the name of the enclosing method (blocks inside a method included)
+    pub fn current_method_name(&self) -> Option<String> {
+        let mut current = Some(self.current_scope);
+
+        while let Some(scope_id) = current {
+            if let Some(scope) = self.scopes.get(&scope_id) {
+                if let ScopeKind::Method { name, .. } = &scope.kind {
+                    return Some(name.clone());
+                }
+                current = scope.parent;
+            } else {
+                break;
+            }
+        }
+
+        None
+    }
+
+    /// Whether the enclosing method is a singleton method (`def self.name`)
+    pub fn in_singleton_method(&self) -> bool {
+        let mut current = Some(self.current_scope);
+
+        while let Some(scope_id) = current {
+            if let Some(scope) = self.scopes.get(&scope_id) {
+                if let ScopeKind::Method { singleton, .. } = &scope.kind {
+                    return *singleton;
+                }
+                current = scope.parent;
+            } else {
+                break;
+            }
+        }
+
+        false
+    }
+
     /// Get current fully qualified name by traversing all parent class/module scopes
     ///
     /// For example, in:
@@ -414,20 +524,116 @@ mod tests {
         sm.current_scope_mut()
             .set_local_var("x".to_string(), VertexId(10));
 
-        // Enter class
+        // Enter block
+        let block_id = sm.new_scope(ScopeKind::Block);
+        sm.enter_scope(block_id);
+
+        // Block level: y = 20
+        sm.current_scope_mut()
+            .set_local_var("y".to_string(), VertexId(20));
+
+        // Can lookup both x (from parent) and y (from current)
+        assert_eq!(sm.lookup_var("x"), Some(VertexId(10)));
+        assert_eq!(sm.lookup_var("y"), Some(VertexId(20)));
+    }
+
+    #[test]
+    fn test_scope_manager_method_is_hard_scope() {
+        let mut sm = ScopeManager::new();
+
+        // Top level: x = 10
+        sm.current_scope_mut()
+            .set_local_var("x".to_string(), VertexId(10));
+
+        let method_id = sm.new_scope(ScopeKind::Method {
+            name: "run".to_string(),
+            receiver_type: None,
+            singleton: false,
+        });
+        sm.enter_scope(method_id);
+        sm.current_scope_mut()
+            .set_local_var("y".to_string(), VertexId(20));
+
+        let block_id = sm.new_scope(ScopeKind::Block);
+        sm.enter_scope(block_id);
+
+        // Block sees the method's locals, but not the top level's
+        assert_eq!(sm.lookup_var("y"), Some(VertexId(20)));
+        assert_eq!(sm.lookup_var("x"), None);
+    }
+
+    #[test]
+    fn test_scope_manager_class_and_module_are_hard_scopes() {
+        let mut sm = ScopeManager::new();
+
+        // Top level: x = 10
+        sm.current_scope_mut()
+            .set_local_var("x".to_string(), VertexId(10));
+
+        let module_id = sm.new_scope(ScopeKind::Module {
+            name: "Admin".to_string(),
+        });
+        sm.enter_scope(module_id);
+        assert_eq!(sm.lookup_var("x"), None);
+        sm.current_scope_mut()
+            .set_local_var("y".to_string(), VertexId(20));
+
         let class_id = sm.new_scope(ScopeKind::Class {
             name: "User".to_string(),
             superclass: None,
         });
         sm.enter_scope(class_id);
 
-        // Class level: y = 20
+        // Neither the top level's nor the module's locals are visible
+        assert_eq!(sm.lookup_var("x"), None);
+        assert_eq!(sm.lookup_var("y"), None);
+        assert!(sm.visible_vars().is_empty());
+
+        // Assignment binds a fresh local in the class body
+        sm.assign_var("x".to_string(), VertexId(30));
+        assert_eq!(sm.lookup_var("x"), Some(VertexId(30)));
+        sm.exit_scope();
+        sm.exit_scope();
+        assert_eq!(sm.lookup_var("x"), Some(VertexId(10)));
+    }
+
+    #[test]
+    fn test_scope_manager_assign_var() {
+        let mut sm = ScopeManager::new();
+
         sm.current_scope_mut()
-            .set_local_var("y".to_string(), VertexId(20));
+            .set_local_var("x".to_string(), VertexId(10));
+
+        let block_id = sm.new_scope(ScopeKind::Block);
+        sm.enter_scope(block_id);
+
+        // Existing outer local is rebound in its own scope
+        sm.assign_var("x".to_string(), VertexId(11));
+        // New local stays in the block
+        sm.assign_var("y".to_string(), VertexId(20));
+
+        sm.exit_scope();
+
+        assert_eq!(sm.lookup_var("x"), Some(VertexId(11)));
+        assert_eq!(sm.lookup_var("y"), None);
+    }
+
+    #[test]
+    fn test_scope_manager_visible_vars_and_restore() {
+        let mut sm = ScopeManager::new();
+
+        sm.current_scope_mut()
+            .set_local_var("x".to_string(), VertexId(10));
+        let snapshot = sm.visible_vars();
+
+        sm.assign_var("x".to_string(), VertexId(11));
+        sm.assign_var("y".to_string(), VertexId(20));
+        assert_eq!(sm.visible_vars().len(), 2);
+
+        sm.restore_vars(&snapshot);
 
-        // Can lookup both x (from parent) and y (from current)
         assert_eq!(sm.lookup_var("x"), Some(VertexId(10)));
-        assert_eq!(sm.lookup_var("y"), Some(VertexId(20)));
+        assert_eq!(sm.lookup_var("y"), None);
     }
 
     #[test]
@@ -448,6 +654,7 @@ mod tests {
         let method_id = sm.new_scope(ScopeKind::Method {
             name: "test".to_string(),
             receiver_type: None,
+            singleton: false,
         });
         sm.enter_scope(method_id);
 
@@ -455,6 +662,26 @@ mod tests {
         assert_eq!(sm.current_class_name(), Some("User".to_string()));
     }
 
+    #[test]
+    fn test_scope_manager_current_method_name() {
+        let mut sm = ScopeManager::new();
+
+        assert_eq!(sm.current_method_name(), None);
+
+        let method_id = sm.new_scope(ScopeKind::Method {
+            name: "each_item".to_string(),
+            receiver_type: None,
+            singleton: false,
+        });
+        sm.enter_scope(method_id);
+
+        let block_id = sm.new_scope(ScopeKind::Block);
+        sm.enter_scope(block_id);
+
+        // Should find the method enclosing the block
+        assert_eq!(sm.current_method_name(), Some("each_item".to_string()));
+    }
+
     #[test]
     fn test_scope_manager_module_scope() {
         let mut sm = ScopeManager::new();
@@ -472,6 +699,7 @@ mod tests {
         let method_id = sm.new_scope(ScopeKind::Method {
             name: "helper".to_string(),
             receiver_type: Some("Utils".to_string()),
+            singleton: false,
         });
         sm.enter_scope(method_id);
 
@@ -500,6 +728,7 @@ mod tests {
         let method_id = sm.new_scope(ScopeKind::Method {
             name: "get_setting".to_string(),
             receiver_type: Some("Config".to_string()),
+            singleton: false,
         });
         sm.enter_scope(method_id);
 
@@ -559,6 +788,7 @@ mod tests {
         let method_id = sm.new_scope(ScopeKind::Method {
             name: "greet".to_string(),
             receiver_type: None,
+            singleton: false,
         });
         sm.enter_scope(method_id);
 
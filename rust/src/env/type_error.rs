@@ -9,6 +9,20 @@ pub struct TypeError {
     pub receiver_type: Type,
     pub method_name: String,
     pub location: Option<SourceLocation>,
+    /// Location of the receiver expression (e.g., `x` in `x.upcase`)
+    pub receiver_location: Option<SourceLocation>,
+    /// Where the receiver type came from, nearest first
+    pub origins: Vec<TypeOrigin>,
+    /// Similarly spelled methods of the receiver type ("did you mean?")
+    pub suggestions: Vec<String>,
+}
+
+/// A source location the receiver type flowed through
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeOrigin {
+    pub location: SourceLocation,
+    /// The type was created here (e.g., a literal), not just passed along
+    pub is_source: bool,
 }
 
 impl TypeError {
@@ -18,6 +32,27 @@ impl TypeError {
             receiver_type,
             method_name,
             location,
+            receiver_location: None,
+            origins: Vec::new(),
+            suggestions: Vec::new(),
         }
     }
+
+    /// Attach the location of the receiver expression
+    pub fn with_receiver_location(mut self, receiver_location: Option<SourceLocation>) -> Self {
+        self.receiver_location = receiver_location;
+        self
+    }
+
+    /// Attach where the receiver type came from
+    pub fn with_origins(mut self, origins: Vec<TypeOrigin>) -> Self {
+        self.origins = origins;
+        self
+    }
+
+    /// Attach similarly spelled method names
+    pub fn with_suggestions(mut self, suggestions: Vec<String>) -> Self {
+        self.suggestions = suggestions;
+        self
+    }
 }
@@ -3,8 +3,9 @@
 //! Handles creation, storage, and type propagation for vertices and sources.
 
 use crate::graph::{Source, Vertex, VertexId};
+use crate::source_map::SourceLocation;
 use crate::types::Type;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 
 /// Manages vertices and sources in the type graph
 #[derive(Debug, Default)]
@@ -13,6 +14,8 @@ pub struct VertexManager {
     pub vertices: HashMap<VertexId, Vertex>,
     /// All sources (fixed-type nodes) in the graph
     pub sources: HashMap<VertexId, Source>,
+    /// Expression that first produced each vertex or source
+    locations: HashMap<VertexId, SourceLocation>,
     /// Next vertex ID to allocate
     next_vertex_id: usize,
 }
@@ -24,6 +27,7 @@ impl VertexManager {
         Self {
             vertices: HashMap::new(),
             sources: HashMap::new(),
+            locations: HashMap::new(),
             next_vertex_id: 0,
         }
     }
@@ -59,6 +63,46 @@ impl VertexManager {
         self.sources.get(&id)
     }
 
+    /// Record the expression of a vertex (the first recorded one is kept)
+    pub fn set_location(&mut self, id: VertexId, location: SourceLocation) {
+        self.locations.entry(id).or_insert(location);
+    }
+
+    /// Get the expression of a vertex
+    pub fn location(&self, id: VertexId) -> Option<&SourceLocation> {
+        self.locations.get(&id)
+    }
+
+    /// Vertices and sources a type reached `id` through, nearest first
+    ///
+    /// Follows the sources recorded in `Vertex::types` backwards; `id`
+    /// itself is not included.
+    pub fn type_provenance(&self, id: VertexId, ty: &Type) -> Vec<VertexId> {
+        let mut visited = HashSet::from([id]);
+        let mut queue = VecDeque::from([id]);
+        let mut provenance = Vec::new();
+
+        while let Some(current) = queue.pop_front() {
+            let Some(sources) = self
+                .vertices
+                .get(&current)
+                .and_then(|vtx| vtx.types.get(ty))
+            else {
+                continue;
+            };
+            let mut sources: Vec<VertexId> = sources.iter().copied().collect();
+            sources.sort_by_key(|source| source.0);
+            for source in sources {
+                if visited.insert(source) {
+                    provenance.push(source);
+                    queue.push_back(source);
+                }
+            }
+        }
+
+        provenance
+    }
+
     /// Add an edge between two vertices and propagate types
     pub fn add_edge(&mut self, src: VertexId, dst: VertexId) {
         // Add edge from src to dst
@@ -192,4 +236,39 @@ mod tests {
             "(Integer | String)"
         );
     }
+
+    #[test]
+    fn test_type_provenance() {
+        let mut manager = VertexManager::new();
+
+        let string_src = manager.new_source(Type::string());
+        let integer_src = manager.new_source(Type::integer());
+        let var = manager.new_vertex();
+        let recv = manager.new_vertex();
+
+        manager.add_edge(string_src, var);
+        manager.add_edge(integer_src, var);
+        manager.add_edge(var, recv);
+
+        // Only the sources of the asked type are followed
+        assert_eq!(
+            manager.type_provenance(recv, &Type::integer()),
+            vec![var, integer_src]
+        );
+        assert!(manager.type_provenance(recv, &Type::symbol()).is_empty());
+    }
+
+    #[test]
+    fn test_first_location_is_kept() {
+        let mut manager = VertexManager::new();
+        let vtx = manager.new_vertex();
+
+        manager.set_location(vtx, SourceLocation::new(1, 1, 1, 6));
+        manager.set_location(vtx, SourceLocation::new(3, 1, 3, 2));
+
+        assert_eq!(
+            manager.location(vtx),
+            Some(&SourceLocation::new(1, 1, 1, 6))
+        );
+    }
 }
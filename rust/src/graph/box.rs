@@ -1,3 +1,4 @@
+use crate::env::method_registry::OpenClassPolicy;
 use crate::env::GlobalEnv;
 use crate::graph::change_set::ChangeSet;
 use crate::graph::vertex::VertexId;
@@ -16,14 +17,130 @@ pub trait BoxTrait: Send + Sync {
     fn ret(&self) -> VertexId;
 }
 
+/// Check if a type is a type variable name (e.g., Elem, K, V)
+fn is_type_variable_name(name: &str) -> bool {
+    matches!(
+        name,
+        "Elem" | "K" | "V" | "T" | "U" | "A" | "B" | "Element" | "Key" | "Value" | "Out" | "In"
+    )
+}
+
+/// Try to resolve a type variable from receiver's type arguments.
+///
+/// For `Array[Integer]#each { |x| }`, the block param type is `Elem`.
+/// This resolves `Elem` → `Integer` using Array's type argument.
+///
+/// Type variable mapping for common generic classes:
+/// - Array[Elem]: Elem → type_args[0]
+/// - Hash[K, V]: K → type_args[0], V → type_args[1]
+fn resolve_type_variable(ty: &Type, recv_ty: &Type) -> Option<Type> {
+    let type_var_name = match ty {
+        Type::Instance { name } if is_type_variable_name(name.full_name()) => name.full_name(),
+        _ => return None, // Not a type variable
+    };
+
+    // Get type arguments from receiver
+    let type_args = recv_ty.type_args()?;
+    let class_name = recv_ty.base_class_name()?;
+
+    // Map type variable to type argument index based on class
+    let index = match (class_name, type_var_name) {
+        // Array[Elem]
+        ("Array", "Elem") => 0,
+        ("Array", "T") => 0,
+        ("Array", "Element") => 0,
+        // Hash[K, V]
+        ("Hash", "K") | ("Hash", "Key") => 0,
+        ("Hash", "V") | ("Hash", "Value") => 1,
+        // Generic fallback: first type arg for common names
+        (_, "Elem") | (_, "T") | (_, "Element") => 0,
+        _ => return None,
+    };
+
+    type_args.get(index).cloned()
+}
+
+/// Substitute type variables in a method's return type using the receiver's type arguments
+///
+/// Returns None if the type is an unresolvable type variable, so callers can
+/// leave the result untyped instead of propagating a bogus `Elem` instance type.
+/// Unresolvable members of a union are dropped.
+pub fn substitute_type_variables(ty: &Type, recv_ty: &Type) -> Option<Type> {
+    match ty {
+        Type::Instance { name } if is_type_variable_name(name.full_name()) => {
+            resolve_type_variable(ty, recv_ty)
+        }
+        Type::Union(types) => {
+            let resolved: Vec<Type> = types
+                .iter()
+                .filter_map(|t| substitute_type_variables(t, recv_ty))
+                .collect();
+            match resolved.len() {
+                0 => None,
+                1 => resolved.into_iter().next(),
+                _ => Some(Type::Union(resolved)),
+            }
+        }
+        Type::Generic { name, type_args } => {
+            let type_args = type_args
+                .iter()
+                .map(|t| substitute_type_variables(t, recv_ty).unwrap_or(Type::Bot))
+                .collect();
+            Some(Type::Generic {
+                name: name.clone(),
+                type_args,
+            })
+        }
+        Type::Tuple(types) => Some(Type::Tuple(
+            types
+                .iter()
+                .map(|t| substitute_type_variables(t, recv_ty).unwrap_or(Type::Bot))
+                .collect(),
+        )),
+        _ => Some(ty.clone()),
+    }
+}
+
+/// Split an array-like type into the types of `count` destructuring targets
+///
+/// - `[String, Integer]` gives each target its own element type (missing ones are nil)
+/// - `Array[Integer]` gives every target the element type
+///
+/// Returns None for other types, whose element types are unknown.
+fn split_element_types(ty: &Type, count: usize) -> Option<Vec<Type>> {
+    match ty {
+        Type::Tuple(types) => Some(
+            (0..count)
+                .map(|i| types.get(i).cloned().unwrap_or(Type::Nil))
+                .collect(),
+        ),
+        Type::Generic { name, type_args } if name.full_name() == "Array" => {
+            let element_type = type_args.first()?;
+            Some(vec![element_type.clone(); count])
+        }
+        _ => None,
+    }
+}
+
 /// Box representing a method call
 #[allow(dead_code)]
 pub struct MethodCallBox {
     id: BoxId,
     recv: VertexId,
     method_name: String,
+    /// Argument vertices (in order)
+    args: Vec<VertexId>,
+    /// Block passed to the call (a Proc vertex), if any
+    block: Option<VertexId>,
     ret: VertexId,
     location: Option<SourceLocation>, // Source code location
+    /// Location of the receiver expression, for labelling diagnostics
+    receiver_location: Option<SourceLocation>,
+    /// Receiver-less call on implicit self (e.g., `puts x`, `each_item { }`)
+    ///
+    /// Kernel methods, private helpers and DSL calls are not known to the
+    /// registry, so unresolved implicit-self calls are not reported.
+    implicit_self: bool,
     /// Number of times this box has been rescheduled
     reschedule_count: u8,
 }
@@ -36,6 +153,8 @@ impl MethodCallBox {
         id: BoxId,
         recv: VertexId,
         method_name: String,
+        args: Vec<VertexId>,
+        block: Option<VertexId>,
         ret: VertexId,
         location: Option<SourceLocation>,
     ) -> Self {
@@ -43,11 +162,41 @@ impl MethodCallBox {
             id,
             recv,
             method_name,
+            args,
+            block,
             ret,
             location,
+            receiver_location: None,
+            implicit_self: false,
             reschedule_count: 0,
         }
     }
+
+    /// Set the location of the receiver expression
+    pub fn with_receiver_location(mut self, receiver_location: Option<SourceLocation>) -> Self {
+        self.receiver_location = receiver_location;
+        self
+    }
+
+    /// Mark this call as a receiver-less call on implicit self
+    pub fn with_implicit_self(mut self) -> Self {
+        self.implicit_self = true;
+        self
+    }
+
+    /// Invoke a Proc receiver: connect arguments to its parameters and its
+    /// return vertex to the call's return value
+    fn run_proc_call(&self, params: &[VertexId], proc_ret: VertexId, changes: &mut ChangeSet) {
+        for (arg, param) in self.args.iter().zip(params) {
+            changes.add_edge(*arg, *param);
+        }
+        changes.add_edge(proc_ret, self.ret);
+    }
+}
+
+/// Check if a method invokes a Proc (`blk.call(x)`, `blk.(x)`, `blk[x]`, `blk.yield(x)`)
+fn is_proc_call_method(method_name: &str) -> bool {
+    matches!(method_name, "call" | "[]" | "yield" | "===")
 }
 
 impl BoxTrait for MethodCallBox {
@@ -84,30 +233,91 @@ impl BoxTrait for MethodCallBox {
         }
 
         for recv_ty in recv_types {
+            // Calling a lambda/proc/block flows arguments into its parameters
+            if let Type::Proc { params, ret } = &recv_ty {
+                if is_proc_call_method(&self.method_name) {
+                    self.run_proc_call(params, *ret, changes);
+                    continue;
+                }
+            }
+
             // Resolve method
             if let Some(method_info) = genv.resolve_method(&recv_ty, &self.method_name) {
+                // Pass the block to a user-defined method (read by `yield` and `&block`)
+                if let (Some(block_vtx), Some(method_block_vtx)) =
+                    (self.block, method_info.block_vertex)
+                {
+                    changes.add_edge(block_vtx, method_block_vtx);
+                }
+
+                // User-defined methods return the values flowing into their return vertex
+                if method_info.return_type == Type::Bot {
+                    if let Some(method_return_vtx) = method_info.return_vertex {
+                        changes.add_edge(method_return_vtx, self.ret);
+                        continue;
+                    }
+                }
+
+                // Resolve type variables in the return type (e.g., Array[Integer]#[] -> Elem -> Integer)
+                let Some(return_type) = substitute_type_variables(&method_info.return_type, &recv_ty)
+                else {
+                    // Type variable couldn't be resolved, leave return value untyped
+                    continue;
+                };
+
+                // Bot carries no type information (e.g., user-defined methods)
+                if return_type == Type::Bot {
+                    continue;
+                }
+
                 // Create return type as Source
-                let ret_src_id = genv.new_source(method_info.return_type.clone());
+                let ret_src_id = genv.new_source(return_type);
 
                 // Add edge to return value
                 changes.add_edge(ret_src_id, self.ret);
+            } else if self.implicit_self {
+                // Unknown receiver-less call (Kernel method, DSL, etc.), not reported
+                continue;
+            } else if let Some(policy) = genv.open_class_policy(&recv_ty) {
+                // Answered by method_missing on an open class
+                if policy == OpenClassPolicy::Info {
+                    genv.record_dynamic_call(
+                        recv_ty.clone(),
+                        self.method_name.clone(),
+                        self.location.clone(),
+                    );
+                }
             } else {
                 // Record type error for diagnostic reporting
                 genv.record_type_error(
+                    self.recv,
                     recv_ty.clone(),
                     self.method_name.clone(),
                     self.location.clone(),
+                    self.receiver_location.clone(),
                 );
             }
         }
     }
 }
 
+/// Destructured block parameter: `|(k, v)|` splits the parameter's value into targets
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DestructuredParameter {
+    /// Vertex of the whole parameter value
+    pub vtx: VertexId,
+    /// Target vertices in order (a nested pattern is a target with its own entry)
+    pub targets: Vec<VertexId>,
+}
+
 /// Box for resolving block parameter types from method call receiver
 ///
 /// When a method with a block is called (e.g., `str.each_char { |c| ... }`),
 /// this box resolves the block parameter types from the method's RBS definition
 /// and propagates them to the block parameter vertices.
+///
+/// Tuple and array types are split into destructured parameters (`|(k, v), i|`),
+/// and a single yielded tuple is auto-splatted across multiple parameters (`|k, v|`).
 #[allow(dead_code)]
 pub struct BlockParameterTypeBox {
     id: BoxId,
@@ -117,6 +327,8 @@ pub struct BlockParameterTypeBox {
     method_name: String,
     /// Block parameter vertices (in order)
     block_param_vtxs: Vec<VertexId>,
+    /// Destructured parameters, keyed by the vertex of the whole value
+    destructured: Vec<DestructuredParameter>,
 }
 
 impl BlockParameterTypeBox {
@@ -131,52 +343,28 @@ impl BlockParameterTypeBox {
             recv_vtx,
             method_name,
             block_param_vtxs,
+            destructured: Vec::new(),
         }
     }
 
-    /// Check if a type is a type variable name (e.g., Elem, K, V)
-    fn is_type_variable_name(name: &str) -> bool {
-        matches!(
-            name,
-            "Elem" | "K" | "V" | "T" | "U" | "A" | "B" | "Element" | "Key" | "Value" | "Out" | "In"
-        )
+    /// Set the destructured parameters of the block
+    pub fn with_destructured(mut self, destructured: Vec<DestructuredParameter>) -> Self {
+        self.destructured = destructured;
+        self
     }
 
-    /// Try to resolve a type variable from receiver's type arguments.
-    ///
-    /// For `Array[Integer]#each { |x| }`, the block param type is `Elem`.
-    /// This resolves `Elem` → `Integer` using Array's type argument.
-    ///
-    /// Type variable mapping for common generic classes:
-    /// - Array[Elem]: Elem → type_args[0]
-    /// - Hash[K, V]: K → type_args[0], V → type_args[1]
-    fn resolve_type_variable(ty: &Type, recv_ty: &Type) -> Option<Type> {
-        let type_var_name = match ty {
-            Type::Instance { name } if Self::is_type_variable_name(name.full_name()) => {
-                name.full_name()
+    /// Propagate a type to a block parameter and split it into destructuring targets
+    fn assign(&self, genv: &mut GlobalEnv, changes: &mut ChangeSet, vtx: VertexId, ty: Type) {
+        if let Some(pattern) = self.destructured.iter().find(|d| d.vtx == vtx) {
+            if let Some(element_types) = split_element_types(&ty, pattern.targets.len()) {
+                for (target, element_type) in pattern.targets.iter().zip(element_types) {
+                    self.assign(genv, changes, *target, element_type);
+                }
             }
-            _ => return None, // Not a type variable
-        };
-
-        // Get type arguments from receiver
-        let type_args = recv_ty.type_args()?;
-        let class_name = recv_ty.base_class_name()?;
-
-        // Map type variable to type argument index based on class
-        let index = match (class_name, type_var_name) {
-            // Array[Elem]
-            ("Array", "Elem") => 0,
-            ("Array", "T") => 0,
-            ("Array", "Element") => 0,
-            // Hash[K, V]
-            ("Hash", "K") | ("Hash", "Key") => 0,
-            ("Hash", "V") | ("Hash", "Value") => 1,
-            // Generic fallback: first type arg for common names
-            (_, "Elem") | (_, "T") | (_, "Element") => 0,
-            _ => return None,
-        };
+        }
 
-        type_args.get(index).cloned()
+        let src_id = genv.new_source(ty);
+        changes.add_edge(src_id, vtx);
     }
 }
 
@@ -212,34 +400,286 @@ impl BoxTrait for BlockParameterTypeBox {
                 .and_then(|info| info.block_param_types.clone());
 
             if let Some(param_types) = block_param_types {
+                // Resolve type variables from receiver's type arguments (e.g., Elem → Integer)
+                // Unresolvable type variables are skipped
+                let resolved_types: Vec<Option<Type>> = param_types
+                    .iter()
+                    .map(|param_type| substitute_type_variables(param_type, &recv_ty))
+                    .collect();
+
+                // A single yielded tuple is splatted across multiple parameters: `|k, v|`
+                let splatted = match resolved_types.as_slice() {
+                    [Some(yielded)] if self.block_param_vtxs.len() > 1 => {
+                        split_element_types(yielded, self.block_param_vtxs.len())
+                    }
+                    _ => None,
+                };
+                if let Some(element_types) = splatted {
+                    for (param_vtx, element_type) in self.block_param_vtxs.iter().zip(element_types)
+                    {
+                        self.assign(genv, changes, *param_vtx, element_type);
+                    }
+                    continue;
+                }
+
                 // Map block parameter types to vertices
-                for (i, param_type) in param_types.iter().enumerate() {
-                    if i < self.block_param_vtxs.len() {
-                        let param_vtx = self.block_param_vtxs[i];
-
-                        // Try to resolve type variable from receiver's type arguments
-                        let resolved_type =
-                            if let Some(resolved) = Self::resolve_type_variable(param_type, &recv_ty) {
-                                // Type variable resolved (e.g., Elem → Integer)
-                                resolved
-                            } else if let Type::Instance { name } = &param_type {
-                                if Self::is_type_variable_name(name.full_name()) {
-                                    // Type variable couldn't be resolved, skip
-                                    continue;
-                                } else {
-                                    // Regular type, use as-is
-                                    param_type.clone()
-                                }
-                            } else {
-                                // Other type (Union, Generic, etc.), use as-is
-                                param_type.clone()
-                            };
-
-                        // Create source with the resolved type
-                        let src_id = genv.new_source(resolved_type);
-                        changes.add_edge(src_id, param_vtx);
+                for (param_vtx, resolved_type) in self.block_param_vtxs.iter().zip(resolved_types) {
+                    if let Some(resolved_type) = resolved_type {
+                        self.assign(genv, changes, *param_vtx, resolved_type);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Get the types of a vertex or source
+fn vertex_types(genv: &GlobalEnv, vtx: VertexId) -> Vec<Type> {
+    if let Some(vertex) = genv.get_vertex(vtx) {
+        vertex.types.keys().cloned().collect()
+    } else if let Some(source) = genv.get_source(vtx) {
+        vec![source.ty.clone()]
+    } else {
+        vec![]
+    }
+}
+
+/// Type test used to narrow a value
+///
+/// - `in String`, `String => s` (pattern matching)
+/// - `x.is_a?(String)`, `x.kind_of?(String)`, `String === x`, `when String`
+/// - `x.instance_of?(String)`, `x.nil?`, `x.respond_to?(:name)`, `if x`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TypeFilter {
+    /// Instances of a class or its subclasses
+    Class(String),
+    /// Instances of exactly this class
+    InstanceOf(String),
+    /// nil
+    Nil,
+    /// Anything but nil and false
+    Truthy,
+    /// Values with the method
+    RespondTo(String),
+    /// Values passing any of the tests (`when String, Symbol`)
+    AnyOf(Vec<TypeFilter>),
+    /// Values failing the test (the else branch)
+    Not(Box<TypeFilter>),
+}
+
+impl TypeFilter {
+    /// Check if a type passes the test
+    ///
+    /// A class matches its subclasses and the classes including it.
+    pub fn matches(&self, genv: &GlobalEnv, ty: &Type) -> bool {
+        match self {
+            TypeFilter::Class(class_name) => {
+                class_name_of(ty).is_some_and(|ty_class| genv.is_kind_of(ty_class, class_name))
+            }
+            TypeFilter::InstanceOf(class_name) => class_name_of(ty) == Some(class_name.as_str()),
+            TypeFilter::Nil => *ty == Type::Nil,
+            TypeFilter::Truthy => !matches!(class_name_of(ty), Some("NilClass" | "FalseClass")),
+            TypeFilter::RespondTo(method_name) => genv.resolve_method(ty, method_name).is_some(),
+            TypeFilter::AnyOf(filters) => filters.iter().any(|filter| filter.matches(genv, ty)),
+            TypeFilter::Not(filter) => !filter.matches(genv, ty),
+        }
+    }
+
+    /// Types of an untyped value known to pass the test
+    ///
+    /// e.g., an untyped value matching `in String` is a String.
+    fn fallback_types(&self) -> Vec<Type> {
+        match self {
+            TypeFilter::Class(class_name) | TypeFilter::InstanceOf(class_name) => {
+                vec![Type::instance(class_name)]
+            }
+            TypeFilter::Nil => vec![Type::Nil],
+            TypeFilter::AnyOf(filters) => filters.iter().flat_map(|f| f.fallback_types()).collect(),
+            TypeFilter::Truthy | TypeFilter::RespondTo(_) | TypeFilter::Not(_) => vec![],
+        }
+    }
+}
+
+/// Get the class of a type's instances
+fn class_name_of(ty: &Type) -> Option<&str> {
+    match ty {
+        Type::Nil => Some("NilClass"),
+        Type::Tuple(_) => Some("Array"),
+        Type::Proc { .. } => Some("Proc"),
+        Type::Singleton { .. } => Some("Class"),
+        _ => ty.base_class_name(),
+    }
+}
+
+/// Box narrowing a value to the types passing a test
+///
+/// The narrowed vertex gets each type of the source that passes the filter.
+/// An untyped source gets the type the test implies.
+#[allow(dead_code)]
+pub struct NarrowingBox {
+    id: BoxId,
+    /// Vertex of the value being tested
+    src: VertexId,
+    /// Vertex of the narrowed value
+    dst: VertexId,
+    filter: TypeFilter,
+    /// Number of times this box has been rescheduled
+    reschedule_count: u8,
+}
+
+impl NarrowingBox {
+    pub fn new(id: BoxId, src: VertexId, dst: VertexId, filter: TypeFilter) -> Self {
+        Self {
+            id,
+            src,
+            dst,
+            filter,
+            reschedule_count: 0,
+        }
+    }
+}
+
+impl BoxTrait for NarrowingBox {
+    fn id(&self) -> BoxId {
+        self.id
+    }
+
+    fn ret(&self) -> VertexId {
+        self.dst
+    }
+
+    fn run(&mut self, genv: &mut GlobalEnv, changes: &mut ChangeSet) {
+        let types = vertex_types(genv, self.src);
+
+        // The value may be typed by boxes that have not run yet
+        if types.is_empty() && self.reschedule_count < MAX_RESCHEDULE_COUNT {
+            self.reschedule_count += 1;
+            changes.reschedule(self.id);
+            return;
+        }
+
+        let narrowed: Vec<Type> = if types.is_empty() {
+            self.filter.fallback_types()
+        } else {
+            types
+                .into_iter()
+                .filter(|ty| self.filter.matches(genv, ty))
+                .collect()
+        };
+
+        for ty in narrowed {
+            let src_id = genv.new_source(ty);
+            changes.add_edge(src_id, self.dst);
+        }
+    }
+}
+
+/// Part of a value extracted by a deconstructing pattern
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ElementAccess {
+    /// Element at a fixed position: `in [a, b]`
+    Index(usize),
+    /// Any element: `in [*, Integer => x, *]`
+    Any,
+    /// Remaining elements as an Array: `in [first, *rest]`
+    Rest,
+    /// Value of a Hash key: `in {name: String => n}`
+    HashValue,
+}
+
+/// Box extracting element types from an Array or Hash value
+///
+/// - `[String, Integer]` gives the element at each position
+/// - `Array[Integer]` gives Integer for every element
+/// - `Hash[Symbol, String]` gives String for every key
+///
+/// Other types have unknown elements, which stay untyped.
+#[allow(dead_code)]
+pub struct ElementTypeBox {
+    id: BoxId,
+    /// Vertex of the deconstructed value
+    src: VertexId,
+    /// Vertex of the extracted element
+    dst: VertexId,
+    access: ElementAccess,
+    /// Number of times this box has been rescheduled
+    reschedule_count: u8,
+}
+
+impl ElementTypeBox {
+    pub fn new(id: BoxId, src: VertexId, dst: VertexId, access: ElementAccess) -> Self {
+        Self {
+            id,
+            src,
+            dst,
+            access,
+            reschedule_count: 0,
+        }
+    }
+
+    /// Get the types of the accessed element of a value
+    fn element_types(&self, ty: &Type) -> Vec<Type> {
+        match (ty, &self.access) {
+            (Type::Tuple(types), ElementAccess::Index(i)) => {
+                types.get(*i).cloned().into_iter().collect()
+            }
+            (Type::Tuple(types), ElementAccess::Any) => types.clone(),
+            (Type::Tuple(types), ElementAccess::Rest) => {
+                let mut element_types: Vec<Type> = Vec::new();
+                for element_type in types {
+                    if !element_types.contains(element_type) {
+                        element_types.push(element_type.clone());
                     }
                 }
+                match element_types.len() {
+                    0 => vec![Type::array()],
+                    1 => vec![Type::array_of(element_types.remove(0))],
+                    _ => vec![Type::array_of(Type::Union(element_types))],
+                }
+            }
+            (Type::Generic { name, type_args }, access) => match (name.full_name(), access) {
+                ("Array", ElementAccess::Rest) => vec![ty.clone()],
+                ("Array", ElementAccess::Index(_) | ElementAccess::Any) => {
+                    type_args.first().cloned().into_iter().collect()
+                }
+                ("Hash", ElementAccess::HashValue) => {
+                    type_args.get(1).cloned().into_iter().collect()
+                }
+                _ => vec![],
+            },
+            (Type::Instance { name }, ElementAccess::Rest) if name.full_name() == "Array" => {
+                vec![ty.clone()]
+            }
+            _ => vec![],
+        }
+    }
+}
+
+impl BoxTrait for ElementTypeBox {
+    fn id(&self) -> BoxId {
+        self.id
+    }
+
+    fn ret(&self) -> VertexId {
+        self.dst
+    }
+
+    fn run(&mut self, genv: &mut GlobalEnv, changes: &mut ChangeSet) {
+        let types = vertex_types(genv, self.src);
+
+        // The value may be typed by boxes that have not run yet
+        if types.is_empty() {
+            if self.reschedule_count < MAX_RESCHEDULE_COUNT {
+                self.reschedule_count += 1;
+                changes.reschedule(self.id);
+            }
+            return;
+        }
+
+        for ty in types {
+            for element_type in self.element_types(&ty) {
+                let src_id = genv.new_source(element_type);
+                changes.add_edge(src_id, self.dst);
             }
         }
     }
@@ -270,6 +710,8 @@ mod tests {
             box_id,
             x_vtx,
             "upcase".to_string(),
+            vec![],
+            None,
             ret_vtx,
             None, // No location in test
         );
@@ -301,6 +743,8 @@ mod tests {
             box_id,
             x_vtx,
             "unknown_method".to_string(),
+            vec![],
+            None,
             ret_vtx,
             None, // No location in test
         );
@@ -314,6 +758,209 @@ mod tests {
         assert_eq!(ret_vertex.show(), "untyped");
     }
 
+    #[test]
+    fn test_method_call_box_resolves_return_type_variable() {
+        let mut genv = GlobalEnv::new();
+
+        // Array#[] returns Elem
+        genv.register_builtin_method(Type::array(), "[]", Type::instance("Elem"));
+
+        // arr = [1, 2] (Array[Integer])
+        let arr_vtx = genv.new_vertex();
+        let arr_src = genv.new_source(Type::array_of(Type::integer()));
+        genv.add_edge(arr_src, arr_vtx);
+
+        // arr[0]
+        let index_src = genv.new_source(Type::integer());
+        let ret_vtx = genv.new_vertex();
+        let mut call_box = MethodCallBox::new(
+            BoxId(0),
+            arr_vtx,
+            "[]".to_string(),
+            vec![index_src],
+            None,
+            ret_vtx,
+            None,
+        );
+
+        let mut changes = ChangeSet::new();
+        call_box.run(&mut genv, &mut changes);
+        genv.apply_changes(changes);
+
+        // Elem is resolved from Array[Integer]
+        assert_eq!(genv.get_vertex(ret_vtx).unwrap().show(), "Integer");
+    }
+
+    #[test]
+    fn test_method_call_box_unresolved_return_type_variable() {
+        let mut genv = GlobalEnv::new();
+
+        genv.register_builtin_method(Type::array(), "first", Type::instance("Elem"));
+
+        // Plain Array without type arguments
+        let arr_src = genv.new_source(Type::array());
+        let ret_vtx = genv.new_vertex();
+        let mut call_box = MethodCallBox::new(
+            BoxId(0),
+            arr_src,
+            "first".to_string(),
+            vec![],
+            None,
+            ret_vtx,
+            None,
+        );
+
+        let mut changes = ChangeSet::new();
+        call_box.run(&mut genv, &mut changes);
+        genv.apply_changes(changes);
+
+        // Elem can't be resolved, so the result stays untyped
+        assert_eq!(genv.get_vertex(ret_vtx).unwrap().show(), "untyped");
+        assert!(genv.type_errors.is_empty());
+    }
+
+    #[test]
+    fn test_substitute_type_variables_in_union() {
+        let recv_ty = Type::array_of(Type::string());
+        let ret_ty = Type::Union(vec![Type::instance("Elem"), Type::Nil]);
+
+        let resolved = substitute_type_variables(&ret_ty, &recv_ty).unwrap();
+        assert_eq!(resolved.show(), "String | nil");
+    }
+
+    #[test]
+    fn test_method_call_box_proc_call() {
+        let mut genv = GlobalEnv::new();
+
+        // f = ->(x) { ... } with body returning String
+        let param_vtx = genv.new_vertex();
+        let proc_ret = genv.new_vertex();
+        let body_src = genv.new_source(Type::string());
+        genv.add_edge(body_src, proc_ret);
+        let f_src = genv.new_source(Type::proc_of(vec![param_vtx], proc_ret));
+
+        // f.call(1)
+        let arg_src = genv.new_source(Type::integer());
+        let ret_vtx = genv.new_vertex();
+        let mut call_box = MethodCallBox::new(
+            BoxId(0),
+            f_src,
+            "call".to_string(),
+            vec![arg_src],
+            None,
+            ret_vtx,
+            None,
+        );
+
+        let mut changes = ChangeSet::new();
+        call_box.run(&mut genv, &mut changes);
+        genv.apply_changes(changes);
+
+        assert_eq!(genv.type_errors.len(), 0);
+        assert_eq!(genv.get_vertex(param_vtx).unwrap().show(), "Integer");
+        assert_eq!(genv.get_vertex(ret_vtx).unwrap().show(), "String");
+    }
+
+    #[test]
+    fn test_method_call_box_passes_block_to_user_method() {
+        let mut genv = GlobalEnv::new();
+
+        let method_block_vtx = genv.new_vertex();
+        let method_return_vtx = genv.new_vertex();
+        genv.register_user_method(
+            Type::instance("User"),
+            "each_item",
+            method_block_vtx,
+            method_return_vtx,
+        );
+
+        let recv_src = genv.new_source(Type::instance("User"));
+        let block_ret = genv.new_vertex();
+        let block_src = genv.new_source(Type::proc_of(vec![], block_ret));
+        let ret_vtx = genv.new_vertex();
+        let mut call_box = MethodCallBox::new(
+            BoxId(0),
+            recv_src,
+            "each_item".to_string(),
+            vec![],
+            Some(block_src),
+            ret_vtx,
+            None,
+        );
+
+        let mut changes = ChangeSet::new();
+        call_box.run(&mut genv, &mut changes);
+        genv.apply_changes(changes);
+
+        assert_eq!(genv.type_errors.len(), 0);
+        assert_eq!(genv.get_vertex(method_block_vtx).unwrap().show(), "Proc");
+        // Nothing has been returned by the user-defined method yet
+        assert_eq!(genv.get_vertex(ret_vtx).unwrap().show(), "untyped");
+    }
+
+    #[test]
+    fn test_method_call_box_returns_user_method_return_values() {
+        let mut genv = GlobalEnv::new();
+
+        let method_block_vtx = genv.new_vertex();
+        let method_return_vtx = genv.new_vertex();
+        genv.register_user_method(
+            Type::instance("User"),
+            "name",
+            method_block_vtx,
+            method_return_vtx,
+        );
+
+        let recv_src = genv.new_source(Type::instance("User"));
+        let ret_vtx = genv.new_vertex();
+        let mut call_box = MethodCallBox::new(
+            BoxId(0),
+            recv_src,
+            "name".to_string(),
+            vec![],
+            None,
+            ret_vtx,
+            None,
+        );
+
+        let mut changes = ChangeSet::new();
+        call_box.run(&mut genv, &mut changes);
+        genv.apply_changes(changes);
+
+        // Values returned later still reach the call site
+        let str_src = genv.new_source(Type::string());
+        let mut changes = ChangeSet::new();
+        changes.add_edge(str_src, method_return_vtx);
+        genv.apply_changes(changes);
+
+        assert_eq!(genv.get_vertex(ret_vtx).unwrap().show(), "String");
+    }
+
+    #[test]
+    fn test_method_call_box_implicit_self_not_reported() {
+        let mut genv = GlobalEnv::new();
+
+        // puts "x" (receiver-less, unknown to the registry)
+        let self_src = genv.new_source(Type::instance("Object"));
+        let ret_vtx = genv.new_vertex();
+        let mut call_box = MethodCallBox::new(
+            BoxId(0),
+            self_src,
+            "puts".to_string(),
+            vec![],
+            None,
+            ret_vtx,
+            None,
+        )
+        .with_implicit_self();
+
+        let mut changes = ChangeSet::new();
+        call_box.run(&mut genv, &mut changes);
+        genv.apply_changes(changes);
+
+        assert_eq!(genv.type_errors.len(), 0);
+    }
+
     #[test]
     fn test_block_param_type_box_simple() {
         let mut genv = GlobalEnv::new();
@@ -488,4 +1135,175 @@ mod tests {
         assert_eq!(genv.get_vertex(key_vtx).unwrap().show(), "String");
         assert_eq!(genv.get_vertex(value_vtx).unwrap().show(), "Integer");
     }
+
+    #[test]
+    fn test_destructured_block_param_tuple_split() {
+        let mut genv = GlobalEnv::new();
+
+        // Hash#each_with_index { |(k, v), i| }
+        genv.register_builtin_method_with_block(
+            Type::hash(),
+            "each_with_index",
+            Type::hash(),
+            Some(vec![
+                Type::tuple(vec![Type::instance("K"), Type::instance("V")]),
+                Type::integer(),
+            ]),
+        );
+
+        let recv_vtx = genv.new_vertex();
+        let hash_src = genv.new_source(Type::hash_of(Type::string(), Type::integer()));
+        genv.add_edge(hash_src, recv_vtx);
+
+        let pair_vtx = genv.new_vertex();
+        let key_vtx = genv.new_vertex();
+        let value_vtx = genv.new_vertex();
+        let index_vtx = genv.new_vertex();
+
+        let box_id = genv.alloc_box_id();
+        let block_box = BlockParameterTypeBox::new(
+            box_id,
+            recv_vtx,
+            "each_with_index".to_string(),
+            vec![pair_vtx, index_vtx],
+        )
+        .with_destructured(vec![DestructuredParameter {
+            vtx: pair_vtx,
+            targets: vec![key_vtx, value_vtx],
+        }]);
+        genv.register_box(box_id, Box::new(block_box));
+
+        genv.run_all();
+
+        assert_eq!(
+            genv.get_vertex(pair_vtx).unwrap().show(),
+            "[String, Integer]"
+        );
+        assert_eq!(genv.get_vertex(key_vtx).unwrap().show(), "String");
+        assert_eq!(genv.get_vertex(value_vtx).unwrap().show(), "Integer");
+        assert_eq!(genv.get_vertex(index_vtx).unwrap().show(), "Integer");
+    }
+
+    #[test]
+    fn test_block_params_auto_splat_tuple() {
+        let mut genv = GlobalEnv::new();
+
+        // Hash#each_pair yields one [K, V] tuple, splatted into |k, v|
+        genv.register_builtin_method_with_block(
+            Type::hash(),
+            "each_pair",
+            Type::hash(),
+            Some(vec![Type::tuple(vec![
+                Type::instance("K"),
+                Type::instance("V"),
+            ])]),
+        );
+
+        let recv_vtx = genv.new_vertex();
+        let hash_src = genv.new_source(Type::hash_of(Type::symbol(), Type::string()));
+        genv.add_edge(hash_src, recv_vtx);
+
+        let key_vtx = genv.new_vertex();
+        let value_vtx = genv.new_vertex();
+
+        let box_id = genv.alloc_box_id();
+        let block_box = BlockParameterTypeBox::new(
+            box_id,
+            recv_vtx,
+            "each_pair".to_string(),
+            vec![key_vtx, value_vtx],
+        );
+        genv.register_box(box_id, Box::new(block_box));
+
+        genv.run_all();
+
+        assert_eq!(genv.get_vertex(key_vtx).unwrap().show(), "Symbol");
+        assert_eq!(genv.get_vertex(value_vtx).unwrap().show(), "String");
+    }
+
+    #[test]
+    fn test_type_filter_class_matches() {
+        let genv = GlobalEnv::new();
+
+        let filter = TypeFilter::Class("Array".to_string());
+        assert!(filter.matches(&genv, &Type::array_of(Type::integer())));
+        assert!(filter.matches(&genv, &Type::tuple(vec![Type::string()])));
+        assert!(!filter.matches(&genv, &Type::string()));
+
+        let numeric = TypeFilter::Class("Numeric".to_string());
+        assert!(numeric.matches(&genv, &Type::float()));
+        assert!(!numeric.matches(&genv, &Type::Nil));
+        assert!(!TypeFilter::InstanceOf("Numeric".to_string()).matches(&genv, &Type::float()));
+
+        let comparable = TypeFilter::Class("Comparable".to_string());
+        assert!(comparable.matches(&genv, &Type::string()));
+        assert!(comparable.matches(&genv, &Type::integer()));
+        assert!(!comparable.matches(&genv, &Type::array()));
+    }
+
+    #[test]
+    fn test_type_filter_class_matches_subclasses() {
+        let mut genv = GlobalEnv::new();
+        genv.enter_class("Base".to_string(), None);
+        genv.exit_scope();
+        genv.enter_class("User".to_string(), Some("Base".to_string()));
+        genv.exit_scope();
+
+        let base = TypeFilter::Class("Base".to_string());
+        assert!(base.matches(&genv, &Type::instance("User")));
+        assert!(base.matches(&genv, &Type::instance("Base")));
+        assert!(!TypeFilter::Class("User".to_string()).matches(&genv, &Type::instance("Base")));
+        assert!(!TypeFilter::InstanceOf("Base".to_string()).matches(&genv, &Type::instance("User")));
+    }
+
+    #[test]
+    fn test_type_filter_nil_truthy_and_respond_to() {
+        let mut genv = GlobalEnv::new();
+        genv.register_builtin_method(Type::string(), "upcase", Type::string());
+
+        assert!(TypeFilter::Nil.matches(&genv, &Type::Nil));
+        assert!(!TypeFilter::Truthy.matches(&genv, &Type::Nil));
+        assert!(!TypeFilter::Truthy.matches(&genv, &Type::instance("FalseClass")));
+        assert!(TypeFilter::Truthy.matches(&genv, &Type::integer()));
+
+        let responds = TypeFilter::RespondTo("upcase".to_string());
+        assert!(responds.matches(&genv, &Type::string()));
+        assert!(TypeFilter::Not(Box::new(responds)).matches(&genv, &Type::integer()));
+    }
+
+    #[test]
+    fn test_narrowing_box_untyped_value() {
+        let mut genv = GlobalEnv::new();
+
+        let value_vtx = genv.new_vertex();
+        let narrowed_vtx = genv.new_vertex();
+        let box_id = genv.alloc_box_id();
+        let narrowing_box = NarrowingBox::new(
+            box_id,
+            value_vtx,
+            narrowed_vtx,
+            TypeFilter::Class("String".to_string()),
+        );
+        genv.register_box(box_id, Box::new(narrowing_box));
+        genv.run_all();
+
+        // An untyped value that passes the test is a String
+        assert_eq!(genv.get_vertex(narrowed_vtx).unwrap().show(), "String");
+    }
+
+    #[test]
+    fn test_element_type_box_hash_value() {
+        let mut genv = GlobalEnv::new();
+
+        let hash_src = genv.new_source(Type::hash_of(Type::symbol(), Type::string()));
+        let value_vtx = genv.new_vertex();
+        let mut element_box =
+            ElementTypeBox::new(BoxId(0), hash_src, value_vtx, ElementAccess::HashValue);
+
+        let mut changes = ChangeSet::new();
+        element_box.run(&mut genv, &mut changes);
+        genv.apply_changes(changes);
+
+        assert_eq!(genv.get_vertex(value_vtx).unwrap().show(), "String");
+    }
 }
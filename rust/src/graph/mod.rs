@@ -3,5 +3,9 @@ pub mod change_set;
 pub mod vertex;
 
 pub use change_set::{ChangeSet, EdgeUpdate};
-pub use r#box::{BlockParameterTypeBox, BoxId, BoxTrait, MethodCallBox};
+pub use r#box::{
+    BlockParameterTypeBox, BoxId, BoxTrait, DestructuredParameter, ElementAccess, ElementTypeBox,
+    MethodCallBox, NarrowingBox, TypeFilter,
+};
+pub(crate) use r#box::substitute_type_variables;
 pub use vertex::{Source, Vertex, VertexId};
@@ -4,6 +4,7 @@
 
 pub mod analyzer;
 pub mod cache;
+pub mod config;
 pub mod diagnostics;
 pub mod env;
 pub mod graph;
@@ -1,75 +1,200 @@
-use crate::diagnostics::{Diagnostic as MethodRayDiagnostic, DiagnosticLevel};
-use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, Position, Range};
+use crate::diagnostics::{Diagnostic as MethodRayDiagnostic, DiagnosticLevel, Location};
+use crate::source_map::{LineIndex, PositionEncoding};
+use serde_json::json;
+use std::collections::HashMap;
+use tower_lsp::lsp_types::{
+    self, CodeAction, CodeActionKind, CodeActionOrCommand, Diagnostic,
+    DiagnosticRelatedInformation, DiagnosticSeverity, NumberOrString, Position,
+    PositionEncodingKind, Range, TextEdit, Url, WorkspaceEdit,
+};
 
-/// Extract method name length from error message
+const SOURCE: &str = "methodray";
+
+/// Extract method name length (in characters) from error message
 /// Supports messages like:
 /// - "undefined method `downcase` for Integer"
 /// - "method `upcase` is defined for ..."
-fn extract_method_name_length(message: &str) -> Option<u32> {
+fn extract_method_name_length(message: &str) -> Option<usize> {
     // Pattern: `method_name`
     if let Some(start) = message.find('`') {
         if let Some(end) = message[start + 1..].find('`') {
             let method_name = &message[start + 1..start + 1 + end];
-            return Some(method_name.len() as u32);
+            return Some(method_name.chars().count());
         }
     }
     None
 }
 
+/// Position encoding to use, from the client's supported encodings
+///
+/// The client lists its encodings by preference; UTF-16 is the default
+/// every client supports.
+pub fn negotiate_position_encoding(
+    client_encodings: Option<&[PositionEncodingKind]>,
+) -> PositionEncoding {
+    client_encodings
+        .unwrap_or_default()
+        .iter()
+        .find_map(|kind| match kind.as_str() {
+            "utf-8" => Some(PositionEncoding::Utf8),
+            "utf-16" => Some(PositionEncoding::Utf16),
+            "utf-32" => Some(PositionEncoding::Utf32),
+            _ => None,
+        })
+        .unwrap_or(PositionEncoding::Utf16)
+}
+
+/// LSP name of a position encoding
+pub fn position_encoding_kind(encoding: PositionEncoding) -> PositionEncodingKind {
+    match encoding {
+        PositionEncoding::Utf8 => PositionEncodingKind::UTF8,
+        PositionEncoding::Utf16 => PositionEncodingKind::UTF16,
+        PositionEncoding::Utf32 => PositionEncodingKind::UTF32,
+    }
+}
+
+/// Document text and position encoding, for converting MethodRay locations
+pub struct DocumentPositions<'a> {
+    pub uri: &'a Url,
+    pub line_index: &'a LineIndex<'a>,
+    pub encoding: PositionEncoding,
+}
+
+impl DocumentPositions<'_> {
+    fn position(&self, line: usize, column: usize) -> Position {
+        Position {
+            line: line.saturating_sub(1) as u32,
+            character: self.line_index.encode_column(line, column, self.encoding) as u32,
+        }
+    }
+
+    /// Convert a MethodRay location to an LSP range
+    ///
+    /// Without a known extent, the method name in `message` is highlighted.
+    fn range(&self, location: &Location, message: &str) -> Range {
+        let (end_line, end_column) = if location.is_empty() {
+            let width = extract_method_name_length(message).unwrap_or(5);
+            (location.line, location.column + width)
+        } else {
+            (location.end_line, location.end_column)
+        };
+
+        Range {
+            start: self.position(location.line, location.column),
+            end: self.position(end_line, end_column),
+        }
+    }
+}
+
 /// Convert MethodRay Diagnostic to LSP Diagnostic
-pub fn to_lsp_diagnostic(diag: &MethodRayDiagnostic) -> Diagnostic {
+///
+/// `document` is the checked document; labels in the same file become
+/// related information in that document.
+pub fn to_lsp_diagnostic(diag: &MethodRayDiagnostic, document: &DocumentPositions) -> Diagnostic {
     let severity = match diag.level {
         DiagnosticLevel::Error => DiagnosticSeverity::ERROR,
         DiagnosticLevel::Warning => DiagnosticSeverity::WARNING,
+        DiagnosticLevel::Info => DiagnosticSeverity::INFORMATION,
     };
 
-    let start_line = if diag.location.line > 0 {
-        (diag.location.line - 1) as u32
-    } else {
-        0
-    };
-
-    let start_char = if diag.location.column > 0 {
-        (diag.location.column - 1) as u32
-    } else {
-        0
-    };
-
-    // Use actual source length if available, otherwise extract from message
-    let highlight_length = diag
-        .location
-        .length
-        .map(|len| len as u32)
-        .or_else(|| extract_method_name_length(&diag.message))
-        .unwrap_or(5);
-    let end_char = start_char + highlight_length;
+    let related_information: Vec<DiagnosticRelatedInformation> = diag
+        .labels
+        .iter()
+        .filter_map(|label| {
+            // Positions in other files cannot be encoded without their text
+            if label.location.file != diag.location.file {
+                return None;
+            }
+            Some(DiagnosticRelatedInformation {
+                location: lsp_types::Location {
+                    uri: document.uri.clone(),
+                    range: document.range(&label.location, &label.message),
+                },
+                message: label.message.clone(),
+            })
+        })
+        .collect();
 
     Diagnostic {
-        range: Range {
-            start: Position {
-                line: start_line,
-                character: start_char,
-            },
-            end: Position {
-                line: start_line,
-                character: end_char,
-            },
-        },
+        range: document.range(&diag.location, &diag.message),
         severity: Some(severity),
-        code: None,
+        code: diag.code.clone().map(NumberOrString::String),
         code_description: None,
-        source: Some("methodray".to_string()),
+        source: Some(SOURCE.to_string()),
         message: diag.message.clone(),
-        related_information: None,
+        related_information: if related_information.is_empty() {
+            None
+        } else {
+            Some(related_information)
+        },
         tags: None,
-        data: None,
+        // Read back by `suggestion_actions` for quick fixes
+        data: if diag.suggestions.is_empty() {
+            None
+        } else {
+            Some(json!({ "suggestions": diag.suggestions }))
+        },
     }
 }
 
+/// Quick fixes replacing the diagnostic's range with each suggestion
+///
+/// The suggestions come from the `data` of a diagnostic published by
+/// `to_lsp_diagnostic`; other diagnostics have no actions.
+pub fn suggestion_actions(uri: &Url, diagnostic: &Diagnostic) -> Vec<CodeActionOrCommand> {
+    if diagnostic.source.as_deref() != Some(SOURCE) {
+        return Vec::new();
+    }
+    let suggestions = diagnostic
+        .data
+        .as_ref()
+        .and_then(|data| data.get("suggestions"))
+        .and_then(|suggestions| suggestions.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|suggestion| suggestion.as_str());
+
+    suggestions
+        .enumerate()
+        .map(|(index, suggestion)| {
+            let edit = TextEdit {
+                range: diagnostic.range,
+                new_text: suggestion.to_string(),
+            };
+            CodeActionOrCommand::CodeAction(CodeAction {
+                title: format!("Replace with `{}`", suggestion),
+                kind: Some(CodeActionKind::QUICKFIX),
+                diagnostics: Some(vec![diagnostic.clone()]),
+                edit: Some(WorkspaceEdit {
+                    changes: Some(HashMap::from([(uri.clone(), vec![edit])])),
+                    ..Default::default()
+                }),
+                is_preferred: Some(index == 0),
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::diagnostics::Location;
+
+    fn document_uri() -> Url {
+        Url::parse("file:///project/test.rb").unwrap()
+    }
+
+    /// Convert a diagnostic of a document with the given text
+    fn convert(diag: &MethodRayDiagnostic, text: &str, encoding: PositionEncoding) -> Diagnostic {
+        let uri = document_uri();
+        let line_index = LineIndex::new(text);
+        let document = DocumentPositions {
+            uri: &uri,
+            line_index: &line_index,
+            encoding,
+        };
+        to_lsp_diagnostic(diag, &document)
+    }
 
     #[test]
     fn test_to_lsp_diagnostic() {
@@ -81,19 +206,26 @@ mod tests {
                 file: PathBuf::from("test.rb"),
                 line: 5,
                 column: 10,
-                length: Some(6), // "upcase".len()
+                end_line: 5,
+                end_column: 16, // after "upcase"
             },
             message: "undefined method `upcase` for Integer".to_string(),
-            code: None,
+            code: Some("E001".to_string()),
+            labels: Vec::new(),
+            suggestions: Vec::new(),
         };
 
-        let lsp_diag = to_lsp_diagnostic(&methodray_diag);
+        let lsp_diag = convert(&methodray_diag, "", PositionEncoding::Utf16);
 
         assert_eq!(lsp_diag.range.start.line, 4); // 0-indexed
         assert_eq!(lsp_diag.range.start.character, 9); // 0-indexed
         assert_eq!(lsp_diag.range.end.character, 15); // start(9) + length(6)
         assert_eq!(lsp_diag.severity, Some(DiagnosticSeverity::ERROR));
         assert_eq!(lsp_diag.message, "undefined method `upcase` for Integer");
+        assert_eq!(
+            lsp_diag.code,
+            Some(NumberOrString::String("E001".to_string()))
+        );
     }
 
     #[test]
@@ -119,15 +251,139 @@ mod tests {
                 file: PathBuf::from("test.rb"),
                 line: 2,
                 column: 5,
-                length: Some(8), // "downcase".len()
+                end_line: 2,
+                end_column: 13, // after "downcase"
             },
             message: "undefined method `downcase` for Integer".to_string(),
             code: None,
+            labels: Vec::new(),
+            suggestions: Vec::new(),
         };
 
-        let lsp_diag = to_lsp_diagnostic(&methodray_diag);
+        let lsp_diag = convert(&methodray_diag, "", PositionEncoding::Utf16);
 
         assert_eq!(lsp_diag.range.start.character, 4); // column 5 -> 0-indexed = 4
         assert_eq!(lsp_diag.range.end.character, 12); // start(4) + length(8)
     }
+
+    #[test]
+    fn test_labels_become_related_information() {
+        use std::path::PathBuf;
+
+        let location = |line, column, width| Location {
+            file: PathBuf::from("/tmp/checked.rb"),
+            line,
+            column,
+            end_line: line,
+            end_column: column + width,
+        };
+        let methodray_diag =
+            MethodRayDiagnostic::undefined_method(location(2, 7, 6), "Integer", "upcase")
+                .with_label(location(1, 5, 3), "Integer originates here".to_string());
+
+        let lsp_diag = convert(&methodray_diag, "", PositionEncoding::Utf16);
+
+        let related = lsp_diag.related_information.unwrap();
+        assert_eq!(related.len(), 1);
+        assert_eq!(related[0].location.uri, document_uri());
+        assert_eq!(related[0].location.range.start, Position::new(0, 4));
+        assert_eq!(related[0].location.range.end, Position::new(0, 7));
+        assert_eq!(related[0].message, "Integer originates here");
+    }
+
+    #[test]
+    fn test_suggestion_quick_fix() {
+        use std::path::PathBuf;
+
+        let location = Location {
+            file: PathBuf::from("test.rb"),
+            line: 1,
+            column: 6,
+            end_line: 1,
+            end_column: 11,
+        };
+        let methodray_diag = MethodRayDiagnostic::undefined_method(location, "String", "upcse")
+            .with_suggestions(vec!["upcase".to_string(), "upcase!".to_string()]);
+        let lsp_diag = convert(&methodray_diag, "", PositionEncoding::Utf16);
+
+        let actions = suggestion_actions(&document_uri(), &lsp_diag);
+        assert_eq!(actions.len(), 2);
+        let CodeActionOrCommand::CodeAction(action) = &actions[0] else {
+            panic!("expected a code action");
+        };
+        assert_eq!(action.title, "Replace with `upcase`");
+        assert_eq!(action.kind, Some(CodeActionKind::QUICKFIX));
+        assert_eq!(action.is_preferred, Some(true));
+        let edits = &action.edit.as_ref().unwrap().changes.as_ref().unwrap()[&document_uri()];
+        assert_eq!(edits[0].range, lsp_diag.range);
+        assert_eq!(edits[0].new_text, "upcase");
+
+        // Diagnostics of other tools are ignored
+        let other = Diagnostic {
+            source: Some("rubocop".to_string()),
+            ..lsp_diag
+        };
+        assert!(suggestion_actions(&document_uri(), &other).is_empty());
+    }
+
+    #[test]
+    fn test_utf16_columns() {
+        use std::path::PathBuf;
+
+        // "😀" is one character but two UTF-16 code units
+        let text = "s = \"😀\".upcse\n";
+        let methodray_diag = MethodRayDiagnostic::undefined_method(
+            Location {
+                file: PathBuf::from("test.rb"),
+                line: 1,
+                column: 9,
+                end_line: 1,
+                end_column: 14,
+            },
+            "String",
+            "upcse",
+        );
+
+        let utf16 = convert(&methodray_diag, text, PositionEncoding::Utf16);
+        assert_eq!(utf16.range.start, Position::new(0, 9));
+        assert_eq!(utf16.range.end, Position::new(0, 14));
+
+        let utf8 = convert(&methodray_diag, text, PositionEncoding::Utf8);
+        assert_eq!(utf8.range.start, Position::new(0, 11));
+        assert_eq!(utf8.range.end, Position::new(0, 16));
+    }
+
+    #[test]
+    fn test_multiline_range() {
+        use std::path::PathBuf;
+
+        let methodray_diag = MethodRayDiagnostic::unreachable_code(Location {
+            file: PathBuf::from("test.rb"),
+            line: 2,
+            column: 3,
+            end_line: 4,
+            end_column: 6,
+        });
+
+        let lsp_diag = convert(&methodray_diag, "", PositionEncoding::Utf16);
+        assert_eq!(lsp_diag.range.start, Position::new(1, 2));
+        assert_eq!(lsp_diag.range.end, Position::new(3, 5));
+    }
+
+    #[test]
+    fn test_negotiate_position_encoding() {
+        assert_eq!(negotiate_position_encoding(None), PositionEncoding::Utf16);
+        assert_eq!(
+            negotiate_position_encoding(Some(&[
+                PositionEncodingKind::new("utf-7"),
+                PositionEncodingKind::UTF8,
+                PositionEncodingKind::UTF16,
+            ])),
+            PositionEncoding::Utf8
+        );
+        assert_eq!(
+            position_encoding_kind(PositionEncoding::Utf32),
+            PositionEncodingKind::UTF32
+        );
+    }
 }
@@ -1,81 +1,341 @@
 use anyhow::Context;
 use std::collections::HashMap;
-use tokio::sync::RwLock;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::sync::{Mutex, RwLock};
 use tower_lsp::jsonrpc::Result;
 use tower_lsp::lsp_types::*;
 use tower_lsp::{Client, LanguageServer, LspService, Server};
 
-use super::diagnostics::to_lsp_diagnostic;
+use super::diagnostics::{
+    negotiate_position_encoding, position_encoding_kind, suggestion_actions, to_lsp_diagnostic,
+    DocumentPositions,
+};
 use crate::checker::FileChecker;
+use crate::config::{Config, CONFIG_FILE_NAMES};
+use crate::source_map::{LineIndex, PositionEncoding};
 
-pub struct MethodRayServer {
+/// Time without further changes before an edited document is checked
+const CHECK_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Buffer of an open document
+#[derive(Debug, Clone)]
+struct Document {
+    text: String,
+    /// Version from the client; increases with every change
+    version: i32,
+}
+
+/// Open documents by URI
+#[derive(Debug, Default)]
+struct Documents {
+    documents: HashMap<Url, Document>,
+}
+
+impl Documents {
+    /// Store the buffer of a document
+    ///
+    /// Notifications may be handled out of order, so a version older than
+    /// the stored one is ignored. Returns whether the buffer was stored.
+    fn update(&mut self, uri: Url, text: String, version: i32) -> bool {
+        if self
+            .documents
+            .get(&uri)
+            .is_some_and(|document| document.version > version)
+        {
+            return false;
+        }
+        self.documents.insert(uri, Document { text, version });
+        true
+    }
+
+    fn get(&self, uri: &Url) -> Option<&Document> {
+        self.documents.get(uri)
+    }
+
+    fn remove(&mut self, uri: &Url) {
+        self.documents.remove(uri);
+    }
+
+    fn uris(&self) -> Vec<Url> {
+        self.documents.keys().cloned().collect()
+    }
+
+    /// Whether `version` is still the latest version of an open document
+    fn is_current(&self, uri: &Url, version: i32) -> bool {
+        self.documents
+            .get(uri)
+            .is_some_and(|document| document.version == version)
+    }
+}
+
+/// Checker of a workspace with its configuration
+struct WorkspaceChecker {
+    checker: FileChecker,
+    config: Config,
+}
+
+/// State shared by the server and its scheduled checks
+struct ServerState {
     client: Client,
-    documents: RwLock<HashMap<Url, String>>,
+    documents: RwLock<Documents>,
+    /// Unit of LSP columns, agreed on in `initialize`
+    position_encoding: OnceLock<PositionEncoding>,
+    /// Workspace folder from `initialize`, where the configuration is discovered
+    workspace_root: OnceLock<PathBuf>,
+    /// Whether the client lets the server watch the configuration files
+    watch_config_files: OnceLock<bool>,
+    /// RBS cache and configuration by project root, loaded by the first
+    /// check and dropped when a configuration file changes
+    checkers: Mutex<HashMap<PathBuf, Arc<WorkspaceChecker>>>,
 }
 
-impl MethodRayServer {
-    pub fn new(client: Client) -> Self {
-        Self {
-            client,
-            documents: RwLock::new(HashMap::new()),
+impl ServerState {
+    fn position_encoding(&self) -> PositionEncoding {
+        self.position_encoding
+            .get()
+            .copied()
+            .unwrap_or(PositionEncoding::Utf16)
+    }
+
+    /// Directory the configuration of a file is discovered from
+    ///
+    /// The workspace folder when the client opened one; otherwise the
+    /// directory of the file's configuration (or of the file without one).
+    fn project_root(&self, file_path: &Path) -> PathBuf {
+        if let Some(root) = self.workspace_root.get() {
+            return root.clone();
+        }
+        let dir = file_path.parent().unwrap_or(file_path);
+        Config::find(dir)
+            .and_then(|config_path| config_path.parent().map(Path::to_path_buf))
+            .unwrap_or_else(|| dir.to_path_buf())
+    }
+
+    /// Checker of the project of a file, created once and reused by every check
+    ///
+    /// A failure (e.g., no RBS cache yet) is retried by the next check.
+    async fn checker(&self, file_path: &Path) -> anyhow::Result<Arc<WorkspaceChecker>> {
+        let root = self.project_root(file_path);
+        // Held while loading, so concurrent checks share one load
+        let mut checkers = self.checkers.lock().await;
+        if let Some(checker) = checkers.get(&root) {
+            return Ok(Arc::clone(checker));
+        }
+
+        let config_dir = root.clone();
+        let checker = tokio::task::spawn_blocking(move || {
+            let config = Config::discover(&config_dir)?;
+            let checker =
+                FileChecker::new(config.clone()).with_context(|| "Failed to create FileChecker")?;
+            Ok::<_, anyhow::Error>(Arc::new(WorkspaceChecker { checker, config }))
+        })
+        .await
+        .with_context(|| "Loading the RBS cache was aborted")??;
+        checkers.insert(root, Arc::clone(&checker));
+        Ok(checker)
+    }
+
+    /// Drop the loaded configurations and RBS caches and re-check the open documents
+    async fn reload(&self) {
+        self.checkers.lock().await.clear();
+        let uris = self.documents.read().await.uris();
+        for uri in uris {
+            self.check_document(uri, None).await;
         }
     }
 
-    async fn check_document(&self, uri: Url) {
-        let documents = self.documents.read().await;
-
-        if let Some(source) = documents.get(&uri) {
-            match self.run_type_check(&uri, source).await {
-                Ok(diagnostics) => {
-                    self.client
-                        .publish_diagnostics(uri.clone(), diagnostics, None)
-                        .await;
-                }
-                Err(e) => {
-                    self.client
-                        .log_message(MessageType::ERROR, format!("Type check failed: {}", e))
-                        .await;
-                }
+    /// Check the buffer of an open document and publish its diagnostics
+    ///
+    /// With `version`, nothing is checked unless that is still the latest
+    /// version. Results are dropped if the document changed or was closed
+    /// while being checked.
+    async fn check_document(&self, uri: Url, version: Option<i32>) {
+        let document = {
+            let documents = self.documents.read().await;
+            match (documents.get(&uri), version) {
+                (Some(document), Some(version)) if document.version != version => return,
+                (Some(document), _) => document.clone(),
+                (None, _) => return,
+            }
+        };
+
+        let result = self.run_type_check(uri.clone(), document.text).await;
+
+        if !self
+            .documents
+            .read()
+            .await
+            .is_current(&uri, document.version)
+        {
+            return;
+        }
+        match result {
+            Ok(diagnostics) => {
+                self.client
+                    .publish_diagnostics(uri, diagnostics, Some(document.version))
+                    .await;
+            }
+            Err(e) => {
+                self.client
+                    .log_message(MessageType::ERROR, format!("Type check failed: {}", e))
+                    .await;
             }
         }
     }
 
-    async fn run_type_check(&self, uri: &Url, source: &str) -> anyhow::Result<Vec<Diagnostic>> {
+    /// Type check a buffer on the blocking thread pool
+    async fn run_type_check(&self, uri: Url, source: String) -> anyhow::Result<Vec<Diagnostic>> {
         // Convert URI to file path
         let file_path = uri
             .to_file_path()
             .map_err(|_| anyhow::anyhow!("Invalid file URI: {}", uri))?;
 
-        // Create a temporary file for checking
-        let temp_dir = tempfile::tempdir()?;
-        let temp_file = temp_dir.path().join("temp.rb");
-        std::fs::write(&temp_file, source)?;
+        let checker = self.checker(&file_path).await?;
+        let encoding = self.position_encoding();
+        tokio::task::spawn_blocking(move || {
+            check_source(&checker, &uri, &source, &file_path, encoding)
+        })
+        .await
+        .with_context(|| "Type check was aborted")?
+    }
+}
+
+fn check_source(
+    workspace: &WorkspaceChecker,
+    uri: &Url,
+    source: &str,
+    file_path: &Path,
+    encoding: PositionEncoding,
+) -> anyhow::Result<Vec<Diagnostic>> {
+    // Files excluded by the configuration are not checked
+    if workspace.config.is_excluded(file_path) {
+        return Ok(vec![]);
+    }
+
+    // Run type check on the buffer using FileChecker
+    let methodray_diagnostics = workspace
+        .checker
+        .check_source(source, file_path)
+        .with_context(|| format!("Failed to check file: {}", file_path.display()))?;
+
+    // Convert to LSP diagnostics, with columns in the negotiated encoding
+    let line_index = LineIndex::new(source);
+    let document = DocumentPositions {
+        uri,
+        line_index: &line_index,
+        encoding,
+    };
+    let lsp_diagnostics = methodray_diagnostics
+        .iter()
+        .map(|diag| to_lsp_diagnostic(diag, &document))
+        .collect();
 
-        // Run type check using FileChecker
-        let checker = FileChecker::new().with_context(|| "Failed to create FileChecker")?;
+    Ok(lsp_diagnostics)
+}
 
-        let methodray_diagnostics = checker
-            .check_file(&temp_file)
-            .with_context(|| format!("Failed to check file: {}", file_path.display()))?;
+/// Whether a URI names a configuration file (`.methodray.yml`, `methodray.toml`)
+fn is_config_file(uri: &Url) -> bool {
+    uri.path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .is_some_and(|name| CONFIG_FILE_NAMES.contains(&name))
+}
 
-        // Convert to LSP diagnostics
-        let lsp_diagnostics = methodray_diagnostics
+/// Registration of `workspace/didChangeWatchedFiles` for the configuration files
+fn config_files_registration() -> Registration {
+    let options = DidChangeWatchedFilesRegistrationOptions {
+        watchers: CONFIG_FILE_NAMES
             .iter()
-            .map(to_lsp_diagnostic)
-            .collect();
+            .map(|name| FileSystemWatcher {
+                glob_pattern: GlobPattern::String(format!("**/{}", name)),
+                kind: None,
+            })
+            .collect(),
+    };
+    Registration {
+        id: "methodray-config-files".to_string(),
+        method: "workspace/didChangeWatchedFiles".to_string(),
+        register_options: serde_json::to_value(options).ok(),
+    }
+}
+
+pub struct MethodRayServer {
+    state: Arc<ServerState>,
+}
+
+impl MethodRayServer {
+    pub fn new(client: Client) -> Self {
+        Self {
+            state: Arc::new(ServerState {
+                client,
+                documents: RwLock::new(Documents::default()),
+                position_encoding: OnceLock::new(),
+                workspace_root: OnceLock::new(),
+                watch_config_files: OnceLock::new(),
+                checkers: Mutex::new(HashMap::new()),
+            }),
+        }
+    }
 
-        Ok(lsp_diagnostics)
+    /// Check a document once it has gone `CHECK_DEBOUNCE` without changes
+    fn schedule_check(&self, uri: Url, version: i32) {
+        let state = Arc::clone(&self.state);
+        tokio::spawn(async move {
+            tokio::time::sleep(CHECK_DEBOUNCE).await;
+            state.check_document(uri, Some(version)).await;
+        });
     }
 }
 
+/// Root folder of the workspace opened by the client
+fn workspace_root(params: &InitializeParams) -> Option<PathBuf> {
+    let folder_uri = params
+        .workspace_folders
+        .as_ref()
+        .and_then(|folders| folders.first())
+        .map(|folder| &folder.uri);
+    #[allow(deprecated)]
+    let uri = folder_uri.or(params.root_uri.as_ref())?;
+    uri.to_file_path().ok()
+}
+
 #[tower_lsp::async_trait]
 impl LanguageServer for MethodRayServer {
-    async fn initialize(&self, _: InitializeParams) -> Result<InitializeResult> {
+    async fn initialize(&self, params: InitializeParams) -> Result<InitializeResult> {
+        let client_encodings = params
+            .capabilities
+            .general
+            .as_ref()
+            .and_then(|general| general.position_encodings.as_deref());
+        let encoding = *self
+            .state
+            .position_encoding
+            .get_or_init(|| negotiate_position_encoding(client_encodings));
+        if let Some(root) = workspace_root(&params) {
+            let _ = self.state.workspace_root.set(root);
+        }
+        let watch_config_files = params
+            .capabilities
+            .workspace
+            .as_ref()
+            .and_then(|workspace| workspace.did_change_watched_files.as_ref())
+            .and_then(|watched_files| watched_files.dynamic_registration)
+            .unwrap_or(false);
+        let _ = self.state.watch_config_files.set(watch_config_files);
+
         Ok(InitializeResult {
             capabilities: ServerCapabilities {
+                position_encoding: Some(position_encoding_kind(encoding)),
                 text_document_sync: Some(TextDocumentSyncCapability::Kind(
                     TextDocumentSyncKind::FULL,
                 )),
+                code_action_provider: Some(CodeActionProviderCapability::Options(
+                    CodeActionOptions {
+                        code_action_kinds: Some(vec![CodeActionKind::QUICKFIX]),
+                        ..Default::default()
+                    },
+                )),
                 ..Default::default()
             },
             ..Default::default()
@@ -83,46 +343,108 @@ impl LanguageServer for MethodRayServer {
     }
 
     async fn initialized(&self, _: InitializedParams) {
-        self.client
+        if self.state.watch_config_files.get() == Some(&true) {
+            if let Err(e) = self
+                .state
+                .client
+                .register_capability(vec![config_files_registration()])
+                .await
+            {
+                self.state
+                    .client
+                    .log_message(
+                        MessageType::WARNING,
+                        format!("Failed to watch the configuration files: {}", e),
+                    )
+                    .await;
+            }
+        }
+        self.state
+            .client
             .log_message(MessageType::INFO, "MethodRay LSP server initialized")
             .await;
     }
 
     async fn did_open(&self, params: DidOpenTextDocumentParams) {
-        let uri = params.text_document.uri;
-        let text = params.text_document.text;
+        let document = params.text_document;
 
-        self.documents.write().await.insert(uri.clone(), text);
-        self.check_document(uri).await;
+        self.state.documents.write().await.update(
+            document.uri.clone(),
+            document.text,
+            document.version,
+        );
+        self.state.check_document(document.uri, None).await;
     }
 
     async fn did_change(&self, params: DidChangeTextDocumentParams) {
         let uri = params.text_document.uri;
+        let version = params.text_document.version;
 
-        if let Some(change) = params.content_changes.first() {
-            let text = change.text.clone();
-            self.documents.write().await.insert(uri.clone(), text);
-            // Note: In production, we'd want debouncing here
-            // self.check_document(uri).await;
+        // Full sync: the last change holds the whole buffer
+        if let Some(change) = params.content_changes.into_iter().last() {
+            let updated =
+                self.state
+                    .documents
+                    .write()
+                    .await
+                    .update(uri.clone(), change.text, version);
+            if updated {
+                self.schedule_check(uri, version);
+            }
         }
     }
 
     async fn did_save(&self, params: DidSaveTextDocumentParams) {
-        self.check_document(params.text_document.uri).await;
+        // Also covers clients that can't watch files for the server
+        if is_config_file(&params.text_document.uri) {
+            self.state.reload().await;
+            return;
+        }
+        self.state
+            .check_document(params.text_document.uri, None)
+            .await;
+    }
+
+    async fn did_change_watched_files(&self, params: DidChangeWatchedFilesParams) {
+        if params
+            .changes
+            .iter()
+            .any(|change| is_config_file(&change.uri))
+        {
+            self.state.reload().await;
+        }
     }
 
     async fn did_close(&self, params: DidCloseTextDocumentParams) {
-        self.documents
+        // Pending and in-flight checks of the document are dropped
+        self.state
+            .documents
             .write()
             .await
             .remove(&params.text_document.uri);
 
         // Clear diagnostics
-        self.client
+        self.state
+            .client
             .publish_diagnostics(params.text_document.uri, vec![], None)
             .await;
     }
 
+    async fn code_action(&self, params: CodeActionParams) -> Result<Option<CodeActionResponse>> {
+        // "Did you mean?" quick fixes of the diagnostics in the range
+        let actions: Vec<CodeActionOrCommand> = params
+            .context
+            .diagnostics
+            .iter()
+            .flat_map(|diagnostic| suggestion_actions(&params.text_document.uri, diagnostic))
+            .collect();
+        Ok(if actions.is_empty() {
+            None
+        } else {
+            Some(actions)
+        })
+    }
+
     async fn shutdown(&self) -> Result<()> {
         Ok(())
     }
@@ -136,3 +458,56 @@ pub async fn run_server() {
 
     Server::new(stdin, stdout, socket).serve(service).await;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uri() -> Url {
+        Url::parse("file:///project/test.rb").unwrap()
+    }
+
+    #[test]
+    fn test_is_config_file() {
+        assert!(is_config_file(
+            &Url::parse("file:///project/.methodray.yml").unwrap()
+        ));
+        assert!(is_config_file(
+            &Url::parse("file:///project/api/methodray.toml").unwrap()
+        ));
+        assert!(!is_config_file(&uri()));
+    }
+
+    #[test]
+    fn test_documents_update() {
+        let mut documents = Documents::default();
+        assert!(documents.update(uri(), "x = 1".to_string(), 1));
+        assert!(documents.update(uri(), "x = 2".to_string(), 2));
+        assert_eq!(documents.get(&uri()).unwrap().text, "x = 2");
+    }
+
+    #[test]
+    fn test_documents_update_ignores_older_version() {
+        let mut documents = Documents::default();
+        documents.update(uri(), "x = 2".to_string(), 2);
+        assert!(!documents.update(uri(), "x = 1".to_string(), 1));
+        assert_eq!(documents.get(&uri()).unwrap().text, "x = 2");
+        assert_eq!(documents.get(&uri()).unwrap().version, 2);
+    }
+
+    #[test]
+    fn test_documents_is_current() {
+        let mut documents = Documents::default();
+        documents.update(uri(), "x = 1".to_string(), 1);
+        assert!(documents.is_current(&uri(), 1));
+
+        // A newer version makes checks of the old one stale
+        documents.update(uri(), "x = 2".to_string(), 2);
+        assert!(!documents.is_current(&uri(), 1));
+        assert!(documents.is_current(&uri(), 2));
+
+        // Closing the document makes every check stale
+        documents.remove(&uri());
+        assert!(!documents.is_current(&uri(), 2));
+    }
+}
@@ -7,6 +7,7 @@ mod analyzer;
 mod cache;
 mod checker;
 mod cli;
+mod config;
 mod diagnostics;
 mod env;
 mod graph;
@@ -16,23 +17,54 @@ mod source_map;
 mod types;
 
 use cli::{commands, Cli, Commands};
+use config::Config;
+use diagnostics::FormatOptions;
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::Check { file, verbose } => {
-            if let Some(file_path) = file {
-                let success = commands::check_single_file(&file_path, verbose)?;
-                if !success {
-                    std::process::exit(1);
-                }
+        Commands::Check {
+            file,
+            verbose,
+            method_missing,
+            format,
+            config,
+            baseline,
+            update_baseline,
+            color,
+            context,
+        } => {
+            let config = Config::load_or_discover(config.as_deref())?;
+            let options = commands::CheckOptions {
+                verbose,
+                format: format.or(config.format).unwrap_or_default(),
+                open_class_policies: method_missing,
+                config,
+                baseline,
+                update_baseline,
+                text: FormatOptions {
+                    color: color.enabled(),
+                    context,
+                },
+            };
+            let success = if let Some(file_path) = file {
+                commands::check_single_file(&file_path, &options)?
             } else {
-                commands::check_project(verbose)?;
+                commands::check_project(&options)?
+            };
+            if !success {
+                std::process::exit(1);
             }
         }
-        Commands::Watch { file } => {
-            commands::watch_file(&file)?;
+        Commands::Watch { file, config } => {
+            commands::watch_file(&file, Config::load_or_discover(config.as_deref())?)?;
+        }
+        Commands::Rules => {
+            commands::print_rules();
+        }
+        Commands::Explain { code } => {
+            commands::explain_rule(&code)?;
         }
         Commands::Version => {
             commands::print_version();
@@ -1,3 +1,4 @@
+use crate::source_map::{LineIndex, SourceLocation};
 use anyhow::{Context, Result};
 use ruby_prism::{parse, ParseResult};
 use std::fs;
@@ -6,6 +7,7 @@ use std::path::Path;
 /// Parse Ruby source code and return ruby-prism AST
 ///
 /// Note: Uses Box::leak internally to ensure 'static lifetime
+#[allow(dead_code)]
 pub fn parse_ruby_file(file_path: &Path) -> Result<ParseResult<'static>> {
     let source = fs::read_to_string(file_path)
         .with_context(|| format!("Failed to read file: {}", file_path.display()))?;
@@ -14,11 +16,9 @@ pub fn parse_ruby_file(file_path: &Path) -> Result<ParseResult<'static>> {
 }
 
 /// Parse Ruby source code string
+#[allow(dead_code)]
 pub fn parse_ruby_source(source: &str, file_name: String) -> Result<ParseResult<'static>> {
-    // ruby-prism accepts &[u8]
-    // Use Box::leak to ensure 'static lifetime (memory leak is acceptable for analysis tools)
-    let source_bytes: &'static [u8] = Box::leak(source.as_bytes().to_vec().into_boxed_slice());
-    let parse_result = parse(source_bytes);
+    let parse_result = parse_ruby_source_tolerant(source);
 
     // Check parse errors
     let error_messages: Vec<String> = parse_result
@@ -43,6 +43,44 @@ pub fn parse_ruby_source(source: &str, file_name: String) -> Result<ParseResult<
     Ok(parse_result)
 }
 
+/// Parse Ruby source code string, keeping syntax errors
+///
+/// prism recovers from syntax errors, so the returned AST can still be
+/// analyzed; `syntax_problems` lists the errors and warnings.
+pub fn parse_ruby_source_tolerant(source: &str) -> ParseResult<'static> {
+    // ruby-prism accepts &[u8]
+    // Use Box::leak to ensure 'static lifetime (memory leak is acceptable for analysis tools)
+    let source_bytes: &'static [u8] = Box::leak(source.as_bytes().to_vec().into_boxed_slice());
+    parse(source_bytes)
+}
+
+/// A syntax error or warning reported by prism
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxProblem {
+    pub location: SourceLocation,
+    pub message: String,
+    /// Error (`true`) or warning (`false`)
+    pub is_error: bool,
+}
+
+/// Syntax errors, then warnings, of a parse result
+pub fn syntax_problems(parse_result: &ParseResult, source: &str) -> Vec<SyntaxProblem> {
+    let errors = parse_result.errors().map(|e| (e, true));
+    let warnings = parse_result.warnings().map(|w| (w, false));
+    let line_index = LineIndex::new(source);
+    errors
+        .chain(warnings)
+        .map(|(problem, is_error)| SyntaxProblem {
+            location: SourceLocation::from_prism_location_with_index(
+                &problem.location(),
+                &line_index,
+            ),
+            message: problem.message().to_string(),
+            is_error,
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -86,6 +124,33 @@ end"#;
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_parse_tolerant_keeps_recovered_ast() {
+        let source = "x = 1\ny = )\nz = 2\n";
+        let parse_result = parse_ruby_source_tolerant(source);
+        assert!(parse_result.node().as_program_node().is_some());
+
+        let problems = syntax_problems(&parse_result, source);
+        assert!(!problems.is_empty());
+        assert!(problems[0].is_error);
+        assert!(problems[0].location.line >= 2);
+    }
+
+    #[test]
+    fn test_syntax_warnings() {
+        let source = "def label
+  unused = 1
+  2
+end
+";
+        let parse_result = parse_ruby_source_tolerant(source);
+        let problems = syntax_problems(&parse_result, source);
+        assert!(problems.iter().all(|problem| !problem.is_error));
+        assert!(problems
+            .iter()
+            .any(|problem| problem.message.contains("unused")));
+    }
+
     #[test]
     fn test_parse_method_call() {
         let source = r#"user = User.new
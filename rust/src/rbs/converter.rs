@@ -5,6 +5,11 @@ pub struct RbsTypeConverter;
 
 impl RbsTypeConverter {
     pub fn parse(rbs_type: &str) -> Type {
+        // Handle tuple types: [K, V]
+        if let Some(elements) = Self::tuple_elements(rbs_type) {
+            return Type::tuple(elements.iter().map(|s| Self::parse(s)).collect());
+        }
+
         // Handle union types
         if rbs_type.contains(" | ") {
             let parts: Vec<&str> = rbs_type.split(" | ").collect();
@@ -15,6 +20,37 @@ impl RbsTypeConverter {
         Self::parse_single(rbs_type)
     }
 
+    /// Split a tuple type `[A, B]` into its element type strings
+    ///
+    /// Returns None if the type is not a single bracketed tuple (e.g. `[A] | B`).
+    fn tuple_elements(rbs_type: &str) -> Option<Vec<&str>> {
+        let inner = rbs_type.trim().strip_prefix('[')?.strip_suffix(']')?;
+
+        let mut elements = Vec::new();
+        let mut depth = 0;
+        let mut start = 0;
+        for (i, c) in inner.char_indices() {
+            match c {
+                '[' | '(' => depth += 1,
+                ']' | ')' => {
+                    if depth == 0 {
+                        // Closing bracket of the tuple before the end: not one tuple
+                        return None;
+                    }
+                    depth -= 1;
+                }
+                ',' if depth == 0 => {
+                    elements.push(inner[start..i].trim());
+                    start = i + 1;
+                }
+                _ => {}
+            }
+        }
+        elements.push(inner[start..].trim());
+
+        Some(elements)
+    }
+
     fn parse_single(rbs_type: &str) -> Type {
         let type_name = rbs_type.trim_start_matches("::");
 
@@ -75,6 +111,21 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_tuple_types() {
+        assert_eq!(RbsTypeConverter::parse("[K, V]").show(), "[K, V]");
+        assert_eq!(
+            RbsTypeConverter::parse("[::String, [Integer, nil]]").show(),
+            "[String, [Integer, nil]]"
+        );
+
+        // Union of tuples is not a single tuple
+        assert!(matches!(
+            RbsTypeConverter::parse("[K, V] | [V, K]"),
+            Type::Union(_)
+        ));
+    }
+
     #[test]
     fn test_parse_union_types() {
         match RbsTypeConverter::parse("String | Integer") {
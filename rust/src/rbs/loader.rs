@@ -3,7 +3,8 @@ use crate::rbs::converter::RbsTypeConverter;
 use crate::rbs::error::RbsError;
 use crate::types::Type;
 use magnus::value::ReprValue;
-use magnus::{Error, RArray, RHash, Ruby, TryConvert, Value};
+use magnus::{Error, Module, RArray, RClass, RHash, RModule, Ruby, TryConvert, Value};
+use std::path::PathBuf;
 
 /// Method information loaded from RBS
 #[derive(Debug, Clone)]
@@ -35,8 +36,12 @@ impl<'a> RbsLoader<'a> {
         Ok(Self { ruby })
     }
 
-    /// Load all method definitions from RBS
-    pub fn load_methods(&self) -> Result<Vec<RbsMethodInfo>, RbsError> {
+    /// Load all method definitions from RBS, including classes declared in
+    /// the signature paths
+    pub fn load_methods(
+        &self,
+        signature_paths: &[PathBuf],
+    ) -> Result<Vec<RbsMethodInfo>, RbsError> {
         // Load method_loader.rb
         let rb_path = concat!(env!("CARGO_MANIFEST_DIR"), "/src/rbs/method_loader.rb");
         let load_code = format!("require '{}'", rb_path);
@@ -46,9 +51,17 @@ impl<'a> RbsLoader<'a> {
             .map_err(|e| RbsError::LoadError(format!("Failed to load method_loader.rb: {}", e)))?;
 
         // Instantiate Rbs::MethodLoader class and call method
+        // (paths are passed as Ruby strings, never evaluated as code)
+        let paths = self.ruby.ary_new();
+        for path in signature_paths {
+            paths
+                .push(self.ruby.str_new(&path.display().to_string()))
+                .map_err(|e| RbsError::LoadError(format!("Invalid signature paths: {}", e)))?;
+        }
         let results: Value = self
-            .ruby
-            .eval("Rbs::MethodLoader.new.load_methods")
+            .method_loader_class()
+            .and_then(|class| class.funcall::<_, _, Value>("new", (paths,)))
+            .and_then(|loader| loader.funcall("load_methods", ()))
             .map_err(|e| {
                 RbsError::LoadError(format!(
                     "Failed to call Rbs::MethodLoader#load_methods: {}",
@@ -59,6 +72,14 @@ impl<'a> RbsLoader<'a> {
         self.parse_results(results)
     }
 
+    /// Get the `Rbs::MethodLoader` class defined by method_loader.rb
+    fn method_loader_class(&self) -> Result<RClass, Error> {
+        self.ruby
+            .class_object()
+            .const_get::<_, RModule>("Rbs")?
+            .const_get("MethodLoader")
+    }
+
     /// Convert Ruby array results to Vec of RbsMethodInfo structs
     fn parse_results(&self, results: Value) -> Result<Vec<RbsMethodInfo>, RbsError> {
         let mut method_infos = Vec::new();
@@ -137,8 +158,13 @@ impl<'a> RbsLoader<'a> {
 }
 
 /// Helper function to register RBS methods to GlobalEnv
-/// Uses cache to avoid slow Ruby FFI calls
-pub fn register_rbs_methods(genv: &mut GlobalEnv, ruby: &Ruby) -> Result<usize, Error> {
+/// Uses cache to avoid slow Ruby FFI calls; the cache is rebuilt when it
+/// was built with other signature paths
+pub fn register_rbs_methods(
+    genv: &mut GlobalEnv,
+    ruby: &Ruby,
+    signature_paths: &[PathBuf],
+) -> Result<usize, Error> {
     use crate::cache::RbsCache;
 
     let methodray_version = env!("CARGO_PKG_VERSION");
@@ -151,29 +177,29 @@ pub fn register_rbs_methods(genv: &mut GlobalEnv, ruby: &Ruby) -> Result<usize,
         String::try_convert(rbs_version_value).unwrap_or_else(|_| "unknown".to_string());
 
     // Try to load from cache
-    let methods = if let Ok(cache) = RbsCache::load() {
-        if cache.is_valid(methodray_version, &rbs_version) {
+    let methods = if let Ok(cache) = RbsCache::load(signature_paths) {
+        if cache.is_valid(methodray_version, &rbs_version)
+            && cache.has_signature_paths(signature_paths)
+        {
             cache.to_method_infos()
         } else {
             eprintln!("Cache invalid, reloading from RBS...");
-            let methods = load_and_cache_rbs_methods(ruby, methodray_version, &rbs_version)?;
+            let methods =
+                load_and_cache_rbs_methods(ruby, methodray_version, &rbs_version, signature_paths)?;
             methods
         }
     } else {
         eprintln!("No cache found, loading from RBS...");
-        load_and_cache_rbs_methods(ruby, methodray_version, &rbs_version)?
+        load_and_cache_rbs_methods(ruby, methodray_version, &rbs_version, signature_paths)?
     };
 
     let count = methods.len();
     for method_info in methods {
         let receiver_type = Type::instance(&method_info.receiver_class);
         // Convert block param type strings to Type enums
-        let block_param_types = method_info.block_param_types.map(|types| {
-            types
-                .iter()
-                .map(|s| RbsTypeConverter::parse(s))
-                .collect()
-        });
+        let block_param_types = method_info
+            .block_param_types
+            .map(|types| types.iter().map(|s| RbsTypeConverter::parse(s)).collect());
         genv.register_builtin_method_with_block(
             receiver_type,
             &method_info.method_name,
@@ -190,17 +216,19 @@ fn load_and_cache_rbs_methods(
     ruby: &Ruby,
     version: &str,
     rbs_version: &str,
+    signature_paths: &[PathBuf],
 ) -> Result<Vec<RbsMethodInfo>, Error> {
     use crate::cache::RbsCache;
 
     let loader = RbsLoader::new(ruby)?;
-    let methods = loader.load_methods()?;
+    let methods = loader.load_methods(signature_paths)?;
 
     // Save to cache
     let cache = RbsCache::from_method_infos(
         methods.clone(),
         version.to_string(),
         rbs_version.to_string(),
+        signature_paths.to_vec(),
     );
 
     if let Err(e) = cache.save() {
@@ -1,5 +1,6 @@
 # frozen_string_literal: true
 
+require 'pathname'
 require 'rbs'
 
 # TODO: use ruby-rbs crate when available
@@ -10,26 +11,34 @@ module Rbs
       String Integer Float Array Hash Symbol
       TrueClass FalseClass NilClass
       Range Regexp Struct Enumerable
+      Exception StandardError RuntimeError ArgumentError TypeError
+      NameError NoMethodError KeyError IndexError ZeroDivisionError IOError
     ].freeze
 
-    def initialize
+    DYNAMIC_DISPATCH_METHODS = %i[method_missing respond_to_missing?].freeze
+    BASE_CLASSES = %w[::BasicObject ::Object ::Kernel].freeze
+
+    # signature_paths: extra RBS directories (e.g. the project's sig/)
+    def initialize(signature_paths = [])
+      @signature_paths = signature_paths.map { |path| Pathname(path).expand_path }
       loader = ::RBS::EnvironmentLoader.new
-      env = ::RBS::Environment.from_loader(loader).resolve_type_names
-      @builder = ::RBS::DefinitionBuilder.new(env: env)
+      @signature_paths.each { |path| loader.add(path: path) }
+      @env = ::RBS::Environment.from_loader(loader).resolve_type_names
+      @builder = ::RBS::DefinitionBuilder.new(env: @env)
     end
 
     def load_methods
       results = []
 
-      TARGET_CLASSES.each do |class_name|
-        type_name = ::RBS::TypeName.new(
-          name: class_name.to_sym,
-          namespace: ::RBS::Namespace.root
-        )
-
+      target_type_names.each do |class_name, type_name|
         definition = @builder.build_instance(type_name)
 
         definition.methods.each do |method_name, method_def|
+          # Every object inherits method_missing and respond_to_missing?;
+          # only classes overriding them answer undefined methods
+          next if DYNAMIC_DISPATCH_METHODS.include?(method_name) &&
+                  BASE_CLASSES.include?(method_def.implemented_in&.to_s)
+
           # Find a method_type with block if available, otherwise use first
           method_type_with_block = method_def.method_types.find(&:block)
           method_type = method_type_with_block || method_def.method_types.first
@@ -54,6 +63,32 @@ module Rbs
 
     private
 
+    # Built-in target classes and classes declared in the signature paths
+    def target_type_names
+      builtins = TARGET_CLASSES.map do |class_name|
+        [class_name, ::RBS::TypeName.new(name: class_name.to_sym, namespace: ::RBS::Namespace.root)]
+      end
+      builtins + signature_type_names.map { |type_name| [type_name.to_s.delete_prefix('::'), type_name] }
+    end
+
+    def signature_type_names
+      return [] if @signature_paths.empty?
+
+      @env.class_decls.filter_map do |type_name, entry|
+        type_name if entry.decls.any? { |d| in_signature_paths?(d.decl.location&.buffer&.name) }
+      end
+    rescue StandardError => e
+      warn "Skipped signature paths: #{e.message}" if ENV['DEBUG']
+      []
+    end
+
+    def in_signature_paths?(file)
+      return false unless file
+
+      path = Pathname(file.to_s).expand_path
+      @signature_paths.any? { |sig| path.to_s.start_with?("#{sig}/") }
+    end
+
     # Extract block parameter types from method_type
     # Returns nil if no block, or array of type strings
     def extract_block_param_types(method_type)
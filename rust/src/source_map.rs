@@ -1,72 +1,151 @@
-/// Convert byte offset to (line, column) - 1-indexed
-fn offset_to_line_column(source: &str, offset: usize) -> (usize, usize) {
-    let mut line = 1;
-    let mut column = 1;
-    let mut current_offset = 0;
-
-    for ch in source.chars() {
-        if current_offset >= offset {
-            break;
+/// Unit of column numbers
+///
+/// Prism reports byte offsets, MethodRay locations count characters
+/// (UTF-32), and LSP clients count UTF-16 code units unless another
+/// encoding is negotiated.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PositionEncoding {
+    /// Bytes
+    Utf8,
+    /// UTF-16 code units (the LSP default)
+    Utf16,
+    /// Characters
+    #[default]
+    Utf32,
+}
+
+#[allow(dead_code)]
+impl PositionEncoding {
+    /// Width of a character in this encoding
+    fn width(self, ch: char) -> usize {
+        match self {
+            PositionEncoding::Utf8 => ch.len_utf8(),
+            PositionEncoding::Utf16 => ch.len_utf16(),
+            PositionEncoding::Utf32 => 1,
         }
+    }
+}
+
+/// Line start offsets of a source, for converting between byte offsets,
+/// character columns and other encodings
+#[derive(Debug, Clone)]
+pub struct LineIndex<'a> {
+    source: &'a str,
+    /// Byte offset of the start of each line
+    line_starts: Vec<usize>,
+}
 
-        if ch == '\n' {
-            line += 1;
-            column = 1;
-        } else {
-            column += 1;
+#[allow(dead_code)]
+impl<'a> LineIndex<'a> {
+    pub fn new(source: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(offset, _)| offset + 1))
+            .collect();
+        Self {
+            source,
+            line_starts,
         }
+    }
 
-        current_offset += ch.len_utf8();
+    /// Text of a 1-indexed line, without its line break
+    pub fn line_text(&self, line: usize) -> Option<&'a str> {
+        let start = *self.line_starts.get(line.checked_sub(1)?)?;
+        let end = self
+            .line_starts
+            .get(line)
+            .map_or(self.source.len(), |next| next - 1);
+        let text = &self.source[start..end.max(start)];
+        Some(text.strip_suffix('\r').unwrap_or(text))
     }
 
-    (line, column)
+    /// 1-indexed line and character column of a byte offset
+    ///
+    /// Offsets past the end are clamped; offsets inside a character
+    /// point at that character.
+    pub fn line_column(&self, offset: usize) -> (usize, usize) {
+        let offset = offset.min(self.source.len());
+        let line_index = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let line_start = self.line_starts[line_index];
+        let column = self.source[line_start..]
+            .char_indices()
+            .take_while(|(index, _)| line_start + index < offset)
+            .count();
+        (line_index + 1, column + 1)
+    }
+
+    /// 0-indexed column in `encoding` units of a 1-indexed character column
+    ///
+    /// Columns past the end of the line count as one unit per character.
+    pub fn encode_column(&self, line: usize, column: usize, encoding: PositionEncoding) -> usize {
+        let chars = column.saturating_sub(1);
+        let text = self.line_text(line).unwrap_or("");
+        let mut units = 0;
+        let mut counted = 0;
+        for ch in text.chars().take(chars) {
+            units += encoding.width(ch);
+            counted += 1;
+        }
+        units + (chars - counted)
+    }
 }
 
 /// Source code location information
+///
+/// A range from (`line`, `column`) to (`end_line`, `end_column`), with
+/// 1-indexed lines and character columns. The end is exclusive: it is the
+/// position just after the last character.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct SourceLocation {
     pub line: usize,
     pub column: usize,
-    pub length: usize,
+    pub end_line: usize,
+    pub end_column: usize,
 }
 
 #[allow(dead_code)]
 impl SourceLocation {
-    pub fn new(line: usize, column: usize, length: usize) -> Self {
+    pub fn new(line: usize, column: usize, end_line: usize, end_column: usize) -> Self {
         Self {
             line,
             column,
-            length,
+            end_line,
+            end_column,
         }
     }
 
     /// Create from ruby-prism Location and source code
-    /// Calculates line/column from byte offset
+    ///
+    /// Builds a line index for the source; use `from_prism_location_with_index`
+    /// when converting many locations of the same source.
     pub fn from_prism_location_with_source(location: &ruby_prism::Location, source: &str) -> Self {
-        let start_offset = location.start_offset();
-        let length = location.end_offset() - start_offset;
-
-        // Calculate line and column from byte offset
-        let (line, column) = offset_to_line_column(source, start_offset);
+        Self::from_prism_location_with_index(location, &LineIndex::new(source))
+    }
 
+    /// Create from ruby-prism Location and the line index of its source
+    pub fn from_prism_location_with_index(
+        location: &ruby_prism::Location,
+        line_index: &LineIndex,
+    ) -> Self {
+        let (line, column) = line_index.line_column(location.start_offset());
+        let (end_line, end_column) = line_index.line_column(location.end_offset());
         Self {
             line,
             column,
-            length,
+            end_line,
+            end_column,
         }
     }
 
     /// Create from ruby-prism Location (without source - uses approximation)
     pub fn from_prism_location(location: &ruby_prism::Location) -> Self {
-        let start_offset = location.start_offset();
-        let length = location.end_offset() - start_offset;
-
         // Without source, we can't calculate exact line/column
-        // Use offset as column for now
+        // Use offsets as columns for now
         Self {
             line: 1, // Placeholder
-            column: start_offset + 1,
-            length,
+            column: location.start_offset() + 1,
+            end_line: 1,
+            end_column: location.end_offset() + 1,
         }
     }
 }
@@ -77,26 +156,69 @@ mod tests {
 
     #[test]
     fn test_source_location_creation() {
-        let loc = SourceLocation::new(10, 5, 6);
+        let loc = SourceLocation::new(10, 5, 10, 11);
         assert_eq!(loc.line, 10);
         assert_eq!(loc.column, 5);
-        assert_eq!(loc.length, 6);
+        assert_eq!(loc.end_line, 10);
+        assert_eq!(loc.end_column, 11);
     }
 
     #[test]
-    fn test_offset_to_line_column() {
-        let source = "x = 1\ny = x.upcase";
+    fn test_line_column() {
+        let line_index = LineIndex::new("x = 1\ny = x.upcase");
         // "x = 1\n" is 6 bytes (0-5)
         // "y = x.upcase" starts at offset 6
-        // "y = x." is 6 bytes, so ".upcase" starts at offset 12
 
         // Test offset 0 (start of line 1)
-        assert_eq!(offset_to_line_column(source, 0), (1, 1));
+        assert_eq!(line_index.line_column(0), (1, 1));
 
         // Test offset 6 (start of line 2, after newline)
-        assert_eq!(offset_to_line_column(source, 6), (2, 1));
+        assert_eq!(line_index.line_column(6), (2, 1));
 
         // Test offset 10 (the 'x' in 'x.upcase')
-        assert_eq!(offset_to_line_column(source, 10), (2, 5));
+        assert_eq!(line_index.line_column(10), (2, 5));
+
+        // End of source
+        assert_eq!(line_index.line_column(18), (2, 13));
+    }
+
+    #[test]
+    fn test_line_column_counts_characters() {
+        // "名前" is 6 bytes
+        let line_index = LineIndex::new("名前 = 1\n名前.upcase");
+        assert_eq!(line_index.line_column(6), (1, 3));
+        assert_eq!(line_index.line_column(17), (2, 3));
+    }
+
+    #[test]
+    fn test_encode_column() {
+        // "😀" is 4 bytes, 2 UTF-16 code units and 1 character
+        let line_index = LineIndex::new("a\ns = \"😀\".upcse\n");
+        let column = 9; // `upcse`
+
+        assert_eq!(
+            line_index.encode_column(2, column, PositionEncoding::Utf32),
+            8
+        );
+        assert_eq!(
+            line_index.encode_column(2, column, PositionEncoding::Utf16),
+            9
+        );
+        assert_eq!(
+            line_index.encode_column(2, column, PositionEncoding::Utf8),
+            11
+        );
+        // Past the end of the line
+        assert_eq!(line_index.encode_column(1, 4, PositionEncoding::Utf16), 3);
+    }
+
+    #[test]
+    fn test_line_text() {
+        let line_index = LineIndex::new("a\r\nbc\n");
+        assert_eq!(line_index.line_text(1), Some("a"));
+        assert_eq!(line_index.line_text(2), Some("bc"));
+        assert_eq!(line_index.line_text(3), Some(""));
+        assert_eq!(line_index.line_text(4), None);
+        assert_eq!(line_index.line_text(0), None);
     }
 }
@@ -1,3 +1,4 @@
+use crate::graph::VertexId;
 use smallvec::SmallVec;
 
 /// Qualified name for classes and modules (e.g., "Api::V1::User")
@@ -140,6 +141,13 @@ pub enum Type {
     Nil,
     /// Union type: sum of multiple types
     Union(Vec<Type>),
+    /// Tuple type: fixed-size array with per-position types, e.g. [String, Integer]
+    Tuple(Vec<Type>),
+    /// Proc type: lambda, proc or block, with its parameter and return vertices
+    Proc {
+        params: Vec<VertexId>,
+        ret: VertexId,
+    },
     /// Bottom type: no type information
     Bot,
 }
@@ -159,6 +167,11 @@ impl Type {
                 let names: Vec<_> = types.iter().map(|t| t.show()).collect();
                 names.join(" | ")
             }
+            Type::Tuple(types) => {
+                let names: Vec<_> = types.iter().map(|t| t.show()).collect();
+                format!("[{}]", names.join(", "))
+            }
+            Type::Proc { .. } => "Proc".to_string(),
             Type::Bot => "untyped".to_string(),
         }
     }
@@ -262,6 +275,16 @@ impl Type {
         }
     }
 
+    /// Create a tuple type: [first, second, ...]
+    pub fn tuple(element_types: Vec<Type>) -> Self {
+        Type::Tuple(element_types)
+    }
+
+    /// Create a Proc type from its parameter and return vertices
+    pub fn proc_of(params: Vec<VertexId>, ret: VertexId) -> Self {
+        Type::Proc { params, ret }
+    }
+
     /// Create a generic Hash type: Hash[key_type, value_type]
     pub fn hash_of(key_type: Type, value_type: Type) -> Self {
         Type::Generic {
@@ -356,12 +379,26 @@ mod tests {
         assert_eq!(user_type.simple_name(), Some("User"));
     }
 
+    #[test]
+    fn test_proc_type_show() {
+        let ty = Type::proc_of(vec![VertexId(1)], VertexId(2));
+        assert_eq!(ty.show(), "Proc");
+        assert_eq!(ty.base_class_name(), None);
+    }
+
     #[test]
     fn test_type_union() {
         let union = Type::Union(vec![Type::string(), Type::integer()]);
         assert_eq!(union.show(), "String | Integer");
     }
 
+    #[test]
+    fn test_tuple_type_show() {
+        let pair = Type::tuple(vec![Type::string(), Type::integer()]);
+        assert_eq!(pair.show(), "[String, Integer]");
+        assert_eq!(pair.base_class_name(), None);
+    }
+
     #[test]
     fn test_generic_type_show() {
         let array_int = Type::array_of(Type::integer());